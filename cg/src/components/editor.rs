@@ -7,19 +7,23 @@ use crate::util::{
     types::monaco_editor,
 };
 
+/// Code editor, `on_run` is called when Ctrl+Enter (Cmd+Enter on macOS) is pressed while the editor is focused.
 #[component]
-pub fn Editor<TOnChange>(
+pub fn Editor<TOnChange, TOnRun>(
     cx: Scope,
     #[prop(optional)] language: &'static str,
     #[prop(optional)] theme: &'static str,
+    #[prop(optional)] read_only: bool,
     #[prop(optional)] set_editor: Option<WriteSignal<Option<monaco_editor::StandaloneCodeEditor>>>,
     #[prop(default = None)] on_change: Option<TOnChange>,
+    #[prop(default = None)] on_run: Option<TOnRun>,
 ) -> impl IntoView
 where
     TOnChange: Fn() + 'static,
+    TOnRun: Fn() + 'static,
 {
     let root = view! { cx, <div style="width: 100%; height: 100%" /> };
-    let options = js!({"automaticLayout": true, "language": language, "theme": theme});
+    let options = js!({"automaticLayout": true, "language": language, "theme": theme, "readOnly": read_only});
     let code_editor = monaco_editor::editor().create(&root, &options);
     if let Some(set_editor) = set_editor {
         set_editor.set(Some(code_editor.clone()));
@@ -28,5 +32,32 @@ where
         let model = code_editor.get_model();
         model.on_did_change_content(&js_fn!(<dyn Fn()> on_change));
     }
+    if let Some(on_run) = on_run {
+        let keybinding = monaco_editor::KEY_MOD_CTRL_CMD | monaco_editor::KEY_CODE_ENTER;
+        code_editor.add_command(keybinding, &js_fn!(<dyn Fn()> on_run));
+    }
+    root
+}
+
+/// Side by side editor highlighting the changes from `original` to `modified`, only `modified` is editable.
+#[component]
+pub fn DiffEditor(
+    cx: Scope,
+    #[prop(into)] original: String,
+    #[prop(into)] modified: String,
+    #[prop(optional)] language: &'static str,
+    #[prop(optional)] theme: &'static str,
+    #[prop(optional)] read_only: bool,
+    #[prop(optional)] set_editor: Option<WriteSignal<Option<monaco_editor::StandaloneDiffEditor>>>,
+) -> impl IntoView {
+    let root = view! { cx, <div style="width: 100%; height: 100%" /> };
+    let options = js!({"automaticLayout": true, "theme": theme, "readOnly": read_only, "originalEditable": false});
+    let diff_editor = monaco_editor::editor().create_diff_editor(&root, &options);
+    let original = monaco_editor::editor().create_model(&original, language);
+    let modified = monaco_editor::editor().create_model(&modified, language);
+    diff_editor.set_model(&js!({"original": original, "modified": modified}));
+    if let Some(set_editor) = set_editor {
+        set_editor.set(Some(diff_editor.clone()));
+    }
     root
 }
@@ -0,0 +1,28 @@
+// Checkerboard with a parameter panel, `cells` is the number of cells along each axis.
+
+//@ param cells 2 32 8
+//@ param tint color
+struct Params {
+    cells: f32,
+    tint: vec3f,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+
+struct Varyings {
+    @builtin(position) position: vec4f,
+    @location(0) uv: vec2f,
+}
+
+@vertex
+fn vertex_main(@builtin(vertex_index) i: u32) -> Varyings {
+    let uv = vec2f(f32((i << 1u) & 2u), f32(i & 2u));
+    return Varyings(vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0), uv);
+}
+
+@fragment
+fn fragment_main(in: Varyings) -> @location(0) vec4f {
+    let cell = floor(in.uv * params.cells);
+    let odd = (cell.x + cell.y) % 2.0;
+    return vec4f(params.tint * mix(0.2, 1.0, odd), 1.0);
+}
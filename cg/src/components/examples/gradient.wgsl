@@ -0,0 +1,17 @@
+// Screen space gradient, red increases to the right and green downwards.
+
+struct Varyings {
+    @builtin(position) position: vec4f,
+    @location(0) uv: vec2f,
+}
+
+@vertex
+fn vertex_main(@builtin(vertex_index) i: u32) -> Varyings {
+    let uv = vec2f(f32((i << 1u) & 2u), f32(i & 2u));
+    return Varyings(vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0), uv);
+}
+
+@fragment
+fn fragment_main(in: Varyings) -> @location(0) vec4f {
+    return vec4f(in.uv, 0.5, 1.0);
+}
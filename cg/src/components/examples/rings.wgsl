@@ -0,0 +1,28 @@
+// Concentric rings around the canvas center, `edge` is the width of the blur between rings.
+
+//@ param frequency 1 40 12
+//@ param edge 0.01 0.5 0.1
+struct Params {
+    frequency: f32,
+    edge: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+
+struct Varyings {
+    @builtin(position) position: vec4f,
+    @location(0) uv: vec2f,
+}
+
+@vertex
+fn vertex_main(@builtin(vertex_index) i: u32) -> Varyings {
+    let uv = vec2f(f32((i << 1u) & 2u), f32(i & 2u));
+    return Varyings(vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0), uv);
+}
+
+@fragment
+fn fragment_main(in: Varyings) -> @location(0) vec4f {
+    let wave = 0.5 + 0.5 * cos(distance(in.uv, vec2f(0.5)) * params.frequency * 6.2831853);
+    let ring = smoothstep(0.5 - params.edge, 0.5 + params.edge, wave);
+    return vec4f(vec3f(ring), 1.0);
+}
@@ -0,0 +1,73 @@
+#![allow(unused)]
+use leptos::*;
+
+/// Built-in example shader, `source` is drawn with `webgpu::compile_fullscreen`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Example {
+    pub name: &'static str,
+    pub source: &'static str,
+}
+
+pub const EXAMPLES: [Example; 3] = [
+    Example {
+        name: "gradient",
+        source: include_str!("examples/gradient.wgsl"),
+    },
+    Example {
+        name: "checker",
+        source: include_str!("examples/checker.wgsl"),
+    },
+    Example {
+        name: "rings",
+        source: include_str!("examples/rings.wgsl"),
+    },
+];
+
+/// Return the example called `name`, if any.
+pub fn example(name: &str) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|example| example.name == name)
+}
+
+/// Dropdown listing the built-in `EXAMPLES`, `on_select` is called with the chosen example.
+#[component]
+pub fn Gallery<F>(cx: Scope, on_select: F) -> impl IntoView
+where
+    F: Fn(&'static Example) + 'static,
+{
+    let on_change = move |event| {
+        if let Some(example) = example(&event_target_value(&event)) {
+            on_select(example);
+        }
+    };
+    let options = EXAMPLES.map(|example| view! { cx, <option value=example.name>{example.name}</option> });
+    view! { cx,
+        <select title="examples" on:change=on_change>
+            <option value="" selected disabled>"examples"</option>
+            {options.to_vec()}
+        </select>
+    }
+}
+
+mod test {
+    use crate::components::gallery::{example, EXAMPLES};
+    use crate::web::wgsl;
+
+    #[test]
+    fn examples() {
+        for example in EXAMPLES {
+            assert!(example.source.contains("fn vertex_main") && example.source.contains("fn fragment_main"));
+            assert!(
+                wgsl::params(example.source).is_some(),
+                "{} params are malformed",
+                example.name
+            );
+        }
+        assert_eq!(
+            example("checker").map(|e| e.source),
+            Some(include_str!("examples/checker.wgsl"))
+        );
+        let checker = wgsl::params(EXAMPLES[1].source).unwrap();
+        assert_eq!(wgsl::params_size(&checker), 32);
+        assert_eq!(example("missing"), None);
+    }
+}
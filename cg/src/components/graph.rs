@@ -0,0 +1,43 @@
+use leptos::{html, *};
+use std::collections::VecDeque;
+use wasm_bindgen::prelude::*;
+
+/// Number of frames displayed by the graph.
+const FRAMES: usize = 100;
+
+/// Frame time budget in milliseconds, frames above the budget are drawn in red.
+const BUDGET: f64 = 16.6;
+
+#[component]
+pub fn FrameGraph(cx: Scope, #[prop(into)] frame_time: MaybeSignal<f64>) -> impl IntoView {
+    let canvas_ref = create_node_ref::<html::Canvas>(cx);
+    let frames = store_value(cx, VecDeque::<f64>::with_capacity(FRAMES));
+    let root = view! { cx, <canvas class="components_graph" _ref=canvas_ref width=FRAMES height=50 /> };
+    let canvas = canvas_ref.get().unwrap();
+    let context = canvas
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .unchecked_into::<web_sys::CanvasRenderingContext2d>();
+    create_effect(cx, move |_| {
+        let time = frame_time.get();
+        frames.update_value(|frames| {
+            if frames.len() == FRAMES {
+                frames.pop_front();
+            }
+            frames.push_back(time);
+        });
+        // a single bar per frame keeps the overlay cost negligible compared to the frame itself
+        let (width, height) = (canvas.width() as f64, canvas.height() as f64);
+        let scale = height / (BUDGET * 2.0);
+        context.clear_rect(0.0, 0.0, width, height);
+        frames.with_value(|frames| {
+            for (i, time) in frames.iter().enumerate() {
+                let bar = (time * scale).min(height);
+                context.set_fill_style(&JsValue::from(if *time > BUDGET { "red" } else { "lime" }));
+                context.fill_rect(i as f64, height - bar, 1.0, bar);
+            }
+        });
+    });
+    root
+}
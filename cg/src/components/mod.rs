@@ -1,3 +1,12 @@
 pub mod editor;
+pub mod gallery;
+pub mod graph;
+pub mod params;
+pub mod picker;
 pub mod player;
+pub mod recorder;
+pub mod share;
+pub mod splitter;
+pub mod theme;
+pub mod toast;
 pub mod view;
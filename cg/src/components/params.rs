@@ -0,0 +1,50 @@
+use crate::math::{color, mx};
+use crate::web::wgsl::{Param, ParamKind};
+use leptos::*;
+
+/// Controls for shader `params`, the inputs edit the uniform struct `values` in place, see `wgsl::params`.
+///
+/// Nothing is rendered without parameters.
+#[component]
+pub fn ParamPanel(cx: Scope, #[prop(into)] params: Signal<Vec<Param>>, values: RwSignal<Vec<f32>>) -> impl IntoView {
+    let control = move |param: Param| {
+        let i = param.offset / 4;
+        let value = move |j: usize| values.with(|values| values.get(i + j).copied().unwrap_or_default());
+        let input = match param.kind {
+            ParamKind::F32 { min, max, .. } => {
+                let on_input = move |event| {
+                    if let Ok(value) = event_target_value(&event).parse::<f32>() {
+                        values.update(|values| {
+                            if let Some(v) = values.get_mut(i) {
+                                *v = value.clamp(min, max);
+                            }
+                        });
+                    }
+                };
+                let step = (max - min) / 100.0;
+                view! { cx,
+                    <input type="range" min=min max=max step=step prop:value=move || value(0).to_string() on:input=on_input />
+                }
+            }
+            ParamKind::Color => {
+                let on_input = move |event| {
+                    if let Some(color) = color::from_hex(&event_target_value(&event), 1.0) {
+                        values.update(|values| {
+                            if let Some(rgb) = values.get_mut(i..i + 3) {
+                                rgb.copy_from_slice(&[color[0], color[1], color[2]]);
+                            }
+                        });
+                    }
+                };
+                let hex = move || color::to_hex(&mx!(VR[value(0), value(1), value(2), 1.0]));
+                view! { cx, <input type="color" prop:value=hex on:input=on_input /> }
+            }
+        };
+        view! { cx, <label>{param.name}{input}</label> }
+    };
+    view! { cx,
+        <Show when=move || params.with(|params| !params.is_empty()) fallback=|_| ()>
+            <span class="components_params">{move || params().into_iter().map(control).collect::<Vec<_>>()}</span>
+        </Show>
+    }
+}
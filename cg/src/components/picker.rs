@@ -0,0 +1,24 @@
+use crate::math::{color, VR};
+use leptos::*;
+
+/// Color picker for linear `rgba` colors, the color input edits the sRGB encoded `rgb` and the slider edits alpha.
+///
+/// Invalid input values are ignored and the previous color is kept.
+#[component]
+pub fn ColorPicker(cx: Scope, color: RwSignal<VR<f32, 4>>) -> impl IntoView {
+    let on_color = move |event| {
+        if let Some(value) = color::from_hex(&event_target_value(&event), color.get_untracked()[3]) {
+            color.set(value);
+        }
+    };
+    let on_alpha = move |event| match event_target_value(&event).parse::<f32>() {
+        Ok(alpha) if (0.0..=1.0).contains(&alpha) => color.update(|color| color[3] = alpha),
+        _ => (),
+    };
+    view! { cx,
+        <span class="components_picker">
+            <input type="color" prop:value=move || color::to_hex(&color()) on:input=on_color />
+            <input type="range" min="0" max="1" step="0.01" prop:value=move || color()[3].to_string() on:input=on_alpha />
+        </span>
+    }
+}
@@ -1,36 +1,426 @@
 use crate::components::editor::Editor;
-use crate::components::view::View;
-use crate::math;
-use crate::util::types::monaco_editor;
+use crate::components::gallery::{Example, Gallery};
+use crate::components::params::ParamPanel;
+use crate::components::picker::ColorPicker;
+use crate::components::recorder::Recorder;
+use crate::components::share::{SharedScene, SharedState};
+use crate::components::splitter::{self, Splitter};
+use crate::components::theme::Theme;
+use crate::components::toast::{Notifier, Severity, Toasts};
+use crate::components::view::{PlayerButton, View};
+use crate::math::sampling::Rng;
+use crate::math::{color, mx, MX, V3};
+use crate::util::{
+    js::{self, js_fn},
+    types::monaco_editor,
+};
 use crate::web;
 use leptos::html;
+use leptos::leptos_dom::helpers::debounce;
 use leptos::*;
-use wasm_bindgen::prelude::*;
+use std::time::Duration;
+use web_sys::GpuUncapturedErrorEvent;
 
 #[component]
 pub fn Player(cx: Scope) -> impl IntoView {
     let rw_playing = create_rw_signal(cx, true);
     let (canvas, set_canvas) = create_signal::<Option<html::HtmlElement<html::Canvas>>>(cx, None);
     let (editor, set_editor) = create_signal::<Option<monaco_editor::StandaloneCodeEditor>>(cx, None);
+    let (webgpu, set_webgpu) = create_signal::<Option<web::webgpu::WebGpu>>(cx, None);
+    let rw_theme = create_rw_signal(cx, Theme::load());
+    let shared = SharedState::load();
+    let clear = shared.as_ref().map_or(rw_theme.get_untracked().clear(), |s| s.clear);
+    let rw_clear = create_rw_signal(cx, clear);
+    let rw_source = create_rw_signal(cx, String::new());
+    let shared_source = store_value(cx, shared.map(|s| s.source));
+    // a scene link is rendered in chunks of rows once the device is ready, and drawn on top of the canvas when done
+    let shared_scene = SharedScene::load();
+    let scene_link = shared_scene.is_some();
+    let shared_scene = store_value(cx, shared_scene);
+    let rw_scene_image = create_rw_signal(cx, None::<Vec<V3<f32>>>);
+    let theme_icon = Signal::derive(cx, move || rw_theme().icon());
+    let notifier = Notifier::new(cx);
+    let rw_compiles = create_rw_signal(cx, 0usize);
+    let rw_brightness = create_rw_signal(cx, 1.0);
+    // the demos of the renderer features are drawn over the shader output, the playground only shows the shader
+    let rw_demos = create_rw_signal(cx, false);
+    let rw_pipeline = create_rw_signal::<Option<web_sys::GpuRenderPipeline>>(cx, None);
+    let rw_params = create_rw_signal(cx, Vec::new());
+    let rw_values = create_rw_signal(cx, Vec::new());
+    let rw_stats = create_rw_signal(cx, web::webgpu::FrameStats::default());
+    // nothing is drawn while paused
+    let stats = Signal::derive(cx, move || if rw_playing() { rw_stats() } else { Default::default() });
+    let rw_split = create_rw_signal(cx, splitter::load_ratio(0.6));
+    let rw_resolution = create_rw_signal(cx, (0, 0));
+    let rw_time = create_rw_signal(cx, 0.0);
+    let rw_frame_time = create_rw_signal(cx, 0.0);
+    let render_loop = store_value(cx, None::<web::webgpu::RenderLoop>);
+    let rw_max_fps = create_rw_signal(cx, None::<f64>);
+    let latest_run = store_value(cx, 0usize);
+    // a quad with the 4 corners shared by 2 triangles
+    let vertices = [
+        mx!(VR[-0.8, -0.8]),
+        mx!(VR[0.8, -0.8]),
+        mx!(VR[0.8, 0.8]),
+        mx!(VR[-0.8, 0.8]),
+    ];
+    let indices: [u16; 6] = [0, 1, 2, 0, 2, 3];
 
     create_resource(
         cx,
         move || canvas,
-        async move |canvas| {
+        move |canvas| async move {
             if let None = canvas.get() {
                 return;
             }
             let canvas = canvas.get().unwrap();
-            let webgpu = web::webgpu::WebGpu::new(Some(canvas)).await.unwrap();
+            let webgpu = match web::webgpu::WebGpu::new(Some(canvas), web_sys::GpuCanvasAlphaMode::Opaque).await {
+                Ok(webgpu) => webgpu,
+                Err(err) => {
+                    notifier.push(Severity::Error, format!("WebGPU initialization failed: {err}"));
+                    return;
+                }
+            };
+            // validation errors, including shader compilation errors, are reported asynchronously by the device
+            let on_error = js_fn!(<dyn Fn(GpuUncapturedErrorEvent)> move |event: GpuUncapturedErrorEvent| {
+                notifier.push(Severity::Error, event.error().message());
+            });
+            webgpu.device.set_onuncapturederror(Some(&on_error));
             webgpu.print();
-            web::webgpu::draw(&webgpu, math::mx!(VR[0.0, 0.3, 0.3, 1.0]));
+            if !webgpu.capabilities().fits_texture(8192, 8192) {
+                notifier.push(
+                    Severity::Warning,
+                    "8192x8192 textures are not supported, high resolution renders are limited",
+                );
+            }
+            set_webgpu(Some(webgpu));
+            if let Some(shared) = shared_scene.try_update_value(|shared| shared.take()).flatten() {
+                notifier.push(Severity::Info, "rendering the scene from the link");
+                spawn_local(async move {
+                    let mut rng = Rng::new(1);
+                    let mut image = Vec::with_capacity((SCENE_WIDTH * SCENE_HEIGHT) as usize);
+                    for start in (0..SCENE_HEIGHT).step_by(SCENE_CHUNK_ROWS as usize) {
+                        // frames and input are handled between chunks
+                        _ = js::await_value(js::timeout(0)).await;
+                        let rows = start..(start + SCENE_CHUNK_ROWS).min(SCENE_HEIGHT);
+                        let chunk = shared
+                            .scene
+                            .render_rows(SCENE_WIDTH, SCENE_HEIGHT, rows, SCENE_SAMPLES, &mut rng);
+                        image.extend(chunk);
+                    }
+                    rw_scene_image.set(Some(image));
+                    notifier.push(Severity::Info, "rendered the scene from the link");
+                });
+            }
         },
     );
 
+    // the occlusion demo is drawn once each time the demos are enabled, frames do not wait for the query results
+    create_effect(cx, move |_| {
+        if !rw_demos() {
+            return;
+        }
+        let Some(queries) = webgpu.with(|webgpu| webgpu.as_ref().map(web::webgpu::draw_occlusion_demo)) else {
+            return;
+        };
+        spawn_local(async move {
+            if let Some([behind, in_front]) = queries.results().await.as_deref() {
+                let text = format!("occlusion demo, small triangle visible behind: {behind}, in front: {in_front}");
+                notifier.push(Severity::Info, text);
+            }
+        });
+    });
+
+    // the editor is created with the initial theme, later changes are applied to all monaco editors
+    create_effect(cx, move |previous: Option<Theme>| {
+        let theme = rw_theme();
+        if previous.is_some_and(|previous| previous != theme) {
+            theme.store();
+            monaco_editor::editor().set_theme(theme.monaco());
+            rw_clear.set(theme.clear());
+        }
+        theme
+    });
+
+    // the shared source is restored once the editor is created
+    create_effect(cx, move |_| {
+        editor.with(|editor| {
+            if let Some(editor) = editor {
+                if let Some(source) = shared_source.try_update_value(|source| source.take()).flatten() {
+                    editor.get_model().set_value(&source);
+                }
+            }
+        })
+    });
+
+    create_effect(cx, move |previous: Option<()>| {
+        let split = rw_split();
+        if previous.is_some() {
+            splitter::store_ratio(split);
+        }
+    });
+
+    // frames are requested while playing, each frame sets the time and redraws
+    create_effect(cx, move |_| {
+        render_loop.set_value(None);
+        let max_fps = rw_max_fps();
+        if !rw_playing() || webgpu.with(Option::is_none) {
+            return;
+        }
+        // the frame time restarts after pausing or changing the frame rate cap
+        let mut previous = None;
+        render_loop.set_value(Some(web::webgpu::start_loop(max_fps, move |time| {
+            rw_frame_time.set(previous.map_or(0.0, |previous| time - previous));
+            rw_time.set(time);
+            previous = Some(time);
+        })));
+    });
+
+    let store = debounce(cx, Duration::from_millis(500), |state: SharedState| state.store());
+    let store = store_value(cx, store);
+    create_effect(cx, move |previous: Option<()>| {
+        let state = SharedState {
+            source: rw_source(),
+            clear: rw_clear(),
+        };
+        // the scene link is kept in the URL, shader changes are not shared
+        if previous.is_some() && !scene_link {
+            store.update_value(|store| store(state));
+        }
+    });
+
+    create_effect(cx, move |_| {
+        let clear = rw_clear();
+        let brightness = rw_brightness();
+        let values = rw_values();
+        let demos = rw_demos();
+        rw_compiles.track();
+        // the quads spin slowly while playing
+        let time = rw_time();
+        let transform = MX::from_euler_xyz(V3(0.0, 0.0, (time * 0.0005) as f32));
+        // the canvas is sized in device pixels to stay sharp on high density displays
+        let ratio = window().device_pixel_ratio();
+        let (width, height) = rw_resolution();
+        webgpu.with(|webgpu| {
+            if let Some(webgpu) = webgpu {
+                webgpu.resize(
+                    (width as f64 * ratio).round() as u32,
+                    (height as f64 * ratio).round() as u32,
+                );
+                webgpu.set_constant("brightness", brightness);
+                webgpu.begin_frame();
+                let quad = web::webgpu::Geometry::Indexed(&vertices, indices[..].into());
+                web::webgpu::draw(webgpu, clear, quad, &transform);
+                rw_pipeline.with_untracked(|pipeline| {
+                    if let Some(pipeline) = pipeline {
+                        web::webgpu::draw_fullscreen(webgpu, pipeline, &values);
+                    }
+                });
+                if demos {
+                    web::webgpu::draw_axes(webgpu);
+                    web::webgpu::draw_bundle_demo(webgpu);
+                    web::webgpu::draw_constants_demo(webgpu);
+                    web::webgpu::draw_compute_demo(webgpu);
+                    // the sampler demo alternates between pixelated and smooth sampling every 2 seconds
+                    let filter = match (time / 2000.0) as u64 % 2 {
+                        0 => web_sys::GpuFilterMode::Nearest,
+                        _ => web_sys::GpuFilterMode::Linear,
+                    };
+                    web::webgpu::draw_sampler_demo(webgpu, filter);
+                    web::webgpu::draw_srgb_demo(webgpu);
+                    web::webgpu::draw_mipmap_demo(webgpu);
+                }
+                rw_scene_image.with(|image| {
+                    if let Some(image) = image {
+                        webgpu.blit(&scene_texture(webgpu, image), None);
+                    }
+                });
+                rw_stats.set(webgpu.frame_stats());
+            }
+        });
+    });
+
+    // right-clicking the canvas copies the hex color of the pixel under the cursor
+    create_effect(cx, move |_| {
+        let Some(canvas) = canvas() else {
+            return;
+        };
+        let target = canvas.clone();
+        _ = canvas.on(ev::contextmenu, move |event: web_sys::MouseEvent| {
+            event.prevent_default();
+            let canvas = target.clone();
+            // css pixels are scaled to canvas pixels
+            let x = event.offset_x() * canvas.width() as i32 / canvas.client_width().max(1);
+            let y = event.offset_y() * canvas.height() as i32 / canvas.client_height().max(1);
+            spawn_local(async move {
+                match copy_pixel(&canvas, x, y).await {
+                    Ok(hex) => notifier.push(Severity::Info, format!("copied {hex}")),
+                    Err(err) => notifier.push(Severity::Error, format!("failed to copy pixel color: {err:?}")),
+                }
+            });
+        });
+    });
+
+    // dropped `.wgsl` files replace the editor content, other files are ignored
+    let on_drop = move |event: web_sys::DragEvent| {
+        event.prevent_default();
+        let Some(files) = event.data_transfer().and_then(|transfer| transfer.files()) else {
+            return;
+        };
+        for file in (0..files.length()).filter_map(|i| files.get(i)) {
+            let name = file.name();
+            if !name.to_lowercase().ends_with(".wgsl") {
+                notifier.push(Severity::Warning, format!("unsupported file type: {name}"));
+                continue;
+            }
+            let reader = web_sys::FileReader::new().unwrap();
+            let result = reader.clone();
+            reader.set_onload(Some(&js_fn!(<dyn Fn()> move || {
+                let Some(source) = result.result().ok().and_then(|source| source.as_string()) else {
+                    notifier.push(Severity::Error, "failed to read the dropped file");
+                    return;
+                };
+                editor.with_untracked(|editor| {
+                    if let Some(editor) = editor {
+                        editor.get_model().set_value(&source);
+                    }
+                });
+            })));
+            reader.read_as_text(&file).unwrap();
+        }
+    };
+
+    let on_max_fps = move |event| {
+        rw_max_fps.set(event_target_value(&event).parse::<f64>().ok());
+    };
+
+    let on_brightness = move |event| {
+        if let Ok(brightness) = event_target_value(&event).parse::<f64>() {
+            rw_brightness.set(brightness);
+        }
+    };
+
+    // the editor source is compiled in the background, the previous pipeline is drawn until it is ready
+    let on_run = move || {
+        let Some(source) = editor.with_untracked(|editor| editor.as_ref().map(|editor| editor.get_model().get_value()))
+        else {
+            return;
+        };
+        let source = String::from(source);
+        // malformed parameter directives hide the panel, the shader is still compiled
+        let params = web::wgsl::params(&source).unwrap_or_default();
+        let Some(compile) = webgpu.with_untracked(|webgpu| {
+            webgpu
+                .as_ref()
+                .map(|webgpu| web::webgpu::compile_fullscreen(webgpu, &source))
+        }) else {
+            return;
+        };
+        latest_run.update_value(|run| *run += 1);
+        let run = latest_run.get_value();
+        spawn_local(async move {
+            let result = compile.await;
+            // a newer run replaced the source while compiling, only its pipeline is used
+            if run != latest_run.get_value() {
+                return;
+            }
+            match result {
+                Ok(pipeline) => {
+                    // values are kept while recompiling shaders with the same parameters
+                    if rw_params.get_untracked() != params {
+                        rw_values.set(web::wgsl::params_defaults(&params));
+                        rw_params.set(params);
+                    }
+                    rw_pipeline.set(Some(pipeline));
+                    rw_compiles.update(|compiles| *compiles += 1);
+                }
+                Err(err) => notifier.push(Severity::Error, err.to_string()),
+            }
+        });
+    };
+
+    // the example replaces the editor source and is compiled right away, the running pipeline is drawn until then
+    let on_example = move |example: &'static Example| {
+        editor.with_untracked(|editor| {
+            if let Some(editor) = editor {
+                editor.get_model().set_value(example.source);
+            }
+        });
+        on_run();
+    };
+
+    // pipelines target the canvas view format, the fullscreen pipeline is compiled again for the new format
+    let on_srgb = move |event| {
+        let srgb = event_target_value(&event) == "srgb";
+        let Some(enabled) = webgpu.with_untracked(|webgpu| webgpu.as_ref().map(|webgpu| webgpu.set_srgb(srgb))) else {
+            return;
+        };
+        if srgb && !enabled {
+            notifier.push(Severity::Warning, "the canvas format has no sRGB view format");
+        }
+        rw_pipeline.set(None);
+        on_run();
+    };
+
+    // the toasts overlay the page, they are not laid out with the player children
     view! { cx,
-        <div class="components_player">
-            <View rw_playing=rw_playing set_canvas=set_canvas />
-            <Editor language="wgsl" theme="vs-dark" on_change=Some(move ||web_sys::console::log_1(&JsValue::from(&editor.get().unwrap().get_model().get_value()))) set_editor=set_editor />
+        <div class="components_player" class:light=move || rw_theme() == Theme::Light style=move || format!("--split: {}", rw_split()) on:dragover=|event| event.prevent_default() on:drop=on_drop>
+            <View rw_playing=rw_playing set_canvas=set_canvas frame_time=rw_frame_time stats=stats rw_resolution=rw_resolution>
+                <ColorPicker color=rw_clear />
+                <ParamPanel params=rw_params values=rw_values />
+                <input type="range" min="0" max="2" step="0.01" prop:value=move || rw_brightness().to_string() on:input=on_brightness />
+                <Gallery on_select=on_example />
+                <select title="frame rate cap" on:change=on_max_fps>
+                    <option value="unlimited" selected>"unlimited"</option>
+                    <option value="60">"60fps"</option>
+                    <option value="30">"30fps"</option>
+                </select>
+                <select title="canvas color space" on:change=on_srgb>
+                    <option value="linear" selected>"linear"</option>
+                    <option value="srgb">"sRGB"</option>
+                </select>
+                <Recorder canvas=canvas notifier=notifier />
+                <PlayerButton icon="layers" on:click=move |_| rw_demos.update(|demos| *demos = !*demos) />
+                <PlayerButton icon=theme_icon on:click=move |_| rw_theme.update(|theme| *theme = theme.toggle()) />
+            </View>
+            <Splitter ratio=rw_split />
+            <Editor language="wgsl" theme=rw_theme.get_untracked().monaco() on_change=Some(move || rw_source.set(editor.get_untracked().unwrap().get_model().get_value().into())) on_run=Some(on_run) set_editor=set_editor />
         </div>
+        <Toasts notifier=notifier />
     }
 }
+
+/// Resolution and samples per pixel of the render of scene links, scenes are traced on the CPU.
+const SCENE_WIDTH: u32 = 320;
+const SCENE_HEIGHT: u32 = 180;
+const SCENE_SAMPLES: u32 = 4;
+/// Rows of the scene link rendered between yields to the browser, the page stays responsive while rendering.
+const SCENE_CHUNK_ROWS: u32 = 8;
+
+/// Return the texture of the `image` of a scene link, created once per canvas view format.
+///
+/// The image has linear colors, they are encoded to sRGB unless the view is sRGB, which encodes them when drawn.
+fn scene_texture(webgpu: &web::webgpu::WebGpu, image: &[V3<f32>]) -> web_sys::GpuTexture {
+    let texture = webgpu.cached("scene link", || {
+        // views with an sRGB variant are linear, drawn values are not encoded
+        let encode = web::webgpu::srgb_format(webgpu.view_format()).is_some();
+        let component = |c: f32| {
+            let c = c.clamp(0.0, 1.0);
+            (if encode { color::linear_to_srgb(c) } else { c } * 255.0).round() as u8
+        };
+        let rgba = image
+            .iter()
+            .flat_map(|&V3(r, g, b)| [component(r), component(g), component(b), 255])
+            .collect::<Vec<_>>();
+        webgpu.create_texture_from_rgba(SCENE_WIDTH, SCENE_HEIGHT, &rgba, false)
+    });
+    (*texture).clone()
+}
+
+/// Copy the hex color of the canvas pixel at `x`, `y` to the clipboard, returning the copied color.
+async fn copy_pixel(canvas: &web_sys::HtmlCanvasElement, x: i32, y: i32) -> Result<String, wasm_bindgen::JsValue> {
+    let hex = color::to_hex(&web::canvas::read_pixel(canvas, x, y).await?);
+    let clipboard = window().navigator().clipboard().ok_or("clipboard not available")?;
+    js::await_value(clipboard.write_text(&hex)).await?;
+    Ok(hex)
+}
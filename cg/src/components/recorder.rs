@@ -0,0 +1,89 @@
+use crate::components::toast::{Notifier, Severity};
+use crate::components::view::PlayerButton;
+use crate::util::js::{js, js_fn};
+use js_sys::Array;
+use leptos::html;
+use leptos::*;
+use std::time::Duration;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, BlobEvent, BlobPropertyBag, MediaRecorder, MediaRecorderOptions, Url};
+
+/// Recorded video type.
+const MIME_TYPE: &str = "video/webm";
+
+/// Delay before the downloaded video url is revoked.
+const REVOKE_DELAY: Duration = Duration::from_secs(1);
+
+/// Button recording `canvas` to a WebM video, the video is downloaded when the recording is stopped.
+///
+/// The canvas stream captures a frame whenever the canvas is drawn, so the video follows the render loop frame rate
+/// and paused players add no frames. The button is hidden if `MediaRecorder` or WebM are not supported.
+#[component]
+pub fn Recorder(
+    cx: Scope,
+    canvas: ReadSignal<Option<html::HtmlElement<html::Canvas>>>,
+    notifier: Notifier,
+) -> impl IntoView {
+    let window = window();
+    let supported = js!(window["MediaRecorder"]).is_function() && MediaRecorder::is_type_supported(MIME_TYPE);
+    let recorder = store_value(cx, None::<MediaRecorder>);
+    let (recording, set_recording) = create_signal(cx, false);
+    let icon = Signal::derive(cx, move || if recording() { "stop-circle" } else { "video" });
+    let on_click = move |_| {
+        if let Some(recorder) = recorder.try_update_value(Option::take).flatten() {
+            _ = recorder.stop();
+            set_recording(false);
+            return;
+        }
+        let Some(canvas) = canvas.get_untracked() else {
+            return;
+        };
+        match start(&canvas) {
+            Ok(started) => {
+                recorder.set_value(Some(started));
+                set_recording(true);
+            }
+            Err(err) => notifier.push(Severity::Error, format!("failed to start recording: {err:?}")),
+        }
+    };
+    view! { cx,
+        <Show when=move || supported fallback=|_| ()>
+            <div class="components_recorder" class:recording=recording>
+                <PlayerButton icon=icon on:click=on_click />
+            </div>
+        </Show>
+    }
+}
+
+/// Start recording `canvas`, the recorded chunks are downloaded as a single video when the recorder stops.
+fn start(canvas: &web_sys::HtmlCanvasElement) -> Result<MediaRecorder, JsValue> {
+    let mut options = MediaRecorderOptions::new();
+    options.mime_type(MIME_TYPE);
+    let recorder =
+        MediaRecorder::new_with_media_stream_and_media_recorder_options(&canvas.capture_stream()?, &options)?;
+    let chunks = Array::new();
+    let data = chunks.clone();
+    recorder.set_ondataavailable(Some(&js_fn!(<dyn Fn(BlobEvent)> move |event: BlobEvent| {
+        if let Some(blob) = event.data() {
+            data.push(&blob);
+        }
+    })));
+    recorder.set_onstop(Some(&js_fn!(<dyn Fn()> move || {
+        let mut options = BlobPropertyBag::new();
+        options.type_(MIME_TYPE);
+        let Ok(video) = Blob::new_with_blob_sequence_and_options(&chunks, &options) else {
+            return;
+        };
+        let Ok(url) = Url::create_object_url_with_blob(&video) else {
+            return;
+        };
+        let anchor = document().create_element("a").unwrap().unchecked_into::<web_sys::HtmlAnchorElement>();
+        anchor.set_href(&url);
+        anchor.set_download("recording.webm");
+        anchor.click();
+        // the download starts after the click is handled, revoking the url right away may cancel it
+        set_timeout(move || _ = Url::revoke_object_url(&url), REVOKE_DELAY);
+    })));
+    recorder.start()?;
+    Ok(recorder)
+}
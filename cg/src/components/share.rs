@@ -0,0 +1,187 @@
+#![allow(unused)]
+use crate::math::{mx, VR};
+use crate::raytrace::scene::Scene;
+use crate::util::{base64, lz77};
+use leptos::leptos_dom::helpers::{location, location_hash};
+use leptos::*;
+
+/// URLs longer than this might be truncated by browsers and link sharing services.
+const MAX_URL_LENGTH: usize = 2000;
+
+/// Prefix of `SharedScene` hashes, it is not in the base64 alphabet, so they are not mistaken for `SharedState`s.
+const SCENE_PREFIX: &str = "scene:";
+
+/// Playground state shared through the URL hash.
+///
+/// The hash is the base64 encoding of the clear color components in the first line followed by the shader source.
+#[derive(Clone, PartialEq)]
+pub struct SharedState {
+    pub source: String,
+    pub clear: VR<f32, 4>,
+}
+
+impl SharedState {
+    pub fn to_hash(&self) -> String {
+        let clear = &self.clear;
+        let text = format!("{} {} {} {}\n{}", clear[0], clear[1], clear[2], clear[3], self.source);
+        base64::encode(text.as_bytes())
+    }
+
+    /// Parse a hash created by `to_hash`, returning `None` if it is malformed.
+    pub fn from_hash(hash: &str) -> Option<SharedState> {
+        let text = String::from_utf8(base64::decode(hash)?).ok()?;
+        let (clear, source) = text.split_once('\n')?;
+        let clear = clear
+            .split(' ')
+            .map(|c| c.parse::<f32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let [r, g, b, a] = clear[..] else {
+            return None;
+        };
+        Some(SharedState {
+            source: source.into(),
+            clear: mx!(VR[r, g, b, a]),
+        })
+    }
+
+    /// Load the state from the page URL hash, if any.
+    pub fn load() -> Option<SharedState> {
+        SharedState::from_hash(&location_hash()?)
+    }
+
+    /// Replace the page URL hash with the state, without adding a history entry.
+    ///
+    /// A warning is logged if the URL is too long to be shared reliably.
+    pub fn store(&self) {
+        replace_hash(&self.to_hash());
+    }
+}
+
+/// Raytraced scene shared through the URL hash.
+///
+/// The hash is `SCENE_PREFIX` followed by the base64 encoding of the `lz77` compressed `Scene::to_text`.
+pub struct SharedScene {
+    pub scene: Scene,
+}
+
+impl SharedScene {
+    pub fn to_hash(&self) -> String {
+        let text = self.scene.to_text();
+        format!("{SCENE_PREFIX}{}", base64::encode(&lz77::compress(text.as_bytes())))
+    }
+
+    /// Parse a hash created by `to_hash`, returning `None` if it is malformed.
+    pub fn from_hash(hash: &str) -> Option<SharedScene> {
+        let data = lz77::decompress(&base64::decode(hash.strip_prefix(SCENE_PREFIX)?)?)?;
+        let scene = Scene::from_text(&String::from_utf8(data).ok()?)?;
+        Some(SharedScene { scene })
+    }
+
+    /// Load the scene from the page URL hash, if any.
+    pub fn load() -> Option<SharedScene> {
+        SharedScene::from_hash(&location_hash()?)
+    }
+
+    /// Replace the page URL hash with the scene, see `SharedState::store`.
+    ///
+    /// Textures and meshes add their values to the text, scenes with large ones warn that the link might not work.
+    pub fn store(&self) {
+        replace_hash(&self.to_hash());
+    }
+}
+
+/// Replace the page URL hash, without adding a history entry, warning if the URL is longer than `MAX_URL_LENGTH`.
+fn replace_hash(hash: &str) {
+    if let Ok(history) = window().history() {
+        let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&format!("#{hash}")));
+    }
+    let length = location().href().map(|href| href.len()).unwrap_or_default();
+    if length > MAX_URL_LENGTH {
+        warn!("shared URL has {length} characters, links longer than {MAX_URL_LENGTH} might not work");
+    }
+}
+
+mod test {
+    use crate::components::share::{SharedScene, SharedState};
+    use crate::math::sampling::Rng;
+    use crate::math::{mx, V3};
+    use crate::raytrace::material::Material;
+    use crate::raytrace::scene::{Camera, Light, Primitive, Scene};
+    use crate::util::{base64, lz77};
+
+    #[test]
+    fn hash() {
+        let state = SharedState {
+            source: "@vertex\nfn main() {}\n// é".into(),
+            clear: mx!(VR[0.0, 0.25, 0.5, 1.0]),
+        };
+        assert!(SharedState::from_hash(&state.to_hash()).unwrap() == state);
+        let crafted = base64::encode(b"1 0 0 0.5\nfn f() {}");
+        let state = SharedState::from_hash(&crafted).unwrap();
+        assert_eq!(state.source, "fn f() {}");
+        assert!(state.clear == mx!(VR[1.0, 0.0, 0.0, 0.5]));
+        for text in ["", "1 0 0\nfn f() {}", "1 0 0 x\n", "1 0 0 0 0\n", "1 0 0 0"] {
+            assert!(SharedState::from_hash(&base64::encode(text.as_bytes())).is_none());
+        }
+        assert!(SharedState::from_hash("not base64!").is_none());
+    }
+
+    #[test]
+    fn scene_hash() {
+        let scene = Scene {
+            camera: Camera {
+                position: mx!(VR[0.0, 0.5, 2.0, 1.0]),
+                direction: mx!(VR[0.0, 0.0, -1.0, 0.0]),
+                near: 0.1,
+                far: 100.0,
+                shutter: 0.0,
+            },
+            primitives: vec![
+                Primitive::Sphere {
+                    center: mx!(VR[0.0, 0.5, -1.0, 1.0]),
+                    velocity: mx!(VR[0.0, 0.0, 0.0, 0.0]),
+                    radius: 0.5,
+                    material: Material::diffuse(V3(0.8, 0.3, 0.1)),
+                },
+                Primitive::Sphere {
+                    center: mx!(VR[0.0, -100.0, -1.0, 1.0]),
+                    velocity: mx!(VR[0.0, 0.0, 0.0, 0.0]),
+                    radius: 100.0,
+                    material: Material::diffuse(V3(0.5, 0.5, 0.5)),
+                },
+            ],
+            lights: vec![Light::Point {
+                position: mx!(VR[1.0, 2.0, 1.0, 1.0]),
+                intensity: V3(3.0, 3.0, 3.0),
+            }],
+            background: V3(0.2, 0.3, 0.5),
+            bvh: None,
+        };
+        let render = |scene: &Scene| scene.render(12, 8, 2, &mut Rng::new(9));
+        let shared = SharedScene::from_hash(&SharedScene { scene }.to_hash()).unwrap();
+        // a crafted link of the same scene renders the same image
+        let text = "camera 0 0.5 2 0 0 -1 0.1 100 0\nbackground 0.2 0.3 0.5\n\
+            sphere 0 0.5 -1 0 0 0 0.5 diffuse 0.8 0.3 0.1\nsphere 0 -100 -1 0 0 0 100 diffuse 0.5 0.5 0.5\n\
+            point 1 2 1 3 3 3\n";
+        let crafted = format!("scene:{}", base64::encode(&lz77::compress(text.as_bytes())));
+        let crafted = SharedScene::from_hash(&crafted).unwrap();
+        assert!(render(&crafted.scene) == render(&shared.scene));
+        assert!(render(&shared.scene)
+            .iter()
+            .any(|&color| color != shared.scene.background));
+        // shader states are not scenes, and scenes are not shader states
+        let state = SharedState {
+            source: "fn f() {}".into(),
+            clear: mx!(VR[0.0, 0.0, 0.0, 1.0]),
+        };
+        assert!(SharedScene::from_hash(&state.to_hash()).is_none());
+        assert!(SharedState::from_hash(&shared.to_hash()).is_none());
+        for hash in [
+            "scene:",
+            "scene:not base64!",
+            &format!("scene:{}", base64::encode(b"\x85\x01\x00")),
+        ] {
+            assert!(SharedScene::from_hash(hash).is_none());
+        }
+    }
+}
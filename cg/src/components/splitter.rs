@@ -0,0 +1,56 @@
+use leptos::*;
+
+/// `localStorage` key of the persisted split ratio.
+const STORAGE_KEY: &str = "cg-split";
+
+/// Load the persisted split ratio, defaulting to `default` if there is none or storage is unavailable.
+pub fn load_ratio(default: f64) -> f64 {
+    let storage = window().local_storage().ok().flatten();
+    storage
+        .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|ratio| ratio.parse::<f64>().ok())
+        .filter(|ratio| ratio.is_finite())
+        .unwrap_or(default)
+}
+
+/// Persist the split ratio, ignoring storage errors.
+pub fn store_ratio(ratio: f64) {
+    if let Ok(Some(storage)) = window().local_storage() {
+        let _ = storage.set_item(STORAGE_KEY, &ratio.to_string());
+    }
+}
+
+/// Draggable divider between flex siblings of a row.
+///
+/// `ratio` is the position of the divider relative to the parent width, dragging sets it to the pointer position
+/// clamped to `[min, max]`. The parent lays out the siblings from `ratio`.
+#[component]
+pub fn Splitter(
+    cx: Scope,
+    ratio: RwSignal<f64>,
+    #[prop(default = 0.2)] min: f64,
+    #[prop(default = 0.8)] max: f64,
+) -> impl IntoView {
+    let rw_dragging = create_rw_signal(cx, false);
+    // the pointer is captured, so moves outside the divider and the page are still received
+    let on_down = move |event: ev::PointerEvent| {
+        let _ = event_target::<web_sys::Element>(&event).set_pointer_capture(event.pointer_id());
+        rw_dragging.set(true);
+    };
+    let on_move = move |event: ev::PointerEvent| {
+        if !rw_dragging.get_untracked() {
+            return;
+        }
+        let Some(parent) = event_target::<web_sys::Element>(&event).parent_element() else {
+            return;
+        };
+        let rect = parent.get_bounding_client_rect();
+        if rect.width() > 0.0 {
+            ratio.set(((event.client_x() as f64 - rect.left()) / rect.width()).clamp(min, max));
+        }
+    };
+    let on_up = move |_| rw_dragging.set(false);
+    view! { cx,
+        <div class="components_splitter" class:dragging=rw_dragging on:pointerdown=on_down on:pointermove=on_move on:pointerup=on_up on:lostpointercapture=on_up />
+    }
+}
@@ -0,0 +1,71 @@
+use crate::math::{mx, VR};
+use leptos::*;
+
+/// `localStorage` key of the persisted theme.
+const STORAGE_KEY: &str = "cg-theme";
+
+/// Page theme, shared by the editor, the canvas clear color, and the page styles.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl Theme {
+    /// Load the persisted theme, defaulting to `Dark` if there is none or storage is unavailable.
+    pub fn load() -> Theme {
+        let storage = window().local_storage().ok().flatten();
+        match storage
+            .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten())
+            .as_deref()
+        {
+            Some("light") => Theme::Light,
+            _ => Theme::Dark,
+        }
+    }
+
+    /// Persist the theme, ignoring storage errors.
+    pub fn store(self) {
+        if let Ok(Some(storage)) = window().local_storage() {
+            let _ = storage.set_item(STORAGE_KEY, self.name());
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    pub fn toggle(self) -> Theme {
+        match self {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Light,
+        }
+    }
+
+    /// Monaco editor theme name.
+    pub fn monaco(self) -> &'static str {
+        match self {
+            Theme::Light => "vs",
+            Theme::Dark => "vs-dark",
+        }
+    }
+
+    /// Default canvas clear color.
+    pub fn clear(self) -> VR<f32, 4> {
+        match self {
+            Theme::Light => mx!(VR[0.8, 0.9, 0.9, 1.0]),
+            Theme::Dark => mx!(VR[0.0, 0.3, 0.3, 1.0]),
+        }
+    }
+
+    /// Icon of the button switching to the other theme.
+    pub fn icon(self) -> &'static str {
+        match self {
+            Theme::Light => "moon",
+            Theme::Dark => "sun",
+        }
+    }
+}
@@ -0,0 +1,88 @@
+#![allow(unused)]
+use leptos::*;
+use std::time::Duration;
+
+/// Time a toast is displayed before it is dismissed automatically.
+const EXPIRE: Duration = Duration::from_secs(5);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn name(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toast {
+    pub id: usize,
+    pub severity: Severity,
+    pub text: String,
+}
+
+/// Reactive queue of toasts rendered by `Toasts`.
+#[derive(Copy, Clone)]
+pub struct Notifier {
+    toasts: RwSignal<Vec<Toast>>,
+    next_id: StoredValue<usize>,
+}
+
+impl Notifier {
+    pub fn new(cx: Scope) -> Notifier {
+        Notifier {
+            toasts: create_rw_signal(cx, Vec::new()),
+            next_id: store_value(cx, 0),
+        }
+    }
+
+    /// Push a toast that expires after a few seconds.
+    ///
+    /// A toast identical to one being displayed replaces it, moving it to the end of the queue and restarting its
+    /// expiration, so rapid repeated errors show a single toast.
+    pub fn push(&self, severity: Severity, text: impl Into<String>) {
+        let text = text.into();
+        let duplicate = self.toasts.with_untracked(|toasts| {
+            toasts
+                .iter()
+                .find(|toast| toast.severity == severity && toast.text == text)
+                .map(|toast| toast.id)
+        });
+        if let Some(id) = duplicate {
+            self.toasts.update(|toasts| toasts.retain(|toast| toast.id != id));
+        }
+        let id = self.next_id.get_value();
+        self.next_id.set_value(id + 1);
+        self.toasts.update(|toasts| toasts.push(Toast { id, severity, text }));
+        let notifier = *self;
+        set_timeout(move || notifier.dismiss(id), EXPIRE);
+    }
+
+    pub fn dismiss(&self, id: usize) {
+        self.toasts.update(|toasts| toasts.retain(|toast| toast.id != id));
+    }
+}
+
+/// Dismissible toasts of `notifier`, clicking a toast dismisses it.
+#[component]
+pub fn Toasts(cx: Scope, notifier: Notifier) -> impl IntoView {
+    view! { cx,
+        <div class="components_toast">
+            <For
+                each=move || notifier.toasts.get()
+                key=|toast| toast.id
+                view=move |cx, toast| view! { cx,
+                    <div class=toast.severity.name() on:click=move |_| notifier.dismiss(toast.id)>{toast.text}</div>
+                }
+            />
+        </div>
+    }
+}
@@ -1,7 +1,9 @@
+use crate::components::graph::FrameGraph;
 use crate::util::{
     js::{js, js_fn},
     types::feather_icons,
 };
+use crate::web::webgpu::FrameStats;
 use js_sys::{Array, Function, JsString, Reflect};
 use leptos::{
     html,
@@ -11,26 +13,39 @@ use leptos::{
 use wasm_bindgen::prelude::*;
 use web_sys::MouseEvent;
 
+/// Canvas with player controls, `rw_resolution` receives the canvas content size in css pixels when it is resized.
 #[component]
 pub fn View(
     cx: Scope,
     rw_playing: RwSignal<bool>,
     set_canvas: WriteSignal<Option<html::HtmlElement<html::Canvas>>>,
+    #[prop(optional, into)] frame_time: MaybeSignal<f64>,
+    #[prop(optional, into)] stats: MaybeSignal<FrameStats>,
+    #[prop(optional)] rw_resolution: Option<RwSignal<(u32, u32)>>,
+    #[prop(optional)] children: Option<Children>,
 ) -> impl IntoView {
     let canvas_ref = create_node_ref::<html::Canvas>(cx);
     let (playing, set_playing) = rw_playing.split();
     let icon = Signal::derive(cx, move || if playing() { "pause" } else { "play" });
-    let (resolution, set_resolution) = create_signal(cx, (0, 0));
+    let (resolution, set_resolution) = rw_resolution.unwrap_or_else(|| create_rw_signal(cx, (0, 0))).split();
+    let (graph, set_graph) = create_signal(cx, false);
+    let fps = move || if frame_time() > 0.0 { 1000.0 / frame_time() } else { 0.0 };
     let root = view! {
         cx,
         <div class="components_view">
             <canvas _ref=canvas_ref />
+            <Show when=graph fallback=|_| ()>
+                <FrameGraph frame_time=frame_time />
+            </Show>
             <div>
                 <PlayerButton icon="skip-back"/>
                 <PlayerButton icon=icon on:click=move |_|set_playing(!playing()) />
                 <span>140.3</span>
-                <span>60.1fps</span>
+                <span>{move || format!("{:.1}fps", fps())}</span>
                 <span>{move || format!("{}x{}", resolution().0, resolution().1)}</span>
+                <span>{move || format!("{}v {}t {}dc", stats().vertices, stats().triangles, stats().draw_calls)}</span>
+                {children.map(|children| children(cx))}
+                <PlayerButton icon="activity" on:click=move |_| set_graph(!graph()) />
                 <PlayerButton icon="maximize" />
             </div>
         </div>
@@ -39,9 +54,9 @@ pub fn View(
     set_canvas(Some(canvas.clone()));
     web_sys::ResizeObserver::new(&js_fn!(<dyn Fn(Array)> move |entries: Array| {
         let entry = js!(entries[0] as web_sys::ResizeObserverEntry).content_box_size().at(0);
-        let block = js!(entry["blockSize"]).as_f64().unwrap_or_default() as i32;
-        let inline = js!(entry["inlineSize"]).as_f64().unwrap_or_default() as i32;
-        set_resolution((inline as i32, block as i32));
+        let block = js!(entry["blockSize"]).as_f64().unwrap_or_default() as u32;
+        let inline = js!(entry["inlineSize"]).as_f64().unwrap_or_default() as u32;
+        set_resolution((inline, block));
     }))
     .unwrap()
     .observe(canvas);
@@ -49,6 +64,6 @@ pub fn View(
 }
 
 #[component]
-fn PlayerButton(cx: Scope, #[prop(into)] icon: MaybeSignal<&'static str>) -> impl IntoView {
+pub fn PlayerButton(cx: Scope, #[prop(into)] icon: MaybeSignal<&'static str>) -> impl IntoView {
     view! { cx, <button><svg viewBox="0 0 24 24"><use_ href=move||feather_icons::name(icon.get()) /></svg></button> }
 }
@@ -5,6 +5,7 @@
 mod components;
 mod math;
 mod pages;
+mod raytrace;
 mod util;
 mod web;
 
@@ -0,0 +1,104 @@
+#![allow(unused)]
+use crate::math::{mx, MX, VR};
+
+/// Axis aligned bounding box, `min` and `max` are opposite corners as points (`w = 1`).
+#[derive(Copy, Clone)]
+pub struct Aabb {
+    pub min: VR<f32, 4>,
+    pub max: VR<f32, 4>,
+}
+
+impl Aabb {
+    pub fn new(min: VR<f32, 4>, max: VR<f32, 4>) -> Aabb {
+        Aabb { min, max }
+    }
+
+    /// Return the smallest box containing all `points`.
+    ///
+    /// An empty slice returns an inverted box with infinite bounds, which contains nothing and is the identity of
+    /// `merge`.
+    pub fn from_points(points: &[VR<f32, 4>]) -> Aabb {
+        let empty = Aabb {
+            min: mx!(VR[f32::INFINITY, f32::INFINITY, f32::INFINITY, 1.0]),
+            max: mx!(VR[f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY, 1.0]),
+        };
+        points.iter().fold(empty, |aabb, &p| aabb.merge(&Aabb::new(p, p)))
+    }
+
+    /// Return the smallest box containing both boxes.
+    pub fn merge(&self, other: &Aabb) -> Aabb {
+        let (min, max) = (&self.min, &self.max);
+        let (other_min, other_max) = (&other.min, &other.max);
+        Aabb {
+            min: mx!(VR[min[0].min(other_min[0]), min[1].min(other_min[1]), min[2].min(other_min[2]), 1.0]),
+            max: mx!(VR[max[0].max(other_max[0]), max[1].max(other_max[1]), max[2].max(other_max[2]), 1.0]),
+        }
+    }
+
+    /// Return the area of the box faces, empty boxes from `from_points` have infinite area.
+    pub fn surface_area(&self) -> f32 {
+        let size = self.max - self.min;
+        2.0 * (size[0] * size[1] + size[1] * size[2] + size[2] * size[0])
+    }
+
+    /// Return the box containing this box transformed by the affine matrix `m` (column vectors, `p' = m * p`).
+    ///
+    /// The result contains all 8 transformed corners, so it is larger than the box when `m` rotates it.
+    pub fn transform(&self, m: &MX<f32, 4, 4>) -> Aabb {
+        Aabb::from_points(&self.corners().map(|corner| m.multiply(&corner.transpose()).transpose()))
+    }
+
+    /// Return the 8 box corners, the bits of the corner index select `max` (1) or `min` (0) for `x`, `y`, and `z`.
+    pub fn corners(&self) -> [VR<f32, 4>; 8] {
+        std::array::from_fn(|i| {
+            let pick = |axis: usize| {
+                if i >> axis & 1 == 1 {
+                    self.max[axis]
+                } else {
+                    self.min[axis]
+                }
+            };
+            mx!(VR[pick(0), pick(1), pick(2), 1.0])
+        })
+    }
+}
+
+mod test {
+    use crate::math::{mx, Aabb, VR};
+
+    fn close(aabb: &Aabb, min: [f32; 3], max: [f32; 3]) -> bool {
+        (0..3).all(|i| (aabb.min[i] - min[i]).abs() < 1e-5 && (aabb.max[i] - max[i]).abs() < 1e-5)
+    }
+
+    #[test]
+    fn bounds() {
+        let points = [
+            mx!(VR[1.0, -2.0, 0.0, 1.0]),
+            mx!(VR[-1.0, 3.0, 0.5, 1.0]),
+            mx!(VR[0.0, 0.0, -4.0, 1.0]),
+        ];
+        let aabb = Aabb::from_points(&points);
+        assert!(close(&aabb, [-1.0, -2.0, -4.0], [1.0, 3.0, 0.5]));
+        let a = Aabb::new(mx!(VR[0.0, 0.0, 0.0, 1.0]), mx!(VR[1.0, 1.0, 1.0, 1.0]));
+        let b = Aabb::new(mx!(VR[2.0, -1.0, 0.5, 1.0]), mx!(VR[3.0, 0.5, 0.75, 1.0]));
+        let merged = a.merge(&b);
+        assert!(close(&merged, [0.0, -1.0, 0.0], [3.0, 1.0, 1.0]));
+        let contains = |c: &VR<f32, 4>| (0..3).all(|i| merged.min[i] <= c[i] && c[i] <= merged.max[i]);
+        assert!(a.corners().iter().chain(b.corners().iter()).all(contains));
+        let empty = Aabb::from_points(&[]);
+        assert!(close(&empty.merge(&a), [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]));
+        assert_eq!(a.surface_area(), 6.0);
+        assert_eq!(b.surface_area(), 2.0 * (1.5 + 1.5 * 0.25 + 0.25));
+    }
+
+    #[test]
+    fn transform() {
+        let aabb = Aabb::new(mx!(VR[-1.0, -1.0, -1.0, 1.0]), mx!(VR[1.0, 1.0, 1.0, 1.0]));
+        let (sin, cos) = std::f32::consts::FRAC_PI_4.sin_cos();
+        let rotation = mx!([cos, -sin, 0.0, 0.0][sin, cos, 0.0, 0.0][0.0, 0.0, 1.0, 0.0][0.0, 0.0, 0.0, 1.0]);
+        let s = 2f32.sqrt();
+        assert!(close(&aabb.transform(&rotation), [-s, -s, -1.0], [s, s, 1.0]));
+        let translation = mx!([1.0, 0.0, 0.0, 5.0][0.0, 1.0, 0.0, 0.0][0.0, 0.0, 2.0, 0.0][0.0, 0.0, 0.0, 1.0]);
+        assert!(close(&aabb.transform(&translation), [4.0, -1.0, -2.0], [6.0, 1.0, 2.0]));
+    }
+}
@@ -0,0 +1,48 @@
+#![allow(unused)]
+use std::f32::consts::{PI, TAU};
+
+/// Map the angle `a` in radians to `(-π, π]`.
+pub fn wrap_angle(a: f32) -> f32 {
+    let a = (a + PI).rem_euclid(TAU) - PI;
+    if a <= -PI {
+        PI
+    } else {
+        a
+    }
+}
+
+/// Interpolate from the angle `a` to `b` in radians along the shortest arc, the result is wrapped to `(-π, π]`.
+pub fn lerp_angle(a: f32, b: f32, t: f32) -> f32 {
+    wrap_angle(a + wrap_angle(b - a) * t)
+}
+
+mod test {
+    use crate::math::{lerp_angle, wrap_angle};
+    use std::f32::consts::PI;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn wrap() {
+        assert!(close(wrap_angle(0.5), 0.5));
+        assert!(close(wrap_angle(PI), PI));
+        assert!(close(wrap_angle(-PI), PI));
+        assert!(close(wrap_angle(3.0 * PI), PI));
+        assert!(close(wrap_angle(1.5 * PI), -0.5 * PI));
+        assert!(close(wrap_angle(-7.0 * PI / 2.0), 0.5 * PI));
+    }
+
+    #[test]
+    fn lerp() {
+        let (a, b) = (170f32.to_radians(), (-170f32).to_radians());
+        assert!(close(lerp_angle(a, b, 0.0), a));
+        assert!(close(lerp_angle(a, b, 0.25), 175f32.to_radians()));
+        assert!(close(lerp_angle(a, b, 0.5).abs(), PI));
+        assert!(close(lerp_angle(a, b, 0.75), (-175f32).to_radians()));
+        assert!(close(lerp_angle(a, b, 1.0), b));
+        assert!(close(lerp_angle(b, a, 0.25), (-175f32).to_radians()));
+        assert!(close(lerp_angle(0.0, 1.0, 0.5), 0.5));
+    }
+}
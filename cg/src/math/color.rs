@@ -0,0 +1,67 @@
+//! Color conversion utilities.
+//!
+//! Colors are `VR<f32, 4>` linear `rgba` values, hex strings are sRGB encoded `#rrggbb` values without alpha.
+#![allow(unused)]
+use crate::math::{mx, VR};
+
+/// Convert an sRGB encoded component in `[0, 1]` to linear.
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a linear component in `[0, 1]` to sRGB encoded.
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Parse a `#rrggbb` hex string into a linear color with `alpha`, returning `None` if `hex` is invalid.
+pub fn from_hex(hex: &str, alpha: f32) -> Option<VR<f32, 4>> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let component = |i: usize| srgb_to_linear(u8::from_str_radix(&hex[i..i + 2], 16).unwrap() as f32 / 255.0);
+    Some(mx!(VR[component(0), component(2), component(4), alpha]))
+}
+
+/// Format the `rgb` components of a linear color as a `#rrggbb` hex string, components are clamped to `[0, 1]`.
+pub fn to_hex(color: &VR<f32, 4>) -> String {
+    let component = |i: usize| (linear_to_srgb(color[i].clamp(0.0, 1.0)) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", component(0), component(1), component(2))
+}
+
+mod test {
+    use crate::math::color::{from_hex, linear_to_srgb, srgb_to_linear, to_hex};
+    use crate::math::matrix::mx;
+
+    #[test]
+    fn transfer() {
+        for c in [0.0, 0.002, 0.1, 0.5, 0.9, 1.0] {
+            assert!((linear_to_srgb(srgb_to_linear(c)) - c).abs() < 1e-5);
+        }
+        assert!((srgb_to_linear(0.5) - 0.214).abs() < 1e-3);
+    }
+
+    #[test]
+    fn hex() {
+        assert!(from_hex("#ffffff", 1.0).unwrap() == mx!(VR[1.0, 1.0, 1.0, 1.0]));
+        assert!(from_hex("#000000", 0.5).unwrap() == mx!(VR[0.0, 0.0, 0.0, 0.5]));
+        for hex in ["#000000", "#ffffff", "#4d4d4d", "#12ab9f"] {
+            assert_eq!(to_hex(&from_hex(hex, 1.0).unwrap()), hex);
+        }
+        assert_eq!(to_hex(&mx!(VR[2.0, -1.0, 1.0, 1.0])), "#ff00ff");
+        for hex in [
+            "", "#", "ffffff", "#fffff", "#fffffff", "#gggggg", "#ff ffff", "#+fffff", "#ffé00",
+        ] {
+            assert!(from_hex(hex, 1.0).is_none());
+        }
+    }
+}
@@ -0,0 +1,86 @@
+#![allow(unused)]
+use crate::math::{mx, Aabb, MX, VR};
+
+/// View frustum for culling, made of 6 planes pointing inwards.
+///
+/// Planes are `VR[a, b, c, d]` vectors, the signed distance of a point `p` (`w = 1`) to a plane is `plane.dot(p)`.
+/// Distances are positive inside the frustum.
+#[derive(Copy, Clone)]
+pub struct Frustum {
+    /// Planes in order: left, right, bottom, top, near, far.
+    pub planes: [VR<f32, 4>; 6],
+}
+
+impl Frustum {
+    /// Extract the frustum planes of a view-projection matrix.
+    ///
+    /// The matrix transforms column vectors (`clip = m * p`) into WebGPU clip space, where visible points satisfy
+    /// `-w <= x <= w`, `-w <= y <= w`, and `0 <= z <= w`.
+    pub fn new(m: &MX<f32, 4, 4>) -> Frustum {
+        let row = |i: usize| mx!(VR[m[4 * i], m[4 * i + 1], m[4 * i + 2], m[4 * i + 3]]);
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        let planes = [w + x, w - x, w + y, w - y, z, w - z].map(|plane| {
+            let normal = mx!(VR[plane[0], plane[1], plane[2], 0.0]);
+            plane / normal.dot(&normal).sqrt()
+        });
+        Frustum { planes }
+    }
+
+    /// Return false if the sphere is completely outside any of the frustum planes.
+    pub fn contains_sphere(&self, center: VR<f32, 4>, radius: f32) -> bool {
+        self.planes.iter().all(|plane| plane.dot(&center) >= -radius)
+    }
+
+    /// Return false if the box is completely outside any of the frustum planes.
+    ///
+    /// This is conservative, boxes near the frustum corners might not be culled even if they are outside.
+    pub fn contains_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the plane normal
+            let pick = |axis: usize| {
+                if plane[axis] >= 0.0 {
+                    aabb.max[axis]
+                } else {
+                    aabb.min[axis]
+                }
+            };
+            plane.dot(&mx!(VR[pick(0), pick(1), pick(2), 1.0])) >= 0.0
+        })
+    }
+}
+
+mod test {
+    use crate::math::{mx, Aabb, Frustum, MX};
+
+    /// Right-handed perspective looking down `-Z` with 90 degrees vertical fov, aspect 1, near 1, and far 10.
+    fn perspective() -> MX<f32, 4, 4> {
+        let (near, far) = (1.0, 10.0);
+        let (a, b) = (far / (near - far), near * far / (near - far));
+        mx!([1.0, 0.0, 0.0, 0.0][0.0, 1.0, 0.0, 0.0][0.0, 0.0, a, b][0.0, 0.0, -1.0, 0.0])
+    }
+
+    #[test]
+    fn sphere() {
+        let frustum = Frustum::new(&perspective());
+        assert!(frustum.contains_sphere(mx!(VR[0.0, 0.0, -5.0, 1.0]), 1.0));
+        assert!(!frustum.contains_sphere(mx!(VR[0.0, 0.0, 5.0, 1.0]), 1.0));
+        assert!(!frustum.contains_sphere(mx!(VR[0.0, 0.0, -20.0, 1.0]), 1.0));
+        assert!(!frustum.contains_sphere(mx!(VR[10.0, 0.0, -5.0, 1.0]), 1.0));
+        assert!(!frustum.contains_sphere(mx!(VR[0.0, -10.0, -5.0, 1.0]), 1.0));
+        // outside the left plane by less than the radius
+        assert!(frustum.contains_sphere(mx!(VR[-5.5, 0.0, -5.0, 1.0]), 1.0));
+    }
+
+    #[test]
+    fn aabb() {
+        let frustum = Frustum::new(&perspective());
+        let inside = Aabb::new(mx!(VR[-1.0, -1.0, -6.0, 1.0]), mx!(VR[1.0, 1.0, -4.0, 1.0]));
+        let behind = Aabb::new(mx!(VR[-1.0, -1.0, 2.0, 1.0]), mx!(VR[1.0, 1.0, 4.0, 1.0]));
+        let right = Aabb::new(mx!(VR[8.0, -1.0, -6.0, 1.0]), mx!(VR[9.0, 1.0, -4.0, 1.0]));
+        let crossing = Aabb::new(mx!(VR[-1.0, -1.0, -6.0, 1.0]), mx!(VR[1.0, 1.0, 6.0, 1.0]));
+        assert!(frustum.contains_aabb(&inside));
+        assert!(!frustum.contains_aabb(&behind));
+        assert!(!frustum.contains_aabb(&right));
+        assert!(frustum.contains_aabb(&crossing));
+    }
+}
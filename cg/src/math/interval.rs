@@ -0,0 +1,142 @@
+#![allow(unused)]
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Closed interval `lo..=hi` bounding a value computed with floating point error.
+///
+/// Arithmetic results are rounded outwards by one ulp, so they contain the exact result of the operation for any
+/// values in the operands. Intervals with `lo > hi` or `NaN` bounds are empty.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Interval {
+    pub lo: f32,
+    pub hi: f32,
+}
+
+impl Interval {
+    pub fn new(lo: f32, hi: f32) -> Interval {
+        Interval { lo, hi }
+    }
+
+    /// Return the interval containing only `value`.
+    pub fn point(value: f32) -> Interval {
+        Interval { lo: value, hi: value }
+    }
+
+    /// Return the interval `value ± error`.
+    pub fn with_error(value: f32, error: f32) -> Interval {
+        Interval::new(value - error, value + error).widen()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lo > self.hi || self.lo.is_nan() || self.hi.is_nan()
+    }
+
+    pub fn width(&self) -> f32 {
+        self.hi - self.lo
+    }
+
+    pub fn midpoint(&self) -> f32 {
+        (self.lo + self.hi) / 2.0
+    }
+
+    pub fn contains(&self, value: f32) -> bool {
+        (self.lo..=self.hi).contains(&value)
+    }
+
+    /// Narrow the interval to the part inside `other`, returning `None` if they do not overlap or any of them is
+    /// empty.
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        let interval = Interval::new(self.lo.max(other.lo), self.hi.min(other.hi));
+        (!self.is_empty() && !other.is_empty() && !interval.is_empty()).then_some(interval)
+    }
+
+    /// Move the bounds one ulp outwards.
+    fn widen(self) -> Interval {
+        Interval::new(self.lo.next_down(), self.hi.next_up())
+    }
+
+    /// Return the interval containing the products or quotients of the bounds, `NaN` values (`0 * ∞`) are ignored by
+    /// `min` and `max`.
+    fn hull(values: [f32; 4]) -> Interval {
+        let lo = values.into_iter().fold(f32::INFINITY, f32::min);
+        let hi = values.into_iter().fold(f32::NEG_INFINITY, f32::max);
+        Interval::new(lo, hi).widen()
+    }
+}
+
+impl Add for Interval {
+    type Output = Interval;
+    fn add(self, rhs: Interval) -> Interval {
+        Interval::new(self.lo + rhs.lo, self.hi + rhs.hi).widen()
+    }
+}
+
+impl Sub for Interval {
+    type Output = Interval;
+    fn sub(self, rhs: Interval) -> Interval {
+        Interval::new(self.lo - rhs.hi, self.hi - rhs.lo).widen()
+    }
+}
+
+impl Neg for Interval {
+    type Output = Interval;
+    fn neg(self) -> Interval {
+        Interval::new(-self.hi, -self.lo)
+    }
+}
+
+impl Mul for Interval {
+    type Output = Interval;
+    fn mul(self, rhs: Interval) -> Interval {
+        let (a, b) = (self, rhs);
+        Interval::hull([a.lo * b.lo, a.lo * b.hi, a.hi * b.lo, a.hi * b.hi])
+    }
+}
+
+impl Div for Interval {
+    type Output = Interval;
+    /// Divide by the interval, dividing by an interval containing zero returns the whole real line.
+    fn div(self, rhs: Interval) -> Interval {
+        if rhs.contains(0.0) {
+            return Interval::new(f32::NEG_INFINITY, f32::INFINITY);
+        }
+        let (a, b) = (self, rhs);
+        Interval::hull([a.lo / b.lo, a.lo / b.hi, a.hi / b.lo, a.hi / b.hi])
+    }
+}
+
+mod test {
+    use crate::math::Interval;
+
+    #[test]
+    fn arithmetic() {
+        let (a, b) = (Interval::new(1.0, 2.0), Interval::new(-3.0, 0.5));
+        let sum = a + b;
+        assert!(sum.contains(-2.0) && sum.contains(2.5) && sum.width() < 4.5 + 1e-5);
+        let difference = a - b;
+        assert!(difference.contains(0.5) && difference.contains(5.0) && difference.width() < 4.5 + 1e-5);
+        // the sign of b changes inside the interval, the bounds are the products of the lower bound of b
+        let product = a * b;
+        assert!(product.contains(-6.0) && product.contains(1.0) && product.width() < 7.0 + 1e-5);
+        assert_eq!(-a, Interval::new(-2.0, -1.0));
+        let quotient = a / Interval::new(2.0, 4.0);
+        assert!(quotient.contains(0.25) && quotient.contains(1.0) && quotient.width() < 0.75 + 1e-5);
+        assert!((a / b).contains(f32::MAX));
+        // 0.1 is not exact in binary, the rounded sum is still bounded
+        let tenth = Interval::point(0.1);
+        let sum = (0..10).fold(Interval::point(0.0), |sum, _| sum + tenth);
+        assert!(sum.contains(1.0) && sum.lo < sum.hi);
+    }
+
+    #[test]
+    fn narrow() {
+        let t = Interval::new(0.0, f32::INFINITY);
+        let hit = t.intersection(&Interval::new(-1.0, 3.0)).unwrap();
+        assert_eq!(hit, Interval::new(0.0, 3.0));
+        let hit = hit.intersection(&Interval::with_error(2.0, 0.5)).unwrap();
+        assert!(hit.contains(1.5) && hit.contains(2.5) && !hit.contains(1.4) && hit.hi < 3.0);
+        assert!((hit.midpoint() - 2.0).abs() < 1e-5);
+        assert!(t.intersection(&Interval::new(-2.0, -1.0)).is_none());
+        assert!(t.intersection(&Interval::new(1.0, 0.0)).is_none());
+        assert!(Interval::new(1.0, 0.0).is_empty() && Interval::new(f32::NAN, 1.0).is_empty());
+    }
+}
@@ -1,5 +1,5 @@
 #![allow(unused)]
-use num_traits::Float;
+use num_traits::{AsPrimitive, Float};
 use std::ops::{
     Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Div, DivAssign, Index, IndexMut,
     Mul, MulAssign, Neg, Not, Rem, RemAssign, Shl, ShlAssign, Shr, ShrAssign, Sub, SubAssign,
@@ -44,6 +44,9 @@ pub type VC<T, const D: usize> = MX<T, D, 1>;
 /// - `mx!([r0...][r1...] rx...)`: `mx!([0, 1][2, 3])`
 ///   - Create a matrix with the `rx` provided elements. The matrix shape is determined by `rx` and `r[i]` lengths.
 ///
+/// - `mx!(cols [c0...][c1...] cx...)`: `mx!(cols [0, 2][1, 3])`
+///   - Create a matrix with the `cx` provided columns. The matrix shape is determined by `cx` and `c[i]` lengths.
+///   - Useful for column-major sources, the matrix is still stored in row-major order.
 ///
 /// - `mx!(VR[d0, d1, d2, d3, ...])`: `mx!(VR[0, 1, 2, 3])`
 ///   - Create a single row matrix with `dx` columns.
@@ -75,6 +78,19 @@ macro_rules! mx {
         crate::math::MX::<_, R, C> { data: [$($($v,)+)+] }
     })()};
 
+    (cols $([$($v:expr),+])+) => {{
+        const C: usize = crate::math::matrix::mx!($([$($v),+],)+);
+        const R: usize = crate::math::matrix::mx!($($($v,)+)+) / C;
+        let data = [$($($v,)+)+];
+        let mut res = crate::math::MX::<_, R, C> { data };
+        for i in 0..R {
+            for j in 0..C {
+                res.data[C * i + j] = data[R * j + i];
+            }
+        }
+        res
+    }};
+
     (VR [$($v:expr),+]) => { (||{
         const R: usize = crate::math::matrix::mx!($($v,)+);
         crate::math::VR::<_, R> { data: [$($v,)+] }
@@ -237,8 +253,46 @@ op_math_assign!(BitXorAssign::bitxor_assign);
 op_math_assign!(ShlAssign::shl_assign);
 op_math_assign!(ShrAssign::shr_assign);
 
+// broadcast
+
+#[allow(clippy::identity_op)]
+impl<T: Copy + Add<Output = T>, const R: usize, const C: usize> MX<T, R, C>
+where
+    [(); R * C]:,
+{
+    /// Create a new matrix adding `row` to each row.
+    pub fn add_row_broadcast(&self, row: &VR<T, C>) -> MX<T, R, C>
+    where
+        [(); 1 * C]:,
+    {
+        let mut res = *self;
+        for i in 0..R {
+            for j in 0..C {
+                res[C * i + j] = res[C * i + j] + row[j];
+            }
+        }
+        res
+    }
+
+    /// Create a new matrix adding `col` to each column.
+    pub fn add_col_broadcast(&self, col: &VC<T, R>) -> MX<T, R, C>
+    where
+        [(); R * 1]:,
+    {
+        let mut res = *self;
+        for i in 0..R {
+            for j in 0..C {
+                res[C * i + j] = res[C * i + j] + col[i];
+            }
+        }
+        res
+    }
+}
+
 // compare
 
+/// Element-wise equality, for float matrices any `NaN` element makes the comparison false, even against itself.
+/// Use `contains_nan` or `is_finite` to validate float matrices.
 impl<T: PartialEq, const R: usize, const C: usize> PartialEq for MX<T, R, C>
 where
     [(); R * C]:,
@@ -248,6 +302,112 @@ where
     }
 }
 
+// layout
+
+impl<T: Copy, const R: usize, const C: usize> MX<T, R, C>
+where
+    [(); R * C]:,
+{
+    /// Create a new `R_`x`C_` matrix copying the overlapping top-left region, other cells are set to `fill`.
+    ///
+    /// Rows and columns past `R_` and `C_` are truncated.
+    pub fn resize<const R_: usize, const C_: usize>(&self, fill: T) -> MX<T, R_, C_>
+    where
+        [(); R_ * C_]:,
+    {
+        let mut res = mx!((R_, C_)(fill));
+        for i in 0..R.min(R_) {
+            for j in 0..C.min(C_) {
+                res[C_ * i + j] = self[C * i + j];
+            }
+        }
+        res
+    }
+
+    /// Create a new matrix converting each element to `U`, the conversion is lossless.
+    pub fn cast<U: From<T>>(&self) -> MX<U, R, C> {
+        MX {
+            data: self.data.map(U::from),
+        }
+    }
+
+    /// Create a new matrix converting each element to `U` as the `as` operator does, the conversion might be lossy.
+    pub fn cast_as<U: Copy + 'static>(&self) -> MX<U, R, C>
+    where
+        T: AsPrimitive<U>,
+    {
+        MX {
+            data: self.data.map(T::as_),
+        }
+    }
+
+    /// Swap rows `a` and `b` in place.
+    pub fn swap_rows(&mut self, a: usize, b: usize) {
+        for j in 0..C {
+            self.data.swap(C * a + j, C * b + j);
+        }
+    }
+
+    /// Swap columns `a` and `b` in place.
+    pub fn swap_columns(&mut self, a: usize, b: usize) {
+        for i in 0..R {
+            self.data.swap(C * i + a, C * i + b);
+        }
+    }
+
+    /// Stack the columns into a single column, the `vec` operator of matrix equations.
+    ///
+    /// With `kron`, linear matrix equations become linear systems, `vec(A X B) = (Bᵀ ⊗ A) vec(X)`. The Sylvester
+    /// equation `A X + X B = C` becomes `(I ⊗ A + Bᵀ ⊗ I) vec(X) = vec(C)`, which is solved with `inverse` and
+    /// `from_vectorized`.
+    #[allow(clippy::identity_op)]
+    pub fn vectorize(&self) -> VC<T, { R * C }>
+    where
+        [(); R * C * 1]:,
+    {
+        MX {
+            data: std::array::from_fn(|k| self[C * (k % R) + k / R]),
+        }
+    }
+
+    /// Create a matrix from its stacked columns, the inverse of `vectorize`.
+    #[allow(clippy::identity_op)]
+    pub fn from_vectorized(v: &VC<T, { R * C }>) -> MX<T, R, C>
+    where
+        [(); R * C * 1]:,
+    {
+        MX {
+            data: std::array::from_fn(|k| v[R * (k % C) + k / C]),
+        }
+    }
+}
+
+impl<T: Copy + Mul<Output = T>, const R: usize, const C: usize> MX<T, R, C>
+where
+    [(); R * C]:,
+{
+    /// Multiply column `c` by `factor` in place.
+    pub fn scale_column(&mut self, c: usize, factor: T) {
+        for i in 0..R {
+            self[C * i + c] = self[C * i + c] * factor;
+        }
+    }
+
+    /// Kronecker product `self ⊗ rhs`, the block matrix with `self[i][j] * rhs` blocks, see `vectorize`.
+    pub fn kron<const R_: usize, const C_: usize>(&self, rhs: &MX<T, R_, C_>) -> MX<T, { R * R_ }, { C * C_ }>
+    where
+        [(); R_ * C_]:,
+        [(); { R * R_ } * { C * C_ }]:,
+    {
+        MX {
+            data: std::array::from_fn(|k| {
+                let (i, j) = (k / (C * C_), k % (C * C_));
+                self[C * (i / R_) + j / C_] * rhs[C_ * (i % R_) + j % C_]
+            }),
+        }
+    }
+}
+
 // algebra
 
 impl<T: Float, const R: usize, const C: usize> MX<T, R, C>
@@ -259,6 +419,16 @@ where
         (R, C)
     }
 
+    /// Return true if any element is `NaN`.
+    pub fn contains_nan(&self) -> bool {
+        self.data.iter().any(|v| v.is_nan())
+    }
+
+    /// Return true if all elements are neither infinite nor `NaN`.
+    pub fn is_finite(&self) -> bool {
+        self.data.iter().all(|v| v.is_finite())
+    }
+
     /// Return the matrix with changed dimensions, the old and new dimensions must be compatible.
     pub fn reshape<const R_: usize, const C_: usize>(self) -> MX<T, R_, C_>
     where
@@ -281,6 +451,16 @@ where
         res
     }
 
+    /// Sum of the element-wise products of two matrices.
+    ///
+    /// This is the dot product for single row (`VR`) and single column (`VC`) matrices.
+    pub fn dot(&self, rhs: &MX<T, R, C>) -> T {
+        self.data
+            .iter()
+            .zip(rhs.data.iter())
+            .fold(T::zero(), |acc, (a, b)| acc + *a * *b)
+    }
+
     /// Multiply two matrices.
     pub fn multiply<const C_: usize>(&self, rhs: &MX<T, C, C_>) -> MX<T, R, C_>
     where
@@ -297,6 +477,252 @@ where
         }
         res
     }
+
+    /// Compute the Moore-Penrose pseudoinverse of a full rank matrix, returning `None` if it is rank deficient.
+    ///
+    /// Tall matrices use the left inverse `(AᵀA)⁻¹Aᵀ`, the least squares solution of `A x = b` is `A⁺ b`. Wide
+    /// matrices use the right inverse `Aᵀ(AAᵀ)⁻¹`, giving the minimum norm solution instead. Square matrices return
+    /// the inverse up to rounding. The normal matrix squares the condition number of `A`, so ill-conditioned matrices
+    /// are rejected earlier than by `inverse`.
+    pub fn pseudo_inverse(&self) -> Option<MX<T, C, R>>
+    where
+        [(); C * R]:,
+        [(); C * C]:,
+        [(); R * R]:,
+    {
+        let transpose = self.transpose();
+        match R >= C {
+            true => Some(transpose.multiply(self).inverse()?.multiply(&transpose)),
+            false => Some(transpose.multiply(&self.multiply(&transpose).inverse()?)),
+        }
+    }
+}
+
+// square
+
+#[allow(clippy::identity_op)]
+impl<T: Float, const N: usize> MX<T, N, N>
+where
+    [(); N * N]:,
+{
+    /// Create an identity matrix, with ones in the diagonal and zeros elsewhere.
+    pub fn identity() -> MX<T, N, N> {
+        let mut res = mx!((N, N)(T::zero()));
+        for i in 0..N {
+            res[N * i + i] = T::one();
+        }
+        res
+    }
+
+    /// Sum of the diagonal elements.
+    pub fn trace(&self) -> T {
+        (0..N).fold(T::zero(), |acc, i| acc + self[N * i + i])
+    }
+
+    /// Return true if all elements outside the diagonal are within `eps` of zero.
+    pub fn is_diagonal(&self, eps: T) -> bool {
+        self.is_zero_where(eps, |i, j| i != j)
+    }
+
+    /// Return true if all elements below the diagonal are within `eps` of zero.
+    pub fn is_upper_triangular(&self, eps: T) -> bool {
+        self.is_zero_where(eps, |i, j| i > j)
+    }
+
+    /// Return true if all elements above the diagonal are within `eps` of zero.
+    pub fn is_lower_triangular(&self, eps: T) -> bool {
+        self.is_zero_where(eps, |i, j| i < j)
+    }
+
+    fn is_zero_where(&self, eps: T, at: impl Fn(usize, usize) -> bool) -> bool {
+        (0..N * N).filter(|k| at(k / N, k % N)).all(|k| self[k].abs() <= eps)
+    }
+
+    /// Add the outer product `v * vᵀ` in place.
+    ///
+    /// Accumulating outer products builds covariance and normal equation matrices without storing the samples.
+    pub fn add_outer(&mut self, v: &VC<T, N>)
+    where
+        [(); N * 1]:,
+    {
+        for i in 0..N {
+            for j in 0..N {
+                self[N * i + j] = self[N * i + j] + v[i] * v[j];
+            }
+        }
+    }
+
+    /// Compute the determinant with LU decomposition and partial pivoting.
+    ///
+    /// Each column is eliminated using the row with the largest absolute pivot, which keeps rounding errors small.
+    /// Singular matrices return zero, or a value close to zero due to rounding.
+    pub fn determinant(&self) -> T {
+        let mut lu = *self;
+        let mut det = T::one();
+        for k in 0..N {
+            let pivot = (k..N).fold(k, |p, i| {
+                if lu[N * i + k].abs() > lu[N * p + k].abs() {
+                    i
+                } else {
+                    p
+                }
+            });
+            if lu[N * pivot + k] == T::zero() {
+                return T::zero();
+            }
+            if pivot != k {
+                lu.swap_rows(k, pivot);
+                det = -det;
+            }
+            det = det * lu[N * k + k];
+            for i in k + 1..N {
+                let factor = lu[N * i + k] / lu[N * k + k];
+                for j in k..N {
+                    lu[N * i + j] = lu[N * i + j] - factor * lu[N * k + j];
+                }
+            }
+        }
+        det
+    }
+
+    /// Compute the inverse with Gauss-Jordan elimination and partial pivoting, returning `None` if it is singular.
+    ///
+    /// The augmented matrix `[A | I]` is stored as two matrices, row operations reducing `A` to `I` turn `I` into
+    /// the inverse. Matrices are singular when a pivot is below `INVERSE_EPSILON` relative to the largest element.
+    /// Matrices with infinite or NaN elements also return `None`.
+    pub fn inverse(&self) -> Option<MX<T, N, N>> {
+        if !self.is_finite() {
+            return None;
+        }
+        let (mut a, mut res) = (*self, MX::identity());
+        let scale = a.data.iter().fold(T::zero(), |scale, x| scale.max(x.abs()));
+        let epsilon = T::from(INVERSE_EPSILON).unwrap() * scale;
+        for k in 0..N {
+            let pivot = (k..N).fold(k, |p, i| if a[N * i + k].abs() > a[N * p + k].abs() { i } else { p });
+            if a[N * pivot + k].abs() <= epsilon {
+                return None;
+            }
+            a.swap_rows(k, pivot);
+            res.swap_rows(k, pivot);
+            let factor = a[N * k + k].recip();
+            for j in 0..N {
+                a[N * k + j] = a[N * k + j] * factor;
+                res[N * k + j] = res[N * k + j] * factor;
+            }
+            for i in (0..N).filter(|&i| i != k) {
+                let factor = a[N * i + k];
+                for j in 0..N {
+                    a[N * i + j] = a[N * i + j] - factor * a[N * k + j];
+                    res[N * i + j] = res[N * i + j] - factor * res[N * k + j];
+                }
+            }
+        }
+        Some(res)
+    }
+
+    /// Compute the matrix exponential `e^A` with scaling and squaring and a Padé approximant.
+    ///
+    /// The matrix is scaled by `2^-s` until its infinity norm is at most `0.5`, where the degree `PADE_DEGREE`
+    /// approximant is accurate to the `f64` precision, and the approximant is squared `s` times. `e^(A t) x0` solves
+    /// the linear system `dx/dt = A x` with `x(0) = x0`. Matrices with infinite or NaN elements return NaN elements.
+    pub fn exp(&self) -> MX<T, N, N> {
+        let norm = (0..N)
+            .map(|i| (0..N).fold(T::zero(), |sum, j| sum + self[N * i + j].abs()))
+            .fold(T::zero(), T::max);
+        let half = T::from(0.5).unwrap();
+        let (mut a, mut squarings) = (*self, 0);
+        // the limit stops infinite norms, which return NaN below
+        while norm * half.powi(squarings) > half && squarings < 1024 {
+            squarings += 1;
+        }
+        let scale = half.powi(squarings);
+        a.data.iter_mut().for_each(|x| *x = *x * scale);
+        // p(A) / q(A), q(A) = p(-A), the coefficients are c_k = c_{k-1} (d - k + 1) / ((2d - k + 1) k)
+        let (mut p, mut q, mut power, mut c) = (MX::identity(), MX::identity(), MX::identity(), T::one());
+        for k in 1..=PADE_DEGREE {
+            c = c * T::from(PADE_DEGREE - k + 1).unwrap() / T::from((2 * PADE_DEGREE - k + 1) * k).unwrap();
+            power = power.multiply(&a);
+            let sign = if k % 2 == 0 { T::one() } else { -T::one() };
+            for i in 0..N * N {
+                p[i] = p[i] + c * power[i];
+                q[i] = q[i] + sign * c * power[i];
+            }
+        }
+        let Some(q_inverse) = q.inverse() else {
+            return mx!((N, N)(T::nan()));
+        };
+        let mut res = q_inverse.multiply(&p);
+        for _ in 0..squarings {
+            res = res.multiply(&res);
+        }
+        res
+    }
+
+    /// Multiply two matrices with the Strassen algorithm.
+    ///
+    /// Strassen uses 7 instead of 8 sub-matrix multiplications per recursion step, which is faster than `multiply`
+    /// for large matrices. `N` must be a power of two above `STRASSEN_THRESHOLD`, other matrices and sub-matrices
+    /// below the threshold use `multiply`. Results differ from `multiply` by floating point rounding.
+    pub fn multiply_strassen(&self, rhs: &MX<T, N, N>) -> MX<T, N, N> {
+        if !N.is_power_of_two() || N <= STRASSEN_THRESHOLD {
+            return self.multiply(rhs);
+        }
+        MX {
+            data: strassen(&self.data, &rhs.data, N).try_into().ok().unwrap(),
+        }
+    }
+}
+
+/// Smallest pivot accepted by `inverse`, relative to the largest absolute matrix element.
+const INVERSE_EPSILON: f64 = 1e-6;
+
+/// Degree of the numerator and denominator of the Padé approximant used by `exp`.
+const PADE_DEGREE: usize = 6;
+
+/// Largest dimension multiplied with the naive algorithm by `multiply_strassen`.
+const STRASSEN_THRESHOLD: usize = 4;
+
+/// Multiply the `n`x`n` row-major matrices `a` and `b`, `n` must be a power of two.
+fn strassen<T: Float>(a: &[T], b: &[T], n: usize) -> Vec<T> {
+    if n <= STRASSEN_THRESHOLD {
+        let mut res = vec![T::zero(); n * n];
+        for i in 0..n {
+            for k in 0..n {
+                for j in 0..n {
+                    res[n * i + j] = res[n * i + j] + a[n * i + k] * b[n * k + j];
+                }
+            }
+        }
+        return res;
+    }
+    let h = n / 2;
+    let quadrant = |m: &[T], qi: usize, qj: usize| {
+        (0..h * h)
+            .map(|x| m[n * (qi * h + x / h) + qj * h + x % h])
+            .collect::<Vec<_>>()
+    };
+    let add = |x: &[T], y: &[T]| x.iter().zip(y).map(|(&x, &y)| x + y).collect::<Vec<_>>();
+    let sub = |x: &[T], y: &[T]| x.iter().zip(y).map(|(&x, &y)| x - y).collect::<Vec<_>>();
+    let [a11, a12, a21, a22] = [(0, 0), (0, 1), (1, 0), (1, 1)].map(|(qi, qj)| quadrant(a, qi, qj));
+    let [b11, b12, b21, b22] = [(0, 0), (0, 1), (1, 0), (1, 1)].map(|(qi, qj)| quadrant(b, qi, qj));
+    let m1 = strassen(&add(&a11, &a22), &add(&b11, &b22), h);
+    let m2 = strassen(&add(&a21, &a22), &b11, h);
+    let m3 = strassen(&a11, &sub(&b12, &b22), h);
+    let m4 = strassen(&a22, &sub(&b21, &b11), h);
+    let m5 = strassen(&add(&a11, &a12), &b22, h);
+    let m6 = strassen(&sub(&a21, &a11), &add(&b11, &b12), h);
+    let m7 = strassen(&sub(&a12, &a22), &add(&b21, &b22), h);
+    let c11 = add(&sub(&add(&m1, &m4), &m5), &m7);
+    let c12 = add(&m3, &m5);
+    let c21 = add(&m2, &m4);
+    let c22 = add(&add(&sub(&m1, &m2), &m3), &m6);
+    let mut res = vec![T::zero(); n * n];
+    for (c, qi, qj) in [(c11, 0, 0), (c12, 0, 1), (c21, 1, 0), (c22, 1, 1)] {
+        for (x, v) in c.into_iter().enumerate() {
+            res[n * (qi * h + x / h) + qj * h + x % h] = v;
+        }
+    }
+    res
 }
 
 mod test {
@@ -314,6 +740,10 @@ mod test {
         assert!((0..4).all(|i| m.data[i] == i as f64));
         let m = mx!(VC[0.0, 1.0, 2.0, 3.0]);
         assert!((0..4).all(|i| m.data[i] == i as f64));
+        let m = mx!(cols [0.0, 2.0][1.0, 3.0]);
+        assert!((0..4).all(|i| m.data[i] == i as f64));
+        assert!(mx!(cols [1, 2][3, 4]) == mx!([1, 3][2, 4]));
+        assert_eq!(mx!(cols [1.0, 2.0, 3.0][4.0, 5.0, 6.0]).shape(), (3, 2));
     }
 
     #[test]
@@ -336,6 +766,80 @@ mod test {
         assert!((0..m.data.len()).all(|i| m[i] == i * 2));
     }
 
+    #[test]
+    fn compare() {
+        let m = mx!([1.0f32, 2.0][3.0, 4.0]);
+        assert!(m == m);
+        assert!(!m.contains_nan());
+        assert!(m.is_finite());
+        let n = mx!([1.0f32, f32::NAN][3.0, 4.0]);
+        assert!(n != n);
+        assert!(n.contains_nan());
+        assert!(!n.is_finite());
+        let i = mx!([1.0f32, f32::INFINITY][3.0, 4.0]);
+        assert!(!i.contains_nan());
+        assert!(!i.is_finite());
+    }
+
+    #[test]
+    fn broadcast() {
+        let m = mx!([1, 2, 3][4, 5, 6]);
+        let r = m.add_row_broadcast(&mx!(VR[10, 20, 30]));
+        assert!(r == mx!([11, 22, 33][14, 25, 36]));
+        assert!((0..6).all(|i| r[i] - m[i] == r[i % 3] - m[i % 3]));
+        let r = m.add_col_broadcast(&mx!(VC[10, 20]));
+        assert!(r == mx!([11, 12, 13][24, 25, 26]));
+    }
+
+    #[test]
+    fn layout() {
+        let m = mx!([1, 2][3, 4]);
+        assert!(m.resize::<3, 3>(0) == mx!([1, 2, 0][3, 4, 0][0, 0, 0]));
+        assert!(m.resize::<2, 3>(9) == mx!([1, 2, 9][3, 4, 9]));
+        let m = mx!([1, 2, 3][4, 5, 6][7, 8, 9]);
+        assert!(m.resize::<2, 2>(0) == mx!([1, 2][4, 5]));
+        assert!(m.resize::<1, 4>(0) == mx!(VR[1, 2, 3, 0]));
+
+        let mut s = mx!([1, 2, 3][4, 5, 6]);
+        s.swap_columns(0, 2);
+        assert!(s == mx!([3, 2, 1][6, 5, 4]));
+        s.swap_columns(0, 2);
+        assert!(s == mx!([1, 2, 3][4, 5, 6]));
+        s.swap_rows(0, 1);
+        assert!(s == mx!([4, 5, 6][1, 2, 3]));
+        s.scale_column(1, 10);
+        assert!(s == mx!([4, 50, 6][1, 20, 3]));
+
+        let m = mx!([1i32, -2][3, 4]);
+        assert!(m.cast::<f64>() == mx!([1.0, -2.0][3.0, 4.0]));
+        assert!(m.cast_as::<f32>() == mx!([1.0f32, -2.0][3.0, 4.0]));
+        assert!(mx!([1.5f64, -2.5][1e40, 4.0]).cast_as::<f32>() == mx!([1.5f32, -2.5][f32::INFINITY, 4.0]));
+        assert!(mx!([1.7f32, -2.7][300.0, 4.0]).cast_as::<u8>() == mx!([1u8, 0][255, 4]));
+    }
+
+    #[test]
+    fn vectorize() {
+        use crate::math::MX;
+        let m = mx!([1, 2, 3][4, 5, 6]);
+        assert!(m.vectorize() == mx!(VC[1, 4, 2, 5, 3, 6]));
+        assert!(MX::<_, 2, 3>::from_vectorized(&m.vectorize()) == m);
+        assert!(MX::<_, 3, 2>::from_vectorized(&mx!(VC[1, 4, 2, 5, 3, 6])) == mx!([1, 5][4, 3][2, 6]));
+        assert!(mx!([1, 2][3, 4]).kron(&mx!([0, 1][1, 0])) == mx!([0, 1, 0, 2][1, 0, 2, 0][0, 3, 0, 4][3, 0, 4, 0]));
+
+        // vec(A X B) = (Bᵀ ⊗ A) vec(X)
+        let (a, b) = (mx!([1.0, 2.0][3.0, 4.0]), mx!([2.0, 0.0][1.0, 3.0]));
+        let x = mx!([5.0, -1.0][0.5, 2.0]);
+        let lhs = a.multiply(&x).multiply(&b).vectorize();
+        assert!(lhs == b.transpose().kron(&a).multiply(&x.vectorize()));
+
+        // Sylvester equation A X + X B = C
+        let c = a.multiply(&x) + x.multiply(&b);
+        let identity = MX::<f64, 2, 2>::identity();
+        let system = identity.kron(&a) + b.transpose().kron(&identity);
+        let solution = MX::<f64, 2, 2>::from_vectorized(&system.inverse().unwrap().multiply(&c.vectorize()));
+        assert!((0..4).all(|i| (solution[i] - x[i]).abs() < 1e-9));
+    }
+
     #[test]
     fn algebra() {
         let m = mx!([0.0, 1.0, 2.0, 3.0][4.0, 5.0, 6.0, 7.0]);
@@ -353,5 +857,145 @@ mod test {
         assert_eq!(ma.shape(), (2, 4));
         assert_eq!(mb.shape(), (4, 2));
         assert_eq!(r.shape(), (ma.shape().0, mb.shape().1));
+
+        let va = mx!(VR[1.0f32, 2.0, 3.0]);
+        let vb = mx!(VR[4.0f32, -5.0, 6.0]);
+        assert_eq!(va.dot(&vb), 12.0);
+        assert_eq!(va.transpose().dot(&vb.transpose()), 12.0);
+    }
+
+    #[test]
+    fn square() {
+        let vs = [mx!(VC[1.0, 2.0, 3.0]), mx!(VC[-1.0, 0.5, 0.0]), mx!(VC[4.0, -2.0, 1.0])];
+        let mut m = mx!((3, 3)(0.0));
+        vs.iter().for_each(|v| m.add_outer(v));
+        let sum = vs
+            .iter()
+            .fold(mx!((3, 3)(0.0)), |acc, v| acc + v.multiply(&v.transpose()));
+        assert!(m == sum);
+        assert!(m == m.transpose());
+    }
+
+    #[test]
+    fn identity() {
+        use crate::math::MX;
+        let m = mx!([2.0, -1.0, 0.5][4.0, 3.0, 7.0][-6.0, 1.0, 9.0]);
+        let identity = MX::<f64, 3, 3>::identity();
+        assert!(identity == mx!([1.0, 0.0, 0.0][0.0, 1.0, 0.0][0.0, 0.0, 1.0]));
+        assert!(identity.multiply(&m) == m && m.multiply(&identity) == m);
+        let m = mx!([1.5f32]);
+        assert!(MX::<f32, 1, 1>::identity().multiply(&m) == m);
+        let m = (0..16).map(|i| i as f64 - 8.0).collect::<MX<f64, 4, 4>>();
+        assert!(MX::<f64, 4, 4>::identity().multiply(&m) == m);
+    }
+
+    #[test]
+    fn structure() {
+        let diagonal = mx!([2.0f32, 0.0, 0.0][0.0, -1.0, 0.0][0.0, 1e-7, 3.0]);
+        let upper = mx!([1.0f32, 2.0, 3.0][0.0, 4.0, 5.0][0.0, 0.0, 6.0]);
+        let dense = mx!([1.0f32, 2.0, 3.0][4.0, 5.0, 6.0][7.0, 8.0, 9.0]);
+        assert!(diagonal.is_diagonal(1e-6) && diagonal.is_upper_triangular(1e-6) && diagonal.is_lower_triangular(1e-6));
+        assert!(!diagonal.is_diagonal(0.0));
+        assert!(upper.is_upper_triangular(0.0) && !upper.is_lower_triangular(0.0) && !upper.is_diagonal(0.0));
+        let lower = upper.transpose();
+        assert!(lower.is_lower_triangular(0.0) && !lower.is_upper_triangular(0.0) && !lower.is_diagonal(0.0));
+        assert!(!dense.is_diagonal(1e-6) && !dense.is_upper_triangular(1e-6) && !dense.is_lower_triangular(1e-6));
+        assert!(mx!([5.0f32]).is_diagonal(0.0));
+    }
+
+    #[test]
+    fn trace() {
+        use crate::math::MX;
+        assert_eq!(mx!([6.0, 1.0, 1.0][4.0, -2.0, 5.0][2.0, 8.0, 7.5]).trace(), 11.5);
+        assert_eq!(MX::<f64, 3, 3>::identity().trace(), 3.0);
+        assert_eq!(MX::<f32, 5, 5>::identity().trace(), 5.0);
+        assert_eq!(mx!([-2.0f32]).trace(), -2.0);
+    }
+
+    #[test]
+    fn determinant() {
+        use crate::math::MX;
+        assert_eq!(mx!([3.0, 8.0][4.0, 6.0]).determinant(), -14.0);
+        assert_eq!(mx!([0.0, 1.0][1.0, 0.0]).determinant(), -1.0);
+        let m = mx!([6.0f64, 1.0, 1.0][4.0, -2.0, 5.0][2.0, 8.0, 7.0]);
+        assert!((m.determinant() - -306.0).abs() < 1e-9);
+        let m = mx!([0.0f64, 2.0, 1.0][1.0, 0.0, 0.0][0.0, 0.0, 3.0]);
+        assert!((m.determinant() - -6.0).abs() < 1e-12);
+        let singular = mx!([1.0f64, 2.0, 3.0][4.0, 5.0, 6.0][7.0, 8.0, 9.0]);
+        assert!(singular.determinant().abs() < 1e-9);
+        assert_eq!(mx!([1.0, 2.0][2.0, 4.0]).determinant(), 0.0);
+        assert_eq!(MX::<f32, 4, 4>::identity().determinant(), 1.0);
+        assert_eq!(mx!([-2.5f32]).determinant(), -2.5);
+    }
+
+    #[test]
+    fn inverse() {
+        use crate::math::MX;
+        let close = |a: &MX<f64, 3, 3>, b: &MX<f64, 3, 3>| (0..9).all(|i| (a[i] - b[i]).abs() < 1e-9);
+        let (m, expected) = (mx!([4.0f64, 7.0][2.0, 6.0]), mx!([0.6, -0.7][-0.2, 0.4]));
+        let inverse = m.inverse().unwrap();
+        assert!((0..4).all(|i| (inverse[i] - expected[i]).abs() < 1e-12));
+        let m = mx!([0.0, 2.0, 1.0][1.0, 0.0, 3.0][4.0, -1.0, 2.0]);
+        let identity = MX::<f64, 3, 3>::identity();
+        assert!(close(&m.inverse().unwrap().multiply(&m), &identity));
+        assert!(close(&m.multiply(&m.inverse().unwrap()), &identity));
+        assert!(mx!([1.0, 2.0, 3.0][4.0, 5.0, 6.0][7.0, 8.0, 9.0]).inverse().is_none());
+        assert!(mx!([1.0, 2.0][1.0, 2.0 + 1e-12]).inverse().is_none());
+        assert!(mx!((2, 2)(0.0f32)).inverse().is_none());
+        assert!(mx!([1.0, f32::NAN][0.0, 1.0]).inverse().is_none());
+        let inverse = mx!([1e-9f64, 0.0][0.0, 1e-9]).inverse().unwrap();
+        assert!((0..4).all(|i| (inverse[i] - mx!([1e9, 0.0][0.0, 1e9])[i]).abs() < 1e-3));
+    }
+
+    #[test]
+    fn pseudo_inverse() {
+        use crate::math::MX;
+        let m = mx!([0.0f64, 2.0, 1.0][1.0, 0.0, 3.0][4.0, -1.0, 2.0]);
+        let (pseudo, inverse) = (m.pseudo_inverse().unwrap(), m.inverse().unwrap());
+        assert!((0..9).all(|i| (pseudo[i] - inverse[i]).abs() < 1e-9));
+        // least squares line y = a + b x through (0, 1), (1, 3), (2, 5), (3, 8)
+        let a = mx!([1.0f64, 0.0][1.0, 1.0][1.0, 2.0][1.0, 3.0]);
+        let y = mx!(VC[1.0, 3.0, 5.0, 8.0]);
+        let x = a.pseudo_inverse().unwrap().multiply(&y);
+        assert!((x[0] - 0.8).abs() < 1e-9 && (x[1] - 2.3).abs() < 1e-9);
+        // the residual is orthogonal to the columns
+        let normal = a.transpose().multiply(&(a.multiply(&x) - y));
+        assert!((0..2).all(|i| normal[i].abs() < 1e-9));
+        let wide = mx!([1.0f64, 1.0]).pseudo_inverse().unwrap();
+        assert!((0..2).all(|i| (wide[i] - 0.5).abs() < 1e-12));
+        assert!(mx!([1.0f64, 2.0][2.0, 4.0][3.0, 6.0]).pseudo_inverse().is_none());
+    }
+
+    #[test]
+    fn exp() {
+        use crate::math::MX;
+        let close = |a: &MX<f64, 2, 2>, b: &MX<f64, 2, 2>, eps: f64| (0..4).all(|i| (a[i] - b[i]).abs() <= eps);
+        assert!(close(&mx!((2, 2)(0.0)).exp(), &MX::identity(), 0.0));
+        let (a, b) = (1.5f64, -7.0f64);
+        let diagonal = mx!([a, 0.0][0.0, b]).exp();
+        assert!(close(&diagonal, &mx!([a.exp(), 0.0][0.0, b.exp()]), 1e-12));
+        // rotation generator, x(t) rotates around the origin with angular velocity theta
+        let theta = 2.5f64;
+        let rotation = mx!([0.0, -theta][theta, 0.0]).exp();
+        let (sin, cos) = theta.sin_cos();
+        assert!(close(&rotation, &mx!([cos, -sin][sin, cos]), 1e-12));
+        // nilpotent, the series is finite
+        let nilpotent = mx!([0.0, 1.0][0.0, 0.0]).exp();
+        assert!(close(&nilpotent, &mx!([1.0, 1.0][0.0, 1.0]), 1e-12));
+        let large = mx!([10.0f32, 0.0][0.0, -2.0]).exp();
+        assert!((large[0] / 10f32.exp() - 1.0).abs() < 1e-4 && (large[3] - (-2f32).exp()).abs() < 1e-5);
+        assert!(mx!([f32::INFINITY, 0.0][0.0, 1.0]).exp().contains_nan());
+        assert!(mx!([f32::NAN, 0.0][0.0, 1.0]).exp().contains_nan());
+    }
+
+    #[test]
+    fn strassen() {
+        use crate::math::MX;
+        let a = (0..64).map(|i| (i * 7 % 13) as f64 - 6.0).collect::<MX<f64, 8, 8>>();
+        let b = (0..64).map(|i| (i * 5 % 11) as f64 * 0.5).collect::<MX<f64, 8, 8>>();
+        let (naive, fast) = (a.multiply(&b), a.multiply_strassen(&b));
+        assert!((0..64).all(|i| (naive[i] - fast[i]).abs() < 1e-9));
+        let a = mx!([1.0, 2.0, 3.0][4.0, 5.0, 6.0][7.0, 8.0, 9.0]);
+        assert!(a.multiply_strassen(&a) == a.multiply(&a));
     }
 }
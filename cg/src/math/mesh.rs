@@ -1 +1,48 @@
-pub type Triangle<const D: usize> = (super::VR<f32, D>, super::VR<f32, D>, super::VR<f32, D>);
+#![allow(unused)]
+use super::{V2, VR};
+
+pub type Triangle<const D: usize> = (VR<f32, D>, VR<f32, D>, VR<f32, D>);
+
+/// Indexed triangle mesh, vertex `i` has position `positions[i]` and texture coordinates `uvs[i]`.
+///
+/// Indices are counter-clockwise vertex triples, see `Ray::intersect_triangle`.
+pub struct Mesh {
+    pub positions: Vec<VR<f32, 3>>,
+    pub uvs: Vec<V2<f32>>,
+    pub indices: Vec<[u32; 3]>,
+}
+
+impl Mesh {
+    /// Return the vertex positions of triangle `i`.
+    pub fn triangle(&self, i: usize) -> Triangle<3> {
+        let [a, b, c] = self.indices[i].map(|index| self.positions[index as usize]);
+        (a, b, c)
+    }
+
+    /// Return the vertex texture coordinates of triangle `i`.
+    pub fn triangle_uvs(&self, i: usize) -> [V2<f32>; 3] {
+        self.indices[i].map(|index| self.uvs[index as usize])
+    }
+}
+
+mod test {
+    use crate::math::{mx, Mesh, V2};
+
+    #[test]
+    fn triangles() {
+        // a unit quad split along its diagonal
+        let mesh = Mesh {
+            positions: vec![
+                mx!(VR[0.0, 0.0, 0.0]),
+                mx!(VR[1.0, 0.0, 0.0]),
+                mx!(VR[1.0, 1.0, 0.0]),
+                mx!(VR[0.0, 1.0, 0.0]),
+            ],
+            uvs: vec![V2(0.0, 0.0), V2(1.0, 0.0), V2(1.0, 1.0), V2(0.0, 1.0)],
+            indices: vec![[0, 1, 2], [0, 2, 3]],
+        };
+        let (a, b, c) = mesh.triangle(1);
+        assert!(a == mesh.positions[0] && b == mesh.positions[2] && c == mesh.positions[3]);
+        assert_eq!(mesh.triangle_uvs(0), [V2(0.0, 0.0), V2(1.0, 0.0), V2(1.0, 1.0)]);
+    }
+}
@@ -1,4 +1,20 @@
+pub mod aabb;
+pub mod angle;
+pub mod color;
+pub mod frustum;
+pub mod interval;
 pub mod matrix;
 pub mod mesh;
+pub mod rect;
+pub mod sampling;
+pub mod stats;
+pub mod transform;
+pub mod vector;
+pub use aabb::*;
+pub use angle::*;
+pub use frustum::*;
+pub use interval::*;
 pub use matrix::*;
 pub use mesh::*;
+pub use rect::*;
+pub use vector::*;
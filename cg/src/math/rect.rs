@@ -0,0 +1,96 @@
+#![allow(unused)]
+use crate::math::V2;
+
+/// Axis aligned 2D rectangle, `min` is the bottom-left corner and `max` is the top-right corner.
+///
+/// Edges are inclusive, rects that only touch at an edge intersect.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct Rect {
+    pub min: V2<f32>,
+    pub max: V2<f32>,
+}
+
+impl Rect {
+    pub fn new(min: V2<f32>, max: V2<f32>) -> Rect {
+        Rect { min, max }
+    }
+
+    /// Return true if any of `min` components is greater than the respective `max` component.
+    pub fn is_empty(&self) -> bool {
+        self.min.0 > self.max.0 || self.min.1 > self.max.1
+    }
+
+    pub fn center(&self) -> V2<f32> {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> V2<f32> {
+        self.max - self.min
+    }
+
+    pub fn contains(&self, point: V2<f32>) -> bool {
+        (self.min.0..=self.max.0).contains(&point.0) && (self.min.1..=self.max.1).contains(&point.1)
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// Return the smallest rect containing both rects.
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            min: V2(self.min.0.min(other.min.0), self.min.1.min(other.min.1)),
+            max: V2(self.max.0.max(other.max.0), self.max.1.max(other.max.1)),
+        }
+    }
+
+    /// Return the overlapping region of both rects, or `None` if they do not overlap or any of them is empty.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if self.is_empty() || other.is_empty() {
+            return None;
+        }
+        let rect = Rect {
+            min: V2(self.min.0.max(other.min.0), self.min.1.max(other.min.1)),
+            max: V2(self.max.0.min(other.max.0), self.max.1.min(other.max.1)),
+        };
+        (!rect.is_empty()).then_some(rect)
+    }
+}
+
+mod test {
+    use crate::math::{Rect, V2};
+
+    #[test]
+    fn measure() {
+        let r = Rect::new(V2(1.0, 2.0), V2(3.0, 6.0));
+        assert_eq!(r.center(), V2(2.0, 4.0));
+        assert_eq!(r.size(), V2(2.0, 4.0));
+        assert!(!r.is_empty());
+        assert!(Rect::new(V2(1.0, 0.0), V2(0.0, 1.0)).is_empty());
+    }
+
+    #[test]
+    fn contains() {
+        let r = Rect::new(V2(0.0, 0.0), V2(2.0, 2.0));
+        assert!(r.contains(V2(1.0, 1.0)));
+        assert!(r.contains(V2(0.0, 2.0)));
+        assert!(!r.contains(V2(2.5, 1.0)));
+        assert!(!r.contains(V2(1.0, -0.1)));
+    }
+
+    #[test]
+    fn overlap() {
+        let a = Rect::new(V2(0.0, 0.0), V2(2.0, 2.0));
+        let b = Rect::new(V2(1.0, 1.0), V2(3.0, 3.0));
+        let c = Rect::new(V2(5.0, 5.0), V2(6.0, 6.0));
+        assert!(a.intersects(&b));
+        assert!(!a.intersects(&c));
+        assert_eq!(a.intersection(&b), Some(Rect::new(V2(1.0, 1.0), V2(2.0, 2.0))));
+        assert_eq!(a.intersection(&c), None);
+        assert_eq!(a.union(&b), Rect::new(V2(0.0, 0.0), V2(3.0, 3.0)));
+        assert_eq!(a.union(&c), Rect::new(V2(0.0, 0.0), V2(6.0, 6.0)));
+        let inverted = Rect::new(V2(2.0, 2.0), V2(0.0, 0.0));
+        assert_eq!(a.intersection(&inverted), None);
+        assert_eq!(inverted.intersection(&a), None);
+    }
+}
@@ -0,0 +1,180 @@
+//! Low-discrepancy sample sequences, a random number generator, and mappings of samples to directions.
+//!
+//! Consecutive samples of these sequences are spread evenly over the unit interval or square, supersampling with them
+//! converges faster than with random jitter. `Rng` is for samples without an index, like the choices made along a
+//! path.
+#![allow(unused)]
+use crate::math::{V2, V3};
+
+/// Largest `f32` below one.
+const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+/// Return the `index`-th element of the Halton sequence in `base` (the radical inverse of `index`), in `[0, 1)`.
+///
+/// The digits of `index` in `base` are mirrored around the decimal point, e.g. `6 = 110₂` maps to `0.011₂ = 0.375`.
+/// `base` must be at least 2, coprime bases give independent sequences for each dimension.
+pub fn halton(index: u32, base: u32) -> f32 {
+    let (mut index, inv_base) = (index, 1.0 / base as f64);
+    let (mut res, mut scale) = (0.0, inv_base);
+    while index > 0 {
+        res += (index % base) as f64 * scale;
+        index /= base;
+        scale *= inv_base;
+    }
+    (res as f32).min(ONE_MINUS_EPSILON)
+}
+
+/// Return the `index`-th point of the 2d Halton sequence with bases 2 and 3, in `[0, 1)²`.
+pub fn halton_2d(index: u32) -> V2<f32> {
+    V2(halton(index, 2), halton(index, 3))
+}
+
+/// Permuted congruential generator (PCG32), a small and fast generator with good statistical quality.
+///
+/// Generators created with the same seed return the same numbers.
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Increment of the underlying linear congruential generator, any odd value works.
+    const INCREMENT: u64 = 1442695040888963407;
+
+    pub fn new(seed: u64) -> Rng {
+        let mut rng = Rng {
+            state: seed.wrapping_add(Rng::INCREMENT),
+        };
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.state = state.wrapping_mul(6364136223846793005).wrapping_add(Rng::INCREMENT);
+        let xorshifted = ((state >> 18 ^ state) >> 27) as u32;
+        xorshifted.rotate_right((state >> 59) as u32)
+    }
+
+    /// Return a uniform number in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// Return a uniform point in `[0, 1)²`.
+    pub fn next_v2(&mut self) -> V2<f32> {
+        V2(self.next_f32(), self.next_f32())
+    }
+}
+
+/// Map the unit square `sample` to a direction in the hemisphere around the unit `normal`, with a density
+/// proportional to the cosine of the angle to the normal.
+///
+/// The sample is mapped to the unit disk and projected up to the hemisphere (Malley's method), so evenly spread
+/// samples give evenly spread directions.
+pub fn cosine_hemisphere(normal: V3<f32>, sample: V2<f32>) -> V3<f32> {
+    let (radius, angle) = (sample.0.sqrt(), 2.0 * std::f32::consts::PI * sample.1);
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    let height = (1.0 - sample.0).max(0.0).sqrt();
+    tangent * (radius * angle.cos()) + bitangent * (radius * angle.sin()) + normal * height
+}
+
+/// Return two unit vectors perpendicular to the unit `normal` and to each other.
+///
+/// The basis is continuous except across the `z = 0` plane and has no singularity (Duff et al. 2017).
+pub fn orthonormal_basis(normal: V3<f32>) -> (V3<f32>, V3<f32>) {
+    let n = normal;
+    let sign = 1.0f32.copysign(n.2);
+    let a = -1.0 / (sign + n.2);
+    let b = n.0 * n.1 * a;
+    (
+        V3(1.0 + sign * n.0 * n.0 * a, sign * b, -sign * n.0),
+        V3(b, sign + n.1 * n.1 * a, -n.1),
+    )
+}
+
+mod test {
+    use crate::math::sampling::{cosine_hemisphere, halton, halton_2d, orthonormal_basis, Rng};
+    use crate::math::{Vx, VxF, V3};
+
+    /// Largest difference between the number of points in a cell of a 4x4 grid and the expected number.
+    fn deviation(points: &[(f32, f32)]) -> f32 {
+        let mut cells = [0; 16];
+        points
+            .iter()
+            .for_each(|&(x, y)| cells[(x * 4.0) as usize * 4 + (y * 4.0) as usize] += 1);
+        let expected = points.len() as f32 / 16.0;
+        cells
+            .iter()
+            .fold(0.0, |max, &count| f32::max(max, (count as f32 - expected).abs()))
+    }
+
+    #[test]
+    fn sequence() {
+        let expected = [0.0, 0.5, 0.25, 0.75, 0.125, 0.625, 0.375, 0.875];
+        assert!((0..8).all(|i| halton(i, 2) == expected[i as usize]));
+        assert!((halton(1, 3) - 1.0 / 3.0).abs() < 1e-7 && (halton(5, 3) - 7.0 / 9.0).abs() < 1e-7);
+        for i in (0..10000).chain([u32::MAX - 1, u32::MAX]) {
+            let p = halton_2d(i);
+            assert!((0.0..1.0).contains(&p.0) && (0.0..1.0).contains(&p.1));
+            assert_eq!(p, halton_2d(i));
+        }
+        assert!(halton(u32::MAX, 2) < 1.0);
+    }
+
+    #[test]
+    fn discrepancy() {
+        let points = (0..48).map(|i| (halton_2d(i).0, halton_2d(i).1)).collect::<Vec<_>>();
+        // linear congruential generator as the random reference
+        let mut state = 12345u32;
+        let mut random = || {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            (state >> 8) as f32 / (1 << 24) as f32
+        };
+        let random = (0..48).map(|_| (random(), random())).collect::<Vec<_>>();
+        assert!(deviation(&points) <= 1.0);
+        assert!(deviation(&points) < deviation(&random));
+    }
+
+    #[test]
+    fn rng() {
+        let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+        assert!((0..100).all(|_| a.next_u32() == b.next_u32()));
+        assert_ne!(Rng::new(7).next_u32(), Rng::new(8).next_u32());
+        let mut rng = Rng::new(0);
+        let samples = (0..4096).map(|_| rng.next_f32()).collect::<Vec<_>>();
+        assert!(samples.iter().all(|x| (0.0..1.0).contains(x)));
+        let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+        assert!((mean - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn basis() {
+        for normal in [
+            V3(0.0, 0.0, 1.0),
+            V3(0.0, 0.0, -1.0),
+            V3(1.0, 2.0, -3.0).norm(),
+            V3(0.6, 0.0, 0.8),
+        ] {
+            let (tangent, bitangent) = orthonormal_basis(normal);
+            assert!((tangent.mag() - 1.0).abs() < 1e-6 && (bitangent.mag() - 1.0).abs() < 1e-6);
+            assert!(tangent.dot(&normal).abs() < 1e-6 && bitangent.dot(&normal).abs() < 1e-6);
+            assert!(tangent.dot(&bitangent).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn hemisphere() {
+        let normal = V3(1.0, -1.0, 2.0).norm();
+        let directions = (0..1024)
+            .map(|i| cosine_hemisphere(normal, halton_2d(i)))
+            .collect::<Vec<_>>();
+        assert!(directions
+            .iter()
+            .all(|d| (d.mag() - 1.0).abs() < 1e-5 && d.dot(&normal) >= -1e-6));
+        // the mean cosine of a cosine weighted hemisphere is 2/3
+        let mean = directions.iter().map(|d| d.dot(&normal)).sum::<f32>() / directions.len() as f32;
+        assert!((mean - 2.0 / 3.0).abs() < 1e-2);
+        assert!(cosine_hemisphere(normal, halton_2d(0)).approx_eq(&normal, 1e-6));
+    }
+}
@@ -0,0 +1,62 @@
+//! Statistics over sets of points.
+#![allow(unused)]
+use crate::math::{mx, Vx, MX, V3};
+
+/// Average of `points`, or the origin if `points` is empty.
+pub fn mean(points: &[V3<f32>]) -> V3<f32> {
+    if points.is_empty() {
+        return V3::default();
+    }
+    points.iter().fold(V3::default(), |acc, &p| acc + p) / points.len() as f32
+}
+
+/// Population covariance matrix of `points`, or zeros if `points` is empty.
+///
+/// The eigenvectors of the covariance are the principal axes of the points, the eigenvector with the largest
+/// eigenvalue is the direction of largest spread.
+pub fn covariance(points: &[V3<f32>]) -> MX<f32, 3, 3> {
+    let mut cov = mx!((3, 3)(0.0));
+    if points.is_empty() {
+        return cov;
+    }
+    let mean = mean(points);
+    for &p in points {
+        let d = p - mean;
+        for i in 0..3 {
+            for j in 0..3 {
+                cov[3 * i + j] += d[i] * d[j];
+            }
+        }
+    }
+    cov / points.len() as f32
+}
+
+mod test {
+    use crate::math::stats::{covariance, mean};
+    use crate::math::{mx, V3};
+
+    #[test]
+    fn empty() {
+        assert_eq!(mean(&[]), V3(0.0, 0.0, 0.0));
+        assert!(covariance(&[]) == mx!((3, 3)(0.0)));
+    }
+
+    #[test]
+    fn principal_axis() {
+        let points: Vec<_> = (-10..=10)
+            .map(|i| V3(i as f32 + 3.0, (i * 7 % 5) as f32 * 0.1, (i * 3 % 4) as f32 * 0.1 - 1.0))
+            .collect();
+        let m = mean(&points);
+        assert!((m.0 - 3.0).abs() < 1e-5);
+        let cov = covariance(&points);
+        assert!(cov == cov.transpose());
+        // power iteration converges to the eigenvector with the largest eigenvalue
+        let mut axis = mx!(VC[1.0, 1.0, 1.0]);
+        for _ in 0..32 {
+            axis = cov.multiply(&axis);
+            axis = axis / axis.dot(&axis).sqrt();
+        }
+        assert!(axis[0].abs() > 0.999);
+        assert!(axis[1].abs() < 0.05 && axis[2].abs() < 0.05);
+    }
+}
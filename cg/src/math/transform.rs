@@ -0,0 +1,357 @@
+//! 3d transformation matrices.
+//!
+//! Matrices transform column vectors (`p' = m * p`), points have `w = 1` and directions have `w = 0`.
+#![allow(unused)]
+use crate::math::{mx, Vx, VxF, MX, V3, V4};
+
+/// Rotations with `cos(pitch)` below `GIMBAL_EPSILON` are treated as gimbal locked by `to_euler_xyz`.
+const GIMBAL_EPSILON: f32 = 1e-6;
+
+impl MX<f32, 4, 4> {
+    /// Create a rotation matrix from euler angles in radians, `V3(roll, pitch, yaw)`.
+    ///
+    /// The rotation is applied around the fixed `X` axis first (roll), then `Y` (pitch), then `Z` (yaw), which is
+    /// the matrix `Rz(yaw) * Ry(pitch) * Rx(roll)`.
+    pub fn from_euler_xyz(angles: V3<f32>) -> MX<f32, 4, 4> {
+        let (sa, ca) = angles.0.sin_cos();
+        let (sb, cb) = angles.1.sin_cos();
+        let (sc, cc) = angles.2.sin_cos();
+        mx!(
+            [cc * cb, cc * sb * sa - sc * ca, cc * sb * ca + sc * sa, 0.0]
+            [sc * cb, sc * sb * sa + cc * ca, sc * sb * ca - cc * sa, 0.0]
+            [-sb, cb * sa, cb * ca, 0.0]
+            [0.0, 0.0, 0.0, 1.0]
+        )
+    }
+
+    /// Create a right-handed perspective projection, the camera looks towards `-Z` with `Y` up.
+    ///
+    /// `fov_y` is the vertical field of view in radians and `aspect` is width over height. View depths `near` to `far`
+    /// map to the WebGPU clip space depth range `0..1`, `w` is the view distance `-z`. An infinite `far` uses the
+    /// reversed infinite form instead, mapping `near` to `1` and infinity to `0`, which needs a `0` depth clear value
+    /// and the `greater` depth comparison. `near` must be positive and `aspect` nonzero, otherwise the matrix contains
+    /// infinite or NaN elements, debug builds panic.
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> MX<f32, 4, 4> {
+        debug_assert!(
+            near > 0.0 && aspect != 0.0,
+            "invalid perspective near {near} or aspect {aspect}"
+        );
+        let f = 1.0 / (fov_y / 2.0).tan();
+        let (a, b) = match far.is_infinite() {
+            true => (0.0, near),
+            false => (far / (near - far), near * far / (near - far)),
+        };
+        mx!(
+            [f / aspect, 0.0, 0.0, 0.0]
+            [0.0, f, 0.0, 0.0]
+            [0.0, 0.0, a, b]
+            [0.0, 0.0, -1.0, 0.0]
+        )
+    }
+
+    /// Create a right-handed orthographic projection of the view box, the camera looks towards `-Z` with `Y` up.
+    ///
+    /// `left..right` and `bottom..top` map to `-1..1`, view depths `near` to `far` (`z = -near` to `z = -far`) map to
+    /// the WebGPU clip space depth range `0..1`, and `w` stays `1`. Empty ranges produce infinite or NaN elements,
+    /// debug builds panic.
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> MX<f32, 4, 4> {
+        debug_assert!(
+            left != right && bottom != top && near != far,
+            "invalid orthographic box {left}..{right}, {bottom}..{top}, {near}..{far}"
+        );
+        let (width, height, depth) = (right - left, top - bottom, near - far);
+        mx!(
+            [2.0 / width, 0.0, 0.0, -(right + left) / width]
+            [0.0, 2.0 / height, 0.0, -(top + bottom) / height]
+            [0.0, 0.0, 1.0 / depth, near / depth]
+            [0.0, 0.0, 0.0, 1.0]
+        )
+    }
+
+    /// Create a right-handed view matrix for a camera at `eye` looking at `target`, the camera looks towards `-Z` with
+    /// `Y` up, see `perspective`.
+    ///
+    /// `up` only needs to be in the plane of the camera up and view directions, it is not required to be unit length
+    /// or perpendicular to the view direction. If `eye` equals `target` the camera looks towards `-Z`, and if `up` is
+    /// parallel to the view direction the world `Y` axis is used instead, or `Z` if the camera looks along `Y`.
+    pub fn look_at(eye: V3<f32>, target: V3<f32>, up: V3<f32>) -> MX<f32, 4, 4> {
+        let forward = (target - eye).try_norm().unwrap_or(V3(0.0, 0.0, -1.0));
+        let fallback = match forward.1.abs() < 0.99 {
+            true => V3(0.0, 1.0, 0.0),
+            false => V3(0.0, 0.0, 1.0),
+        };
+        let right = forward
+            .cross(&up)
+            .try_norm()
+            .unwrap_or_else(|| forward.cross(&fallback).norm());
+        let up = right.cross(&forward);
+        mx!(
+            [right.0, right.1, right.2, -right.dot(&eye)]
+            [up.0, up.1, up.2, -up.dot(&eye)]
+            [-forward.0, -forward.1, -forward.2, forward.dot(&eye)]
+            [0.0, 0.0, 0.0, 1.0]
+        )
+    }
+
+    /// Extract the euler angles `V3(roll, pitch, yaw)` of the rotation part of the matrix, see `from_euler_xyz`.
+    ///
+    /// Pitch is in `[-π/2, π/2]`, roll and yaw are in `[-π, π]`. When the pitch is `±π/2` (gimbal lock), roll and yaw
+    /// rotate around the same axis and only their combination is defined, yaw is then set to zero.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_euler_xyz(&self) -> V3<f32> {
+        let m = |i: usize, j: usize| self[4 * i + j];
+        let pitch = (-m(2, 0)).clamp(-1.0, 1.0).asin();
+        if pitch.cos() > GIMBAL_EPSILON {
+            return V3(m(2, 1).atan2(m(2, 2)), pitch, m(1, 0).atan2(m(0, 0)));
+        }
+        let sign = pitch.signum();
+        V3((sign * m(0, 1)).atan2(m(1, 1)), pitch, 0.0)
+    }
+}
+
+/// Interpolate the affine TRS transforms `a` (`t = 0`) and `b` (`t = 1`) component-wise.
+///
+/// Both matrices are decomposed into translation, rotation, and scale, translations and scales are interpolated
+/// linearly and rotations with `slerp`, then recomposed as `T * R * S`. Element-wise interpolation of the matrices
+/// instead shrinks and shears intermediate rotations. Shear in the inputs is not preserved, and transforms with a
+/// zero scale axis have no defined rotation, which is replaced by the identity.
+pub fn lerp_transform(a: &MX<f32, 4, 4>, b: &MX<f32, 4, 4>, t: f32) -> MX<f32, 4, 4> {
+    let (translation_a, rotation_a, scale_a) = decompose(a);
+    let (translation_b, rotation_b, scale_b) = decompose(b);
+    compose(
+        translation_a.lerp(&translation_b, t),
+        slerp(rotation_a, rotation_b, t),
+        scale_a.lerp(&scale_b, t),
+    )
+}
+
+/// Split an affine transform into translation, rotation quaternion `V4(x, y, z, w)`, and scale.
+///
+/// Reflections are returned as a negative `x` scale.
+fn decompose(m: &MX<f32, 4, 4>) -> (V3<f32>, V4<f32>, V3<f32>) {
+    let column = |j: usize| V3(m[j], m[4 + j], m[8 + j]);
+    let (x, y, z) = (column(0), column(1), column(2));
+    let reflection = x.dot(&y.cross(&z)) < 0.0;
+    let scale = V3(if reflection { -x.mag() } else { x.mag() }, y.mag(), z.mag());
+    let rotation = match [scale.0, scale.1, scale.2].iter().all(|s| s.abs() > f32::EPSILON) {
+        true => to_quaternion([x / scale.0, y / scale.1, z / scale.2]),
+        false => V4(0.0, 0.0, 0.0, 1.0),
+    };
+    (column(3), rotation, scale)
+}
+
+/// Create the transform `T * R * S`, see `decompose`.
+fn compose(translation: V3<f32>, rotation: V4<f32>, scale: V3<f32>) -> MX<f32, 4, 4> {
+    let V4(x, y, z, w) = rotation;
+    let r = [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+        ],
+        [
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+        ],
+        [
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ];
+    let s = [scale.0, scale.1, scale.2];
+    let t = [translation.0, translation.1, translation.2];
+    let mut m = MX::<f32, 4, 4>::identity();
+    for i in 0..3 {
+        for j in 0..3 {
+            m[4 * i + j] = r[i][j] * s[j];
+        }
+        m[4 * i + 3] = t[i];
+    }
+    m
+}
+
+/// Convert the rotation matrix with unit `columns` to a unit quaternion, from the largest of `w`, `x`, `y`, and `z`
+/// to avoid dividing by small values.
+fn to_quaternion(columns: [V3<f32>; 3]) -> V4<f32> {
+    let r = |i: usize, j: usize| columns[j][i];
+    let trace = r(0, 0) + r(1, 1) + r(2, 2);
+    let q = if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        V4(
+            (r(2, 1) - r(1, 2)) / s,
+            (r(0, 2) - r(2, 0)) / s,
+            (r(1, 0) - r(0, 1)) / s,
+            s / 4.0,
+        )
+    } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+        let s = (1.0 + r(0, 0) - r(1, 1) - r(2, 2)).sqrt() * 2.0;
+        V4(
+            s / 4.0,
+            (r(0, 1) + r(1, 0)) / s,
+            (r(0, 2) + r(2, 0)) / s,
+            (r(2, 1) - r(1, 2)) / s,
+        )
+    } else if r(1, 1) > r(2, 2) {
+        let s = (1.0 + r(1, 1) - r(0, 0) - r(2, 2)).sqrt() * 2.0;
+        V4(
+            (r(0, 1) + r(1, 0)) / s,
+            s / 4.0,
+            (r(1, 2) + r(2, 1)) / s,
+            (r(0, 2) - r(2, 0)) / s,
+        )
+    } else {
+        let s = (1.0 + r(2, 2) - r(0, 0) - r(1, 1)).sqrt() * 2.0;
+        V4(
+            (r(0, 2) + r(2, 0)) / s,
+            (r(1, 2) + r(2, 1)) / s,
+            s / 4.0,
+            (r(1, 0) - r(0, 1)) / s,
+        )
+    };
+    q.norm()
+}
+
+/// Spherical interpolation of unit quaternions along the shortest arc.
+///
+/// Nearly equal quaternions are interpolated linearly and normalized, where `sin` of the angle is too small.
+fn slerp(a: V4<f32>, b: V4<f32>, t: f32) -> V4<f32> {
+    let (b, cos) = match a.dot(&b) {
+        cos if cos < 0.0 => (-b, -cos),
+        cos => (b, cos),
+    };
+    if cos > 1.0 - 1e-4 {
+        return a.lerp(&b, t).norm();
+    }
+    let angle = cos.acos();
+    (a * ((1.0 - t) * angle).sin() + b * (t * angle).sin()) / angle.sin()
+}
+
+mod test {
+    use crate::math::transform::{compose, lerp_transform};
+    use crate::math::{Vx, VxF, MX, V3, V4};
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn euler() {
+        for angles in [
+            V3(0.0, 0.0, 0.0),
+            V3(0.3, -0.7, 1.2),
+            V3(-2.5, 1.2, -3.0),
+            V3(1.0, 0.2, 2.9),
+        ] {
+            let m = MX::from_euler_xyz(angles);
+            assert!((m.to_euler_xyz() - angles).mag() < 1e-4);
+        }
+        let m = MX::from_euler_xyz(V3(0.0, 0.0, FRAC_PI_2));
+        let x = m.multiply(&crate::math::mx!(VC[1.0, 0.0, 0.0, 0.0]));
+        assert!((0..4).all(|i| (x[i] - [0.0, 1.0, 0.0, 0.0][i]).abs() < 1e-6));
+    }
+
+    #[test]
+    fn gimbal_lock() {
+        for angles in [V3(0.4, FRAC_PI_2, 0.3), V3(-1.0, -FRAC_PI_2, 0.5)] {
+            let m = MX::from_euler_xyz(angles);
+            let res = m.to_euler_xyz();
+            assert!(!res.0.is_nan() && !res.1.is_nan() && !res.2.is_nan());
+            assert_eq!(res.2, 0.0);
+            // the same rotation, even if the angles differ
+            let r = MX::from_euler_xyz(res);
+            assert!((0..16).all(|i| (r[i] - m[i]).abs() < 1e-4));
+        }
+    }
+
+    #[test]
+    fn lerp_rotation() {
+        let (a, b) = (
+            MX::from_euler_xyz(V3(0.0, 0.0, 0.2)),
+            MX::from_euler_xyz(V3(0.0, 0.0, 1.4)),
+        );
+        let res = lerp_transform(&a, &b, 0.5);
+        let expected = MX::from_euler_xyz(V3(0.0, 0.0, 0.8));
+        assert!((0..16).all(|i| (res[i] - expected[i]).abs() < 1e-5));
+        // columns stay orthonormal, element-wise interpolation gives shorter columns
+        let column = |m: &MX<f32, 4, 4>, j: usize| V3(m[j], m[4 + j], m[8 + j]);
+        assert!((column(&res, 0).mag() - 1.0).abs() < 1e-5 && column(&res, 0).dot(&column(&res, 1)).abs() < 1e-5);
+        let naive = a * 0.5 + b * 0.5;
+        assert!(column(&naive, 0).mag() < 0.9);
+        let (a, b) = (lerp_transform(&a, &b, 0.0), lerp_transform(&a, &b, 1.0));
+        assert!((0..16).all(|i| (a[i] - MX::from_euler_xyz(V3(0.0, 0.0, 0.2))[i]).abs() < 1e-5));
+        assert!((0..16).all(|i| (b[i] - MX::from_euler_xyz(V3(0.0, 0.0, 1.4))[i]).abs() < 1e-5));
+    }
+
+    #[test]
+    fn lerp_trs() {
+        let (sin, cos) = std::f32::consts::FRAC_PI_4.sin_cos();
+        let z = |angle: f32| V4(0.0, 0.0, (angle / 2.0).sin(), (angle / 2.0).cos());
+        let a = compose(V3(0.0, 0.0, 0.0), z(0.0), V3(1.0, 1.0, 1.0));
+        let b = compose(V3(2.0, 4.0, -6.0), z(FRAC_PI_2), V3(3.0, 3.0, 3.0));
+        let expected = compose(V3(1.0, 2.0, -3.0), z(FRAC_PI_2 / 2.0), V3(2.0, 2.0, 2.0));
+        let res = lerp_transform(&a, &b, 0.5);
+        assert!((0..16).all(|i| (res[i] - expected[i]).abs() < 1e-5));
+        assert!((res[0] - 2.0 * cos).abs() < 1e-5 && (res[4] - 2.0 * sin).abs() < 1e-5);
+        // reflections and the rotation opposite in the quaternion sign are interpolated too
+        let mirror = compose(V3(0.0, 0.0, 0.0), V4(0.0, 0.0, -1.0, 0.0), V3(-1.0, 1.0, 1.0));
+        let res = lerp_transform(&mirror, &mirror, 0.3);
+        assert!((0..16).all(|i| (res[i] - mirror[i]).abs() < 1e-5));
+    }
+
+    #[test]
+    fn perspective() {
+        let project = |m: &MX<f32, 4, 4>, p: [f32; 3]| m.multiply(&crate::math::mx!(VC[p[0], p[1], p[2], 1.0]));
+        let m = MX::perspective(FRAC_PI_2, 2.0, 0.5, 10.0);
+        let clip = project(&m, [0.0, 0.0, -0.5]);
+        assert!(clip[2].abs() < 1e-6 && (clip[3] - 0.5).abs() < 1e-6);
+        let clip = project(&m, [0.0, 0.0, -10.0]);
+        assert!((clip[2] / clip[3] - 1.0).abs() < 1e-6 && (clip[3] - 10.0).abs() < 1e-6);
+        // the top right corner of the view at distance 4, tan(fov / 2) = 1
+        let clip = project(&m, [8.0, 4.0, -4.0]);
+        assert!((clip[0] / clip[3] - 1.0).abs() < 1e-6 && (clip[1] / clip[3] - 1.0).abs() < 1e-6);
+        assert!((0.0..1.0).contains(&(clip[2] / clip[3])) && clip[3] == 4.0);
+        let infinite = MX::perspective(FRAC_PI_2, 1.0, 0.5, f32::INFINITY);
+        let clip = project(&infinite, [0.0, 0.0, -0.5]);
+        assert!((clip[2] / clip[3] - 1.0).abs() < 1e-6);
+        let clip = project(&infinite, [0.0, 0.0, -1e6]);
+        assert!(clip[2] / clip[3] < 1e-6 && clip[3] == 1e6);
+        assert!(infinite.is_finite());
+    }
+
+    #[test]
+    fn orthographic() {
+        let project = |m: &MX<f32, 4, 4>, p: [f32; 3]| m.multiply(&crate::math::mx!(VC[p[0], p[1], p[2], 1.0]));
+        let close = |a: crate::math::VC<f32, 4>, b: [f32; 4]| (0..4).all(|i| (a[i] - b[i]).abs() < 1e-6);
+        let m = MX::orthographic(-1.0, 3.0, -2.0, 0.5, 1.0, 5.0);
+        assert!(close(project(&m, [-1.0, -2.0, -1.0]), [-1.0, -1.0, 0.0, 1.0]));
+        assert!(close(project(&m, [3.0, 0.5, -5.0]), [1.0, 1.0, 1.0, 1.0]));
+        assert!(close(project(&m, [3.0, -2.0, -3.0]), [1.0, -1.0, 0.5, 1.0]));
+        assert!(close(project(&m, [1.0, -0.75, -1.0]), [0.0, 0.0, 0.0, 1.0]));
+        // a camera at z = 10 looking towards -Z moves the world by -10 in view space
+        let view = compose(V3(0.0, 0.0, -10.0), V4(0.0, 0.0, 0.0, 1.0), V3(1.0, 1.0, 1.0));
+        let view_projection = m.multiply(&view);
+        assert!(close(project(&view_projection, [3.0, 0.5, 5.0]), [1.0, 1.0, 1.0, 1.0]));
+        assert!(close(
+            project(&view_projection, [-1.0, -2.0, 9.0]),
+            [-1.0, -1.0, 0.0, 1.0]
+        ));
+    }
+
+    #[test]
+    fn look_at() {
+        let view = |m: &MX<f32, 4, 4>, p: [f32; 4]| m.multiply(&crate::math::mx!(VC[p[0], p[1], p[2], p[3]]));
+        let close = |a: crate::math::VC<f32, 4>, b: [f32; 4]| (0..4).all(|i| (a[i] - b[i]).abs() < 1e-6);
+        // looking at the origin from +X, the view direction is -X and right is -Z
+        let m = MX::look_at(V3(4.0, 0.0, 0.0), V3(0.0, 0.0, 0.0), V3(1.0, 2.0, 0.0));
+        assert!(close(view(&m, [0.0, 0.0, 0.0, 1.0]), [0.0, 0.0, -4.0, 1.0]));
+        assert!(close(view(&m, [0.0, 0.0, -1.0, 0.0]), [1.0, 0.0, 0.0, 0.0]));
+        assert!(close(view(&m, [0.0, 1.0, 0.0, 0.0]), [0.0, 1.0, 0.0, 0.0]));
+        // up parallel to the view direction falls back to Z up when looking along Y, eye equal to target looks to -Z
+        let m = MX::look_at(V3(0.0, 3.0, 0.0), V3(0.0, 0.0, 0.0), V3(0.0, 1.0, 0.0));
+        assert!(m.is_finite());
+        assert!(close(view(&m, [0.0, 0.0, 0.0, 1.0]), [0.0, 0.0, -3.0, 1.0]));
+        assert!(close(view(&m, [0.0, 0.0, 1.0, 0.0]), [0.0, 1.0, 0.0, 0.0]));
+        let m = MX::look_at(V3(1.0, 1.0, 1.0), V3(1.0, 1.0, 1.0), V3(0.0, 0.0, -1.0));
+        assert!(m.is_finite());
+        assert!(close(view(&m, [0.0, 0.0, -1.0, 0.0]), [0.0, 0.0, -1.0, 0.0]));
+    }
+}
@@ -0,0 +1,522 @@
+#![allow(unused)]
+use num_traits::{Float, Num};
+use std::cmp::Ordering;
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// `V2`, `V3`, and `V4` are small fixed size vectors for geometry code.
+///
+/// Unlike `VR` and `VC`, these are plain structs with named positional components (`V3(x, y, z)`).
+///
+/// utilities:
+/// - `Vx` trait for component-wise operations and algebra.
+/// - `VxF` trait for algebra that requires `Float` components.
+/// - 1-dimensional `Index` and `IndexMut`.
+/// - Arithmetic `std::ops` operators against vectors and scalars.
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct V2<T>(pub T, pub T);
+
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct V3<T>(pub T, pub T, pub T);
+
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct V4<T>(pub T, pub T, pub T, pub T);
+
+/// Component-wise vector operations.
+pub trait Vx<T: Num + Copy>:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<T, Output = Self> + Div<T, Output = Self>
+{
+    /// Number of components.
+    const D: usize;
+
+    /// Create a new vector applying `f` to each component.
+    fn map(self, f: impl Fn(T) -> T) -> Self;
+
+    /// Create a new vector applying `f` to each pair of components.
+    fn zip(self, other: Self, f: impl Fn(T, T) -> T) -> Self;
+
+    /// Fold components from first to last.
+    fn fold<A>(self, init: A, f: impl Fn(A, T) -> A) -> A;
+
+    /// Linear interpolation from `self` (`t = 0`) to `other` (`t = 1`).
+    ///
+    /// Components are weighted as `a * (1 - t) + b * t`, which returns both ends exactly, unlike `a + (b - a) * t`.
+    fn lerp(&self, other: &Self, t: T) -> Self {
+        self.zip(*other, |a, b| a * (T::one() - t) + b * t)
+    }
+
+    /// Dot product.
+    fn dot(&self, other: &Self) -> T {
+        self.zip(*other, |a, b| a * b).sum()
+    }
+
+    /// Sum of all components.
+    fn sum(&self) -> T {
+        self.fold(T::zero(), |acc, v| acc + v)
+    }
+
+    /// Product of all components.
+    fn product(&self) -> T {
+        self.fold(T::one(), |acc, v| acc * v)
+    }
+
+    /// Largest component.
+    fn max_component(&self) -> T
+    where
+        T: PartialOrd,
+    {
+        self.fold(None, |acc, v| match acc {
+            Some(max) if max >= v => Some(max),
+            _ => Some(v),
+        })
+        .unwrap()
+    }
+
+    /// Smallest component.
+    fn min_component(&self) -> T
+    where
+        T: PartialOrd,
+    {
+        self.fold(None, |acc, v| match acc {
+            Some(min) if min <= v => Some(min),
+            _ => Some(v),
+        })
+        .unwrap()
+    }
+
+    /// Reflect the direction across the surface with `normal`, which is expected to be unit length.
+    fn reflect(&self, normal: &Self) -> Self {
+        *self - *normal * ((T::one() + T::one()) * self.dot(normal))
+    }
+
+    /// Compare components from first to last, the first unequal pair decides the order.
+    ///
+    /// Returns `None` if a compared pair is unordered (`NaN`). Unlike `cmp_magnitude`, only equal vectors compare
+    /// `Equal`, which is consistent with `PartialEq` and suitable for sorting and ordered collections.
+    fn cmp_lexicographic(&self, other: &Self) -> Option<Ordering>
+    where
+        T: PartialOrd,
+        Self: Index<usize, Output = T>,
+    {
+        (0..Self::D).try_fold(Ordering::Equal, |ordering, i| match ordering {
+            Ordering::Equal => self[i].partial_cmp(&other[i]),
+            _ => Some(ordering),
+        })
+    }
+
+    /// Compare squared magnitudes.
+    ///
+    /// Different vectors with the same magnitude compare `Equal`, so this is not a `PartialOrd` implementation.
+    fn cmp_magnitude(&self, other: &Self) -> Option<Ordering>
+    where
+        T: PartialOrd,
+    {
+        self.dot(self).partial_cmp(&other.dot(other))
+    }
+
+    /// Index of the largest component, the first index is returned on ties.
+    fn argmax_axis(&self) -> usize
+    where
+        T: PartialOrd,
+    {
+        let (_, axis, _) = self.fold((0, 0, None), |(i, axis, acc), v| match acc {
+            Some(max) if max >= v => (i + 1, axis, Some(max)),
+            _ => (i + 1, i, Some(v)),
+        });
+        axis
+    }
+}
+
+/// Vector algebra for `Float` components.
+pub trait VxF<T: Float>: Vx<T> {
+    /// Vector magnitude.
+    fn mag(&self) -> T {
+        self.dot(self).sqrt()
+    }
+
+    /// Unit vector with the same direction.
+    ///
+    /// The zero vector has no direction and returns `NaN` components, use `try_norm` or `norm_or_zero` if the vector
+    /// might be zero.
+    fn norm(self) -> Self {
+        self / self.mag()
+    }
+
+    /// Unit vector with the same direction, returning `None` if the magnitude is below `T::epsilon()`.
+    fn try_norm(self) -> Option<Self> {
+        let mag = self.mag();
+        (mag >= T::epsilon()).then(|| self / mag)
+    }
+
+    /// Unit vector with the same direction, returning the zero vector if the magnitude is below `T::epsilon()`.
+    fn norm_or_zero(self) -> Self {
+        self.try_norm().unwrap_or(self * T::zero())
+    }
+
+    /// Scale the vector down to magnitude `max` if it is longer, shorter vectors, including zero, are unchanged.
+    ///
+    /// Negative `max` is treated as zero.
+    fn clamp_magnitude(self, max: T) -> Self {
+        let (mag, max) = (self.mag(), max.max(T::zero()));
+        match mag > max {
+            true => self * (max / mag),
+            false => self,
+        }
+    }
+
+    /// Return true if all components differ by at most `epsilon`, vectors with `NaN` components are never equal.
+    fn approx_eq(&self, other: &Self, epsilon: T) -> bool {
+        self.zip(*other, |a, b| (a - b).abs())
+            .fold(true, |eq, difference| eq && difference <= epsilon)
+    }
+
+    /// Refract the unit direction through the surface with unit `normal` pointing against it (Snell's law).
+    ///
+    /// `eta` is the ratio of the refractive indices of the incident and transmitted media. Returns `None` on total
+    /// internal reflection, non-unit vectors return a distorted direction instead of panicking.
+    fn refract(&self, normal: &Self, eta: T) -> Option<Self> {
+        let cos = self.dot(normal);
+        let k = T::one() - eta * eta * (T::one() - cos * cos);
+        if k < T::zero() {
+            return None;
+        }
+        Some(*self * eta - *normal * (eta * cos + k.sqrt()))
+    }
+}
+
+impl<T: Float, V: Vx<T>> VxF<T> for V {}
+
+macro_rules! vector {
+    ($v:ident $d:literal $($i:tt)+) => {
+        impl<T: Num + Copy> Vx<T> for $v<T> {
+            const D: usize = $d;
+
+            fn map(self, f: impl Fn(T) -> T) -> Self {
+                $v($(f(self.$i)),+)
+            }
+
+            fn zip(self, other: Self, f: impl Fn(T, T) -> T) -> Self {
+                $v($(f(self.$i, other.$i)),+)
+            }
+
+            fn fold<A>(self, init: A, f: impl Fn(A, T) -> A) -> A {
+                let acc = init;
+                $(let acc = f(acc, self.$i);)+
+                acc
+            }
+        }
+
+        // index
+
+        impl<T> Index<usize> for $v<T> {
+            type Output = T;
+            fn index(&self, index: usize) -> &Self::Output {
+                match index {
+                    $($i => &self.$i,)+
+                    _ => panic!("index out of bounds: the len is {} but the index is {}", $d, index),
+                }
+            }
+        }
+
+        impl<T> IndexMut<usize> for $v<T> {
+            fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+                match index {
+                    $($i => &mut self.$i,)+
+                    _ => panic!("index out of bounds: the len is {} but the index is {}", $d, index),
+                }
+            }
+        }
+
+        // operators
+
+        vector!(@binary $v Add::add AddAssign::add_assign $($i)+);
+        vector!(@binary $v Sub::sub SubAssign::sub_assign $($i)+);
+        vector!(@binary $v Mul::mul MulAssign::mul_assign $($i)+);
+        vector!(@binary $v Div::div DivAssign::div_assign $($i)+);
+
+        impl<T: Neg<Output = T>> Neg for $v<T> {
+            type Output = $v<T>;
+            fn neg(self) -> Self::Output {
+                $v($(-self.$i),+)
+            }
+        }
+    };
+
+    (@binary $v:ident $op:ident::$fn:ident $op_assign:ident::$fn_assign:ident $($i:tt)+) => {
+        impl<T: $op<Output = T>> $op for $v<T> {
+            type Output = $v<T>;
+            fn $fn(self, rhs: Self) -> Self::Output {
+                $v($($op::$fn(self.$i, rhs.$i)),+)
+            }
+        }
+
+        impl<T: Copy + $op<Output = T>> $op<T> for $v<T> {
+            type Output = $v<T>;
+            fn $fn(self, rhs: T) -> Self::Output {
+                $v($($op::$fn(self.$i, rhs)),+)
+            }
+        }
+
+        impl<T: $op_assign> $op_assign for $v<T> {
+            fn $fn_assign(&mut self, rhs: Self) {
+                $($op_assign::$fn_assign(&mut self.$i, rhs.$i);)+
+            }
+        }
+
+        impl<T: Copy + $op_assign> $op_assign<T> for $v<T> {
+            fn $fn_assign(&mut self, rhs: T) {
+                $($op_assign::$fn_assign(&mut self.$i, rhs);)+
+            }
+        }
+    };
+}
+
+vector!(V2 2 0 1);
+vector!(V3 3 0 1 2);
+vector!(V4 4 0 1 2 3);
+
+// 2d
+
+impl V2<f32> {
+    /// Return the polar coordinates `(radius, angle)` of the vector, the angle is in `[-π, π]`.
+    ///
+    /// Vectors on the negative `x` axis have angle `π`, or `-π` if `y` is `-0.0`. The zero vector returns `(0, 0)`.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_polar(&self) -> (f32, f32) {
+        let radius = self.mag();
+        if radius == 0.0 {
+            return (0.0, 0.0);
+        }
+        (radius, self.1.atan2(self.0))
+    }
+
+    /// Create a vector from polar coordinates.
+    pub fn from_polar(radius: f32, angle: f32) -> V2<f32> {
+        V2(radius * angle.cos(), radius * angle.sin())
+    }
+}
+
+// 3d
+
+impl<T: Num + Copy> V3<T> {
+    /// Cross product, perpendicular to both vectors following the right-hand rule.
+    pub fn cross(&self, other: &V3<T>) -> V3<T> {
+        let (a, b) = (self, other);
+        V3(a.1 * b.2 - a.2 * b.1, a.2 * b.0 - a.0 * b.2, a.0 * b.1 - a.1 * b.0)
+    }
+}
+
+impl V3<f32> {
+    /// Move towards `target` with a critically damped spring, `velocity` is the spring state updated by each call.
+    ///
+    /// `smooth_time` is roughly the time to reach the target and `dt` is the elapsed time since the previous call.
+    /// The exponential decay is approximated with a polynomial, which is stable for any `dt`.
+    /// The result never passes the target, keeping cameras from oscillating around it.
+    pub fn smooth_damp(&self, target: V3<f32>, velocity: &mut V3<f32>, smooth_time: f32, dt: f32) -> V3<f32> {
+        let omega = 2.0 / smooth_time.max(1e-4);
+        let x = omega * dt;
+        let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+        let change = *self - target;
+        let temp = (*velocity + change * omega) * dt;
+        *velocity = (*velocity - temp * omega) * decay;
+        let res = target + (change + temp) * decay;
+        if (target - *self).dot(&(res - target)) > 0.0 {
+            *velocity = V3(0.0, 0.0, 0.0);
+            return target;
+        }
+        res
+    }
+
+    /// Mirror the point across the plane through `plane_point` with normal `plane_normal`.
+    ///
+    /// `plane_normal` does not need to be normalized. Mirroring a camera position this way renders planar
+    /// reflections.
+    pub fn mirror_across(&self, plane_point: V3<f32>, plane_normal: V3<f32>) -> V3<f32> {
+        let distance = (*self - plane_point).dot(&plane_normal) / plane_normal.dot(&plane_normal);
+        *self - plane_normal * (2.0 * distance)
+    }
+}
+
+mod test {
+    use crate::math::{Vx, VxF, V2, V3, V4};
+
+    #[test]
+    fn ops() {
+        let a = V3(1.0, 2.0, 3.0);
+        let b = V3(4.0, 5.0, 6.0);
+        assert_eq!(a + b, V3(5.0, 7.0, 9.0));
+        assert_eq!(b - a, V3(3.0, 3.0, 3.0));
+        assert_eq!(a * 2.0, V3(2.0, 4.0, 6.0));
+        assert_eq!(-a, V3(-1.0, -2.0, -3.0));
+        let mut c = a;
+        c += b;
+        assert_eq!(c, a + b);
+        assert_eq!(a[0], 1.0);
+        assert_eq!(a[2], 3.0);
+    }
+
+    #[test]
+    fn algebra() {
+        assert_eq!(V2(1, 2).dot(&V2(3, 4)), 11);
+        assert_eq!(V3(3.0, 4.0, 0.0).mag(), 5.0);
+        assert_eq!(V4(0.0, 2.0, 0.0, 0.0).norm(), V4(0.0, 1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn norm() {
+        assert_eq!(V3(0.0, 3.0, 4.0).try_norm(), Some(V3(0.0, 0.6, 0.8)));
+        assert_eq!(V3(0.0, 3.0, 4.0).norm_or_zero(), V3(0.0, 0.6, 0.8));
+        assert_eq!(V3::<f32>::default().try_norm(), None);
+        assert_eq!(V3::<f32>::default().norm_or_zero(), V3(0.0, 0.0, 0.0));
+        assert_eq!(V2(1e-9f32, -1e-9).try_norm(), None);
+        assert!(V4(1e-17f64, 0.0, 0.0, 0.0).norm_or_zero() == V4(0.0, 0.0, 0.0, 0.0));
+        assert!(V3::<f32>::default().norm().0.is_nan());
+    }
+
+    #[test]
+    fn clamp_magnitude() {
+        let clamped = V2(3.0, 4.0).clamp_magnitude(2.5);
+        assert_eq!(clamped, V2(1.5, 2.0));
+        assert_eq!(clamped.mag(), 2.5);
+        assert!((V3(1.0f32, -7.0, 2.5).clamp_magnitude(3.0).mag() - 3.0).abs() < 1e-6);
+        assert_eq!(V3(0.1, 0.2, 0.2).clamp_magnitude(1.0), V3(0.1, 0.2, 0.2));
+        assert_eq!(V3::<f32>::default().clamp_magnitude(1.0), V3(0.0, 0.0, 0.0));
+        assert_eq!(V2(3.0, 4.0).clamp_magnitude(-1.0), V2(0.0, 0.0));
+    }
+
+    #[test]
+    fn approx_eq() {
+        let a = V3(1.0, -2.0, 0.5);
+        let b = a + V3(1e-9, 0.0, -1e-9);
+        assert!(a != b && a.approx_eq(&b, 1e-6) && !a.approx_eq(&b, 1e-12));
+        assert!(V2(3.0f32, 4.0).norm().approx_eq(&V2(0.6, 0.8), 1e-6));
+        assert!(!V2(f32::NAN, 0.0).approx_eq(&V2(f32::NAN, 0.0), 1.0));
+    }
+
+    #[test]
+    fn reduce() {
+        assert_eq!(V3(1, 2, 3).sum(), 6);
+        assert_eq!(V3(1, 2, 3).product(), 6);
+        assert_eq!(V2(0.5, 4.0).product(), 2.0);
+        assert_eq!(V4(1, 2, 3, 4).sum(), 10);
+    }
+
+    #[test]
+    fn compare() {
+        use std::cmp::Ordering;
+        let (a, b) = (V3(1.0, 0.0, 0.0), V3(0.0, 1.0, 0.0));
+        // equal magnitudes compare `Equal` even if the vectors are not equal, breaking the `PartialOrd` contract
+        assert_eq!(a.cmp_magnitude(&b), Some(Ordering::Equal));
+        assert_ne!(a, b);
+        assert_eq!(a.cmp_lexicographic(&b), Some(Ordering::Greater));
+        assert_eq!(b.cmp_lexicographic(&a), Some(Ordering::Less));
+        assert_eq!(a.cmp_lexicographic(&a), Some(Ordering::Equal));
+        assert_eq!(V2(1, 2).cmp_lexicographic(&V2(1, 3)), Some(Ordering::Less));
+        assert_eq!(V2(1.0, f32::NAN).cmp_lexicographic(&V2(1.0, 0.0)), None);
+        assert_eq!(V2(0.0, f32::NAN).cmp_lexicographic(&V2(1.0, 0.0)), Some(Ordering::Less));
+        let mut vs = vec![V2(2, 0), V2(0, 5), V2(0, 1), V2(1, 1)];
+        vs.sort_by(|a, b| a.cmp_lexicographic(b).unwrap());
+        assert_eq!(vs, vec![V2(0, 1), V2(0, 5), V2(1, 1), V2(2, 0)]);
+        assert_eq!(V2(3, 4).cmp_magnitude(&V2(0, 6)), Some(Ordering::Less));
+    }
+
+    #[test]
+    fn extrema() {
+        assert_eq!(V3(1, 5, 3).max_component(), 5);
+        assert_eq!(V3(1, 5, 3).min_component(), 1);
+        assert_eq!(V3(1, 5, 3).argmax_axis(), 1);
+        assert_eq!(V4(-1.0, -5.0, -3.0, -0.5).argmax_axis(), 3);
+        assert_eq!(V2(2.0, 2.0).argmax_axis(), 0);
+        assert_eq!(V2(0.5, -2.0).min_component(), -2.0);
+    }
+
+    #[test]
+    fn damp() {
+        let target = V3(10.0, -5.0, 2.0);
+        let (mut position, mut velocity) = (V3(0.0, 0.0, 0.0), V3(0.0, 0.0, 0.0));
+        let direction = target.norm();
+        for _ in 0..300 {
+            position = position.smooth_damp(target, &mut velocity, 0.5, 1.0 / 60.0);
+            assert!((position - target).dot(&direction) < 1e-3);
+        }
+        assert!((position - target).mag() < 1e-3);
+        // a large step does not jump past the target
+        let (mut position, mut velocity) = (V3(0.0, 0.0, 0.0), V3(0.0, 0.0, 0.0));
+        position = position.smooth_damp(target, &mut velocity, 0.1, 1.0);
+        assert!((position - target).dot(&direction) <= 0.0);
+    }
+
+    #[test]
+    fn lerp() {
+        let (a, b) = (V2(0.1f32, -3.7), V2(2.9, 1e-3));
+        assert_eq!((a.lerp(&b, 0.0), a.lerp(&b, 1.0)), (a, b));
+        assert_eq!(V2(0.0, 2.0).lerp(&V2(4.0, -2.0), 0.25), V2(1.0, 1.0));
+        let (a, b) = (V3(0.3f32, 1e6, -0.7), V3(-1e-7, 0.1, 3.3));
+        assert_eq!((a.lerp(&b, 0.0), a.lerp(&b, 1.0)), (a, b));
+        assert_eq!(V3(0.0, 0.0, 0.0).lerp(&V3(2.0, 4.0, 6.0), 0.5), V3(1.0, 2.0, 3.0));
+        let (a, b) = (V4(0.1f64, 0.2, 0.3, 0.4), V4(1e9, -0.9, 7.1, 0.0));
+        assert_eq!((a.lerp(&b, 0.0), a.lerp(&b, 1.0)), (a, b));
+        // extrapolates outside [0, 1]
+        let (a, b) = (V4(1.0, 1.0, 1.0, 1.0), V4(3.0, 3.0, 3.0, 3.0));
+        assert_eq!(a.lerp(&b, 2.0), V4(5.0, 5.0, 5.0, 5.0));
+    }
+
+    #[test]
+    fn scatter() {
+        let s = std::f32::consts::FRAC_1_SQRT_2;
+        let up = V3(0.0, 1.0, 0.0);
+        assert!((V3(s, -s, 0.0).reflect(&up) - V3(s, s, 0.0)).mag() < 1e-6);
+        assert_eq!(V2(3, -2).reflect(&V2(0, 1)), V2(3, 2));
+        // air to glass at 45 degrees bends towards the normal
+        let refracted = V3(s, -s, 0.0).refract(&up, 1.0 / 1.5).unwrap();
+        let sin = s / 1.5;
+        assert!((refracted - V3(sin, -(1.0 - sin * sin).sqrt(), 0.0)).mag() < 1e-6);
+        assert!((refracted.mag() - 1.0).abs() < 1e-6);
+        assert_eq!(V3(0.0, -1.0, 0.0).refract(&up, 1.0 / 1.5), Some(V3(0.0, -1.0, 0.0)));
+        // glass to air at 45 degrees is past the critical angle
+        assert_eq!(V3(s, -s, 0.0).refract(&up, 1.5), None);
+        assert!(V3(s, -s, 0.0).refract(&(up * 3.0), 1.0 / 1.5).is_some());
+    }
+
+    #[test]
+    fn cross() {
+        let (x, y, z) = (V3(1, 0, 0), V3(0, 1, 0), V3(0, 0, 1));
+        assert_eq!(x.cross(&y), z);
+        assert_eq!(y.cross(&z), x);
+        assert_eq!(z.cross(&x), y);
+        assert_eq!(y.cross(&x), V3(0, 0, -1));
+        let (a, b) = (V3(1.5f32, -2.0, 0.25), V3(-3.0, 0.5, 4.0));
+        let c = a.cross(&b);
+        assert!(c.dot(&a).abs() < 1e-5 && c.dot(&b).abs() < 1e-5);
+        assert_eq!(a.cross(&a), V3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn mirror() {
+        let (origin, up) = (V3(0.0, 0.0, 0.0), V3(0.0, 1.0, 0.0));
+        assert_eq!(V3(1.0, 2.0, 3.0).mirror_across(origin, up), V3(1.0, -2.0, 3.0));
+        assert_eq!(V3(1.0, 2.0, 3.0).mirror_across(origin, up * 4.0), V3(1.0, -2.0, 3.0));
+        assert_eq!(V3(-4.0, 0.0, 2.0).mirror_across(origin, up), V3(-4.0, 0.0, 2.0));
+        let (point, normal) = (V3(1.0, 1.0, 1.0), V3(1.0, 1.0, 0.0));
+        let mirrored = V3(3.0, 1.0, 5.0).mirror_across(point, normal);
+        assert!((mirrored - V3(1.0, -1.0, 5.0)).mag() < 1e-5);
+        assert!((mirrored.mirror_across(point, normal) - V3(3.0, 1.0, 5.0)).mag() < 1e-5);
+    }
+
+    #[test]
+    fn polar() {
+        for v in [V2(1.0, 0.0), V2(-2.0, 3.0), V2(0.5, -0.25), V2(-4.0, -4.0)] {
+            let (radius, angle) = v.to_polar();
+            let r = V2::from_polar(radius, angle);
+            assert!((r - v).mag() < 1e-5);
+        }
+        assert_eq!(V2(0.0, 0.0).to_polar(), (0.0, 0.0));
+        assert_eq!(V2(-0.0, -0.0).to_polar(), (0.0, 0.0));
+        assert_eq!(V2(-1.0, 0.0).to_polar(), (1.0, std::f32::consts::PI));
+        assert_eq!(V2(-1.0, -0.0).to_polar(), (1.0, -std::f32::consts::PI));
+        let up = V2::from_polar(1.0, std::f32::consts::PI / 2.0);
+        assert!((up - V2(0.0, 1.0)).mag() < 1e-6);
+    }
+}
@@ -0,0 +1,229 @@
+#![allow(unused)]
+use crate::math::{mx, Aabb, MX, VR};
+use crate::raytrace::ray::Ray;
+
+/// Leaves have at most `MAX_LEAF_SIZE` primitives, unless splitting them costs more with `BvhBuildStrategy::Sah`.
+const MAX_LEAF_SIZE: usize = 2;
+
+/// Cost of visiting a node relative to intersecting a primitive, used by `BvhBuildStrategy::Sah`.
+const TRAVERSAL_COST: f32 = 1.0;
+
+/// How `Bvh::new` partitions the primitives of a node between its children.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BvhBuildStrategy {
+    /// Split in halves along the axis with the largest spread of primitive centers, which is fast to build, but
+    /// boxes of uneven geometry overlap and contain much empty space.
+    Median,
+    /// Split at the position with the lowest surface area heuristic cost among all primitive centers along the
+    /// three axes.
+    ///
+    /// The chance of a ray hitting a child is estimated as the ratio of the child and parent box areas, so the cost
+    /// of a split is the cost of traversing the node and intersecting the primitives of each child by that chance.
+    /// Nodes are kept as leaves when intersecting all their primitives costs less than the best split.
+    Sah,
+}
+
+enum Node {
+    Leaf { aabb: Aabb, start: usize, count: usize },
+    Branch { aabb: Aabb, left: usize, right: usize },
+}
+
+impl Node {
+    fn aabb(&self) -> &Aabb {
+        match self {
+            Node::Leaf { aabb, .. } | Node::Branch { aabb, .. } => aabb,
+        }
+    }
+}
+
+/// Bounding volume hierarchy of primitive bounds, the first node is the root.
+///
+/// Leaves store ranges of `indices`, the indices of the primitives in the slice the hierarchy was built from.
+pub struct Bvh {
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+}
+
+impl Bvh {
+    /// Build the hierarchy of the primitives with `bounds` with `strategy`.
+    pub fn new(bounds: &[Aabb], strategy: BvhBuildStrategy) -> Bvh {
+        let mut bvh = Bvh {
+            nodes: Vec::new(),
+            indices: (0..bounds.len()).collect(),
+        };
+        if !bounds.is_empty() {
+            bvh.build(bounds, 0, bounds.len(), strategy);
+        }
+        bvh
+    }
+
+    /// Build the node of `indices[start..start + count]`, returning its index.
+    fn build(&mut self, bounds: &[Aabb], start: usize, count: usize, strategy: BvhBuildStrategy) -> usize {
+        let indices = &mut self.indices[start..start + count];
+        let aabb = indices
+            .iter()
+            .fold(Aabb::from_points(&[]), |aabb, &i| aabb.merge(&bounds[i]));
+        let node = self.nodes.len();
+        self.nodes.push(Node::Leaf { aabb, start, count });
+        if count <= MAX_LEAF_SIZE {
+            return node;
+        }
+        let center = |i: usize, axis: usize| bounds[i].min[axis] + bounds[i].max[axis];
+        let (axis, split) = match strategy {
+            BvhBuildStrategy::Median => {
+                let centers = Aabb::from_points(
+                    &indices
+                        .iter()
+                        .map(|&i| (bounds[i].min + bounds[i].max) / 2.0)
+                        .collect::<Vec<_>>(),
+                );
+                let spread = centers.max - centers.min;
+                let axis = (0..3).max_by(|&a, &b| spread[a].total_cmp(&spread[b])).unwrap();
+                (axis, count / 2)
+            }
+            BvhBuildStrategy::Sah => {
+                let area = aabb.surface_area();
+                let mut best = (f32::INFINITY, 0, count / 2);
+                for axis in 0..3 {
+                    indices.sort_by(|&a, &b| center(a, axis).total_cmp(&center(b, axis)));
+                    // areas of the boxes of the first i primitives, and then of the last i primitives
+                    let sweep = |order: &mut dyn Iterator<Item = &usize>| {
+                        order
+                            .scan(Aabb::from_points(&[]), |aabb, &i| {
+                                *aabb = aabb.merge(&bounds[i]);
+                                Some(aabb.surface_area())
+                            })
+                            .collect::<Vec<_>>()
+                    };
+                    let (left, right) = (sweep(&mut indices.iter()), sweep(&mut indices.iter().rev()));
+                    for split in 1..count {
+                        let cost = TRAVERSAL_COST
+                            + (left[split - 1] * split as f32 + right[count - split - 1] * (count - split) as f32)
+                                / area;
+                        if cost < best.0 {
+                            best = (cost, axis, split);
+                        }
+                    }
+                }
+                if best.0 >= count as f32 {
+                    return node;
+                }
+                (best.1, best.2)
+            }
+        };
+        let indices = &mut self.indices[start..start + count];
+        indices.sort_by(|&a, &b| center(a, axis).total_cmp(&center(b, axis)));
+        let left = self.build(bounds, start, split, strategy);
+        let right = self.build(bounds, start + split, count - split, strategy);
+        self.nodes[node] = Node::Branch { aabb, left, right };
+        node
+    }
+
+    /// Return the index and `t` of the nearest primitive hit by `ray`, `intersect` returns the `t` of the hit of the
+    /// primitive with the index, if any.
+    ///
+    /// Only primitives whose leaf box is hit closer than the nearest hit so far are intersected. `visits` is
+    /// incremented for each node whose box is tested.
+    pub fn intersect(
+        &self,
+        ray: &Ray,
+        visits: &mut u32,
+        mut intersect: impl FnMut(usize) -> Option<f32>,
+    ) -> Option<(usize, f32)> {
+        let mut nearest: Option<(usize, f32)> = None;
+        let mut stack = if self.nodes.is_empty() { vec![] } else { vec![0] };
+        while let Some(node) = stack.pop() {
+            *visits += 1;
+            let node = &self.nodes[node];
+            let aabb = node.aabb();
+            match ray.intersect_aabb(aabb.min, aabb.max) {
+                Some((near, _)) if nearest.is_none_or(|(_, t)| near < t) => (),
+                _ => continue,
+            }
+            match *node {
+                Node::Leaf { start, count, .. } => {
+                    for &i in &self.indices[start..start + count] {
+                        if let Some(t) = intersect(i).filter(|&t| nearest.is_none_or(|(_, nearest)| t < nearest)) {
+                            nearest = Some((i, t));
+                        }
+                    }
+                }
+                Node::Branch { left, right, .. } => stack.extend([right, left]),
+            }
+        }
+        nearest
+    }
+}
+
+mod test {
+    use crate::math::sampling::Rng;
+    use crate::math::{mx, Aabb, MX, VR};
+    use crate::raytrace::bvh::{Bvh, BvhBuildStrategy, Node};
+    use crate::raytrace::ray::Ray;
+
+    /// Unit spheres in a large cluster around the origin and a small cluster far along `+X`.
+    fn spheres() -> Vec<VR<f32, 4>> {
+        let mut rng = Rng::new(7);
+        let mut point = |x: f32, size: f32| {
+            let mut coordinate = || (rng.next_f32() - 0.5) * size;
+            mx!(VR[x + coordinate(), coordinate(), coordinate(), 1.0])
+        };
+        let large = (0..60).map(|_| point(0.0, 10.0)).collect::<Vec<_>>();
+        large.into_iter().chain((0..8).map(|_| point(100.0, 4.0))).collect()
+    }
+
+    fn bounds(centers: &[VR<f32, 4>]) -> Vec<Aabb> {
+        let offset = mx!(VR[1.0, 1.0, 1.0, 0.0]);
+        centers.iter().map(|&c| Aabb::new(c - offset, c + offset)).collect()
+    }
+
+    /// Rays down `-Z` spread over the clusters and the empty space between them, slightly tilted.
+    fn rays() -> Vec<Ray> {
+        let mut rng = Rng::new(11);
+        (0..256)
+            .map(|_| {
+                let (x, y) = (rng.next_f32() * 120.0 - 10.0, rng.next_f32() * 12.0 - 6.0);
+                let tilt = mx!(VR[rng.next_f32() - 0.5, rng.next_f32() - 0.5, -10.0, 0.0]);
+                Ray::new(mx!(VR[x, y, 30.0, 1.0]), tilt)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nearest() {
+        let centers = spheres();
+        let bvhs =
+            [BvhBuildStrategy::Median, BvhBuildStrategy::Sah].map(|strategy| Bvh::new(&bounds(&centers), strategy));
+        for ray in rays() {
+            let intersect = |i: usize| ray.intersect_sphere(centers[i], 1.0);
+            let expected = (0..centers.len())
+                .filter_map(|i| intersect(i).map(|t| (i, t)))
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            for bvh in &bvhs {
+                assert_eq!(bvh.intersect(&ray, &mut 0, intersect), expected);
+            }
+        }
+        let empty = Bvh::new(&[], BvhBuildStrategy::Sah);
+        assert_eq!(empty.intersect(&rays()[0], &mut 0, |_| Some(1.0)), None);
+    }
+
+    #[test]
+    fn sah() {
+        let centers = spheres();
+        let visits = |strategy| {
+            let bvh = Bvh::new(&bounds(&centers), strategy);
+            let mut visits = 0;
+            for ray in rays() {
+                bvh.intersect(&ray, &mut visits, |i| ray.intersect_sphere(centers[i], 1.0));
+            }
+            visits
+        };
+        let (median, sah) = (visits(BvhBuildStrategy::Median), visits(BvhBuildStrategy::Sah));
+        assert!(sah < median, "sah {sah} median {median}");
+        // splitting identical boxes only adds the traversal cost, they stay in a single leaf
+        let identical = bounds(&[mx!(VR[0.0, 0.0, 0.0, 1.0]); 8]);
+        let bvh = Bvh::new(&identical, BvhBuildStrategy::Sah);
+        assert!(matches!(bvh.nodes[..], [Node::Leaf { count: 8, .. }]));
+        assert_eq!(Bvh::new(&identical, BvhBuildStrategy::Median).nodes.len(), 7);
+    }
+}
@@ -0,0 +1,201 @@
+#![allow(unused)]
+use crate::math::sampling::orthonormal_basis;
+use crate::math::{Vx, VxF, V2, V3};
+
+/// How `Texture::sample` maps coordinates outside `[0, 1]`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Addressing {
+    /// Repeat the texture, `1.25` samples as `0.25`.
+    Wrap,
+    /// Extend the edge texels, `1.25` samples as `1`.
+    Clamp,
+}
+
+/// Image of linear colors sampled by texture coordinates, texel `(x, y)` is `texels[width * y + x]`.
+///
+/// Coordinates are `[0, 1]` from left to right and top to bottom, outside they follow `addressing`.
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pub texels: Vec<V3<f32>>,
+    pub addressing: Addressing,
+}
+
+impl Texture {
+    /// Create a texture with `Addressing::Wrap`.
+    pub fn new(width: usize, height: usize, texels: Vec<V3<f32>>) -> Texture {
+        assert_eq!(texels.len(), width * height, "texture size does not match the texels");
+        Texture {
+            width,
+            height,
+            texels,
+            addressing: Addressing::Wrap,
+        }
+    }
+
+    pub fn addressing(mut self, addressing: Addressing) -> Texture {
+        self.addressing = addressing;
+        self
+    }
+
+    /// Return the texel containing `uv`.
+    pub fn sample(&self, uv: V2<f32>) -> V3<f32> {
+        let texel = |coordinate: f32, size: usize| {
+            let coordinate = match self.addressing {
+                Addressing::Wrap => coordinate.rem_euclid(1.0),
+                Addressing::Clamp => coordinate.clamp(0.0, 1.0),
+            };
+            ((coordinate * size as f32) as usize).min(size - 1)
+        };
+        self.texels[self.width * texel(uv.1, self.height) + texel(uv.0, self.width)]
+    }
+}
+
+/// Surface material of a primitive.
+pub enum Material {
+    /// Diffuse surface of `color`, multiplied by the `texture` texels.
+    ///
+    /// The `normal_map` texels are tangent space normals encoded from `[-1, 1]` to `[0, 1]`, with `x` along the
+    /// increasing `u` coordinate, `y` along the increasing `v` coordinate, and `z` along the surface normal. Only
+    /// meshes have the texture coordinates and tangents to use the textures.
+    Diffuse {
+        color: V3<f32>,
+        texture: Option<Texture>,
+        normal_map: Option<Texture>,
+    },
+    /// Clear glass-like surface with index of refraction `ior`, rays refract or reflect, see `dielectric_scatter`.
+    ///
+    /// Surfaces face outwards, into the medium with index 1. Shadow rays do not pass through dielectrics.
+    Dielectric { ior: f32 },
+}
+
+impl Material {
+    /// Diffuse material of `color` without textures.
+    pub fn diffuse(color: V3<f32>) -> Material {
+        Material::Diffuse {
+            color,
+            texture: None,
+            normal_map: None,
+        }
+    }
+
+    /// Return the surface color at `uv`, dielectrics are white.
+    pub fn color(&self, uv: V2<f32>) -> V3<f32> {
+        match self {
+            Material::Diffuse { color, texture, .. } => match texture {
+                Some(texture) => *color * texture.sample(uv),
+                None => *color,
+            },
+            Material::Dielectric { .. } => V3(1.0, 1.0, 1.0),
+        }
+    }
+
+    /// Return the shading normal at `uv` of a surface with unit `normal`, perturbed by the normal map if any.
+    ///
+    /// `tangent` and `bitangent` are the directions of the increasing texture coordinates, they are orthonormalized
+    /// against the normal, so they do not need to be perpendicular or unit length, and may be zero.
+    pub fn shading_normal(&self, normal: V3<f32>, tangent: V3<f32>, bitangent: V3<f32>, uv: V2<f32>) -> V3<f32> {
+        let Material::Diffuse {
+            normal_map: Some(normal_map),
+            ..
+        } = self
+        else {
+            return normal;
+        };
+        // without tangents any frame around the normal is used
+        let (tangent, bitangent) = match (tangent - normal * normal.dot(&tangent)).try_norm() {
+            Some(tangent) => {
+                let cross = normal.cross(&tangent);
+                (tangent, if cross.dot(&bitangent) < 0.0 { -cross } else { cross })
+            }
+            None => orthonormal_basis(normal),
+        };
+        let texel = normal_map.sample(uv) * 2.0 - V3(1.0, 1.0, 1.0);
+        (tangent * texel.0 + bitangent * texel.1 + normal * texel.2)
+            .try_norm()
+            .unwrap_or(normal)
+    }
+}
+
+/// Return the unit direction of the ray continuing from a unit `direction` hitting a dielectric with index of
+/// refraction `ior` and unit outward `normal`.
+///
+/// The ray refracts with Snell's law or reflects with the Fresnel reflectance, approximated with Schlick's formula,
+/// as probability, chosen by `sample` in `[0, 1)`. Rays leaving the dielectric beyond the critical angle always
+/// reflect (total internal reflection).
+pub fn dielectric_scatter(direction: V3<f32>, normal: V3<f32>, ior: f32, sample: f32) -> V3<f32> {
+    // rays leaving the dielectric see the inverse ratio and the normal facing into it
+    let (normal, eta) = match direction.dot(&normal) < 0.0 {
+        true => (normal, 1.0 / ior),
+        false => (-normal, ior),
+    };
+    let cos = -direction.dot(&normal);
+    let r0 = ((1.0 - ior) / (1.0 + ior)).powi(2);
+    let reflectance = r0 + (1.0 - r0) * (1.0 - cos).powi(5);
+    match direction.refract(&normal, eta) {
+        Some(refracted) if sample >= reflectance => refracted,
+        _ => direction.reflect(&normal),
+    }
+}
+
+mod test {
+    use crate::math::{VxF, V2, V3};
+    use crate::raytrace::material::{dielectric_scatter, Addressing, Material, Texture};
+
+    #[test]
+    fn texture() {
+        let (black, white) = (V3(0.0, 0.0, 0.0), V3(1.0, 1.0, 1.0));
+        let texture = Texture::new(2, 2, vec![black, white, white, black]);
+        assert_eq!(texture.sample(V2(0.25, 0.25)), black);
+        assert_eq!(texture.sample(V2(0.75, 0.25)), white);
+        assert_eq!(texture.sample(V2(0.25, 0.75)), white);
+        assert_eq!(texture.sample(V2(1.0, 1.0)), black);
+        assert_eq!(texture.sample(V2(-0.25, 0.25)), white);
+        // clamped coordinates sample the edge texels
+        let texture = texture.addressing(Addressing::Clamp);
+        assert_eq!(texture.sample(V2(1.0, 1.0)), black);
+        assert_eq!(texture.sample(V2(-0.25, 0.25)), black);
+        assert_eq!(texture.sample(V2(1.5, -3.0)), white);
+    }
+
+    #[test]
+    fn dielectric() {
+        let (normal, ior) = (V3(0.0, 1.0, 0.0), 1.5);
+        // entering at 45 degrees the ray bends towards the normal, sin(45) / 1.5 = sin(28.1)
+        let direction = V3(1.0, -1.0, 0.0).norm();
+        let refracted = dielectric_scatter(direction, normal, ior, 0.99);
+        let sin = (0.5f32).sqrt() / ior;
+        assert!(refracted.approx_eq(&V3(sin, -(1.0 - sin * sin).sqrt(), 0.0), 1e-6));
+        // unless the sample falls in the reflectance, about 5% at 45 degrees
+        assert!(dielectric_scatter(direction, normal, ior, 0.01).approx_eq(&V3(1.0, 1.0, 0.0).norm(), 1e-6));
+        // leaving, the ray bends away from the normal back to the entering direction
+        let leaving = dielectric_scatter(refracted, -normal, ior, 0.99);
+        assert!(leaving.approx_eq(&direction, 1e-6));
+        // beyond the critical angle of 41.8 degrees rays leaving always reflect
+        let steep = V3(60f32.to_radians().sin(), 60f32.to_radians().cos(), 0.0);
+        let reflected = V3(steep.0, -steep.1, 0.0);
+        assert!(dielectric_scatter(steep, normal, ior, 0.99).approx_eq(&reflected, 1e-6));
+        assert!(dielectric_scatter(steep, normal, ior, 0.0).approx_eq(&reflected, 1e-6));
+    }
+
+    #[test]
+    fn normal_map() {
+        let (normal, tangent, bitangent) = (V3(0.0, 0.0, 1.0), V3(2.0, 0.0, 0.5), V3(0.0, -1.0, 0.0));
+        let map = |texel: V3<f32>| Material::Diffuse {
+            color: V3(1.0, 1.0, 1.0),
+            texture: None,
+            normal_map: Some(Texture::new(1, 1, vec![texel])),
+        };
+        let uv = V2(0.5, 0.5);
+        // a flat map keeps the geometric normal
+        let flat = map(V3(0.5, 0.5, 1.0)).shading_normal(normal, tangent, bitangent, uv);
+        assert!(flat.approx_eq(&normal, 1e-6));
+        // normals tilted to +x and +y follow the orthonormalized tangent and bitangent
+        let shading_normal = map(V3(1.0, 0.5, 0.5)).shading_normal(normal, tangent, bitangent, uv);
+        assert!(shading_normal.approx_eq(&V3(1.0, 0.0, 0.0), 1e-6));
+        let shading_normal = map(V3(0.5, 1.0, 0.5)).shading_normal(normal, tangent, bitangent, uv);
+        assert!(shading_normal.approx_eq(&V3(0.0, -1.0, 0.0), 1e-6));
+        let shading_normal = Material::diffuse(V3(1.0, 1.0, 1.0)).shading_normal(normal, tangent, bitangent, uv);
+        assert_eq!(shading_normal, normal);
+    }
+}
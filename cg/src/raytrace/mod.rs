@@ -1 +1,5 @@
+pub mod bvh;
+pub mod material;
+pub mod ray;
 pub mod scene;
+pub mod text;
@@ -0,0 +1,415 @@
+#![allow(unused)]
+use crate::math::{mx, Aabb, Triangle, Vx, MX, V3, VR};
+
+/// Hits closer than `EPSILON` are ignored, preventing rays from hitting the surface they start from.
+const EPSILON: f32 = 1e-4;
+
+/// Box face, identified by the axis and sign of its outward normal, `+X` is `Face { axis: 0, positive: true }`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Face {
+    pub axis: usize,
+    pub positive: bool,
+}
+
+/// Ray with an `origin` point (`w = 1`) and a `direction` vector (`w = 0`).
+///
+/// Intersection methods return the ray parameter `t` of the nearest hit in front of the origin, the hit point is
+/// `ray.at(t)`. Distances are in `direction` units.
+#[derive(Copy, Clone)]
+pub struct Ray {
+    pub origin: VR<f32, 4>,
+    pub direction: VR<f32, 4>,
+    /// Time since the camera shutter opened, moving primitives are hit where they are at `time`.
+    pub time: f32,
+}
+
+impl Ray {
+    /// Create a ray with a unit `direction`, distances are in world units.
+    ///
+    /// A zero `direction` has no direction and the ray has `NaN` components, as with `VxF::norm`.
+    pub fn new(origin: VR<f32, 4>, direction: VR<f32, 4>) -> Ray {
+        let direction = direction / direction.dot(&direction).sqrt();
+        Ray {
+            origin,
+            direction,
+            time: 0.0,
+        }
+    }
+
+    /// Create a ray with `direction` as is, distances are in `direction` units.
+    pub fn new_unnormalized(origin: VR<f32, 4>, direction: VR<f32, 4>) -> Ray {
+        Ray {
+            origin,
+            direction,
+            time: 0.0,
+        }
+    }
+
+    /// Return the ray transformed by `m`, the origin as a point (`w = 1`) and the direction as a vector (`w = 0`).
+    ///
+    /// The direction is not normalized, so `t` values of the transformed ray are hits at the same points of the
+    /// original ray, which allows intersecting object space shapes with world space rays.
+    pub fn transform(&self, m: &MX<f32, 4, 4>) -> Ray {
+        let (o, d) = (&self.origin, &self.direction);
+        Ray {
+            origin: m.multiply(&mx!(VC[o[0], o[1], o[2], 1.0])).transpose(),
+            direction: m.multiply(&mx!(VC[d[0], d[1], d[2], 0.0])).transpose(),
+            time: self.time,
+        }
+    }
+
+    /// Return the point at `origin + direction * t`.
+    pub fn at(&self, t: f32) -> VR<f32, 4> {
+        self.origin + self.direction * t
+    }
+
+    /// Intersect the sphere at `center`, rays starting inside the sphere hit it from the inside.
+    pub fn intersect_sphere(&self, center: VR<f32, 4>, radius: f32) -> Option<f32> {
+        // solve |origin + direction * t - center|^2 = radius^2, with b halved
+        let oc = self.origin - center;
+        let (a, b, c) = (
+            self.direction.dot(&self.direction),
+            oc.dot(&self.direction),
+            oc.dot(&oc) - radius * radius,
+        );
+        let discriminant = b * b - a * c;
+        if a < f32::EPSILON || discriminant < 0.0 {
+            return None;
+        }
+        let sqrt = discriminant.sqrt();
+        [(-b - sqrt) / a, (-b + sqrt) / a].into_iter().find(|&t| t > EPSILON)
+    }
+
+    /// Intersect the triangle with the Möller–Trumbore algorithm, returning `t` and the barycentric coordinates
+    /// `(u, v)` of the hit, the hit point is `a * (1 - u - v) + b * u + c * v`.
+    ///
+    /// Front faces have counter-clockwise vertices seen from the ray origin, if `cull_back_faces` is set rays hitting
+    /// the back face miss. Rays parallel to the triangle plane and degenerate triangles never hit.
+    pub fn intersect_triangle(&self, triangle: &Triangle<3>, cull_back_faces: bool) -> Option<(f32, f32, f32)> {
+        let v3 = |v: &VR<f32, 3>| V3(v[0], v[1], v[2]);
+        let (a, b, c) = (v3(&triangle.0), v3(&triangle.1), v3(&triangle.2));
+        let origin = V3(self.origin[0], self.origin[1], self.origin[2]);
+        let direction = V3(self.direction[0], self.direction[1], self.direction[2]);
+        let (ab, ac) = (b - a, c - a);
+        let p = direction.cross(&ac);
+        let det = ab.dot(&p);
+        if det.abs() < f32::EPSILON || cull_back_faces && det < 0.0 {
+            return None;
+        }
+        let inv = 1.0 / det;
+        let ao = origin - a;
+        let u = ao.dot(&p) * inv;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = ao.cross(&ab);
+        let v = direction.dot(&q) * inv;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = ac.dot(&q) * inv;
+        (t > EPSILON).then_some((t, u, v))
+    }
+
+    /// Intersect the plane through `point` facing `normal` (`w = 0`), from either side.
+    ///
+    /// Rays parallel to the plane never hit, even if they are inside it.
+    pub fn intersect_plane(&self, point: VR<f32, 4>, normal: VR<f32, 4>) -> Option<f32> {
+        let denom = normal.dot(&self.direction);
+        if denom.abs() < f32::EPSILON {
+            return None;
+        }
+        let t = (point - self.origin).dot(&normal) / denom;
+        (t > EPSILON).then_some(t)
+    }
+
+    /// Intersect the disk at `center` facing `normal` (unit length, `w = 0`), from either side.
+    pub fn intersect_disk(&self, center: VR<f32, 4>, normal: VR<f32, 4>, radius: f32) -> Option<f32> {
+        let t = self.intersect_plane(center, normal)?;
+        let offset = self.at(t) - center;
+        (offset.dot(&offset) <= radius * radius).then_some(t)
+    }
+
+    /// Intersect the capped cylinder starting at the `base` cap center and extending `height` along `axis` (unit
+    /// length, `w = 0`).
+    pub fn intersect_cylinder(&self, base: VR<f32, 4>, axis: VR<f32, 4>, radius: f32, height: f32) -> Option<f32> {
+        // side, solve |p - base|^2 = radius^2 with p and base projected onto the plane perpendicular to the axis
+        let oc = self.origin - base;
+        let d = self.direction - axis * axis.dot(&self.direction);
+        let o = oc - axis * axis.dot(&oc);
+        let (a, b, c) = (d.dot(&d), 2.0 * d.dot(&o), o.dot(&o) - radius * radius);
+        let discriminant = b * b - 4.0 * a * c;
+        let side = (a > f32::EPSILON && discriminant >= 0.0)
+            .then(|| {
+                let sqrt = discriminant.sqrt();
+                [(-b - sqrt) / (2.0 * a), (-b + sqrt) / (2.0 * a)]
+                    .into_iter()
+                    .filter(|&t| t > EPSILON)
+                    .find(|&t| (0.0..=height).contains(&(self.at(t) - base).dot(&axis)))
+            })
+            .flatten();
+        // caps
+        let bottom = self.intersect_disk(base, axis, radius);
+        let top = self.intersect_disk(base + axis * height, axis, radius);
+        [side, bottom, top].into_iter().flatten().reduce(f32::min)
+    }
+
+    /// Return the entry and exit `t` of the ray between the `min` and `max` planes perpendicular to `axis`, or `None`
+    /// if the ray is parallel to the planes and outside them.
+    ///
+    /// Parallel rays between the planes, including rays lying on them, are inside the slab for every `t`.
+    fn slab(&self, axis: usize, min: f32, max: f32) -> Option<(f32, f32)> {
+        let (origin, direction) = (self.origin[axis], self.direction[axis]);
+        if direction == 0.0 {
+            return (min <= origin && origin <= max).then_some((f32::NEG_INFINITY, f32::INFINITY));
+        }
+        let (t_min, t_max) = ((min - origin) / direction, (max - origin) / direction);
+        Some(if direction > 0.0 {
+            (t_min, t_max)
+        } else {
+            (t_max, t_min)
+        })
+    }
+
+    /// Intersect the box between the `min` and `max` corners with the slab method, returning the entry and exit `t`.
+    ///
+    /// Rays starting inside the box have a negative entry `t`, boxes behind the origin never hit. Rays grazing an edge
+    /// or corner, or lying on a face, hit with the entry and exit of the boundary.
+    pub fn intersect_aabb(&self, min: VR<f32, 4>, max: VR<f32, 4>) -> Option<(f32, f32)> {
+        let (mut near, mut far) = (f32::NEG_INFINITY, f32::INFINITY);
+        for axis in 0..3 {
+            let (t0, t1) = self.slab(axis, min[axis], max[axis])?;
+            near = near.max(t0);
+            far = far.min(t1);
+        }
+        (near <= far && far > EPSILON).then_some((near, far))
+    }
+
+    /// Intersect the box, returning the hit `t` and the face the ray enters through.
+    ///
+    /// Rays starting inside the box have no entry face, they return the exit `t` and `None` instead.
+    pub fn intersect_aabb_face(&self, aabb: &Aabb) -> Option<(f32, Option<Face>)> {
+        let (mut near, mut far, mut entry) = (f32::NEG_INFINITY, f32::INFINITY, None);
+        for axis in 0..3 {
+            let (t0, t1) = self.slab(axis, aabb.min[axis], aabb.max[axis])?;
+            // positive directions enter through the min face, whose normal is negative, parallel slabs are not entered
+            if t0 > near {
+                let positive = self.direction[axis] < 0.0;
+                (near, entry) = (t0, Some(Face { axis, positive }));
+            }
+            far = far.min(t1);
+        }
+        if near > far || far <= EPSILON {
+            return None;
+        }
+        if near > EPSILON {
+            Some((near, entry))
+        } else {
+            Some((far, None))
+        }
+    }
+}
+
+mod test {
+    use crate::math::{mx, Aabb, MX, V3};
+    use crate::raytrace::ray::{Face, Ray};
+
+    fn ray(origin: [f32; 3], direction: [f32; 3]) -> Ray {
+        Ray {
+            origin: mx!(VR[origin[0], origin[1], origin[2], 1.0]),
+            direction: mx!(VR[direction[0], direction[1], direction[2], 0.0]),
+            time: 0.0,
+        }
+    }
+
+    #[test]
+    fn new() {
+        let ray = Ray::new(mx!(VR[1.0, 2.0, 3.0, 1.0]), mx!(VR[3.0, -4.0, 12.0, 0.0]));
+        let offset = ray.at(1.0) - ray.origin;
+        assert!((offset.dot(&offset).sqrt() - 1.0).abs() < 1e-6);
+        assert!(ray.direction == mx!(VR[3.0 / 13.0, -4.0 / 13.0, 12.0 / 13.0, 0.0]));
+        let ray = Ray::new_unnormalized(mx!(VR[0.0, 0.0, 0.0, 1.0]), mx!(VR[0.0, 2.0, 0.0, 0.0]));
+        assert!(ray.at(1.0) == mx!(VR[0.0, 2.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn transform() {
+        let mut m = MX::from_euler_xyz(V3(0.0, 0.0, std::f32::consts::FRAC_PI_2));
+        m[3] = 5.0;
+        let ray = Ray::new(mx!(VR[1.0, 0.0, 0.0, 1.0]), mx!(VR[1.0, 0.0, 0.0, 0.0])).transform(&m);
+        let close = |a: [f32; 4], b: [f32; 4]| (0..4).all(|i| (a[i] - b[i]).abs() < 1e-6);
+        // rotated by 90 degrees around Z, only the origin is translated
+        assert!(close(ray.origin.data, [5.0, 1.0, 0.0, 1.0]));
+        assert!(close(ray.direction.data, [0.0, 1.0, 0.0, 0.0]));
+        // a scaled sphere hit at the same t as the sphere in object space
+        let scale = mx!([2.0, 0.0, 0.0, 0.0][0.0, 2.0, 0.0, 0.0][0.0, 0.0, 2.0, 0.0][0.0, 0.0, 0.0, 1.0]);
+        let world = Ray::new(mx!(VR[-10.0, 0.0, 0.0, 1.0]), mx!(VR[1.0, 0.0, 0.0, 0.0]));
+        let inverse = scale.inverse().unwrap();
+        let t = world
+            .transform(&inverse)
+            .intersect_sphere(mx!(VR[0.0, 0.0, 0.0, 1.0]), 1.0);
+        assert!((t.unwrap() - 8.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn sphere() {
+        let center = mx!(VR[0.0, 0.0, -5.0, 1.0]);
+        let t = ray([0.0, 0.0, 0.0], [0.0, 0.0, -1.0]).intersect_sphere(center, 1.0);
+        assert_eq!(t, Some(4.0));
+        // direction units, the hit distance is halved for a direction of length 2
+        let t = ray([0.0, 0.0, 0.0], [0.0, 0.0, -2.0]).intersect_sphere(center, 1.0);
+        assert_eq!(t, Some(2.0));
+        let t = ray([1.0, 0.0, 0.0], [0.0, 0.0, -1.0]).intersect_sphere(center, 1.0);
+        assert_eq!(t, Some(5.0));
+        let t = ray([1.5, 0.0, 0.0], [0.0, 0.0, -1.0]).intersect_sphere(center, 1.0);
+        assert_eq!(t, None);
+        let t = ray([0.0, 0.0, 0.0], [0.0, 0.0, 1.0]).intersect_sphere(center, 1.0);
+        assert_eq!(t, None);
+        // inside, only the far root is in front of the origin
+        let t = ray([0.0, 0.0, -5.5], [0.0, 0.0, -1.0]).intersect_sphere(center, 1.0);
+        assert_eq!(t, Some(0.5));
+    }
+
+    #[test]
+    fn triangle() {
+        // counter-clockwise seen from +Z
+        let triangle = (mx!(VR[0.0, 0.0, 0.0]), mx!(VR[3.0, 0.0, 0.0]), mx!(VR[0.0, 3.0, 0.0]));
+        let (t, u, v) = ray([1.0, 1.0, 2.0], [0.0, 0.0, -1.0])
+            .intersect_triangle(&triangle, true)
+            .unwrap();
+        assert!((t - 2.0).abs() < 1e-6 && (u - 1.0 / 3.0).abs() < 1e-6 && (v - 1.0 / 3.0).abs() < 1e-6);
+        let hit = |origin, direction, cull| ray(origin, direction).intersect_triangle(&triangle, cull);
+        // just outside the hypotenuse and the edges along the axes
+        assert_eq!(hit([1.51, 1.5, 2.0], [0.0, 0.0, -1.0], false), None);
+        assert_eq!(hit([-0.01, 1.0, 2.0], [0.0, 0.0, -1.0], false), None);
+        assert_eq!(hit([1.0, -0.01, 2.0], [0.0, 0.0, -1.0], false), None);
+        assert!(hit([1.49, 1.5, 2.0], [0.0, 0.0, -1.0], false).is_some());
+        // back face
+        assert!(hit([1.0, 1.0, -2.0], [0.0, 0.0, 1.0], true).is_none());
+        let (t, ..) = hit([1.0, 1.0, -2.0], [0.0, 0.0, 1.0], false).unwrap();
+        assert!((t - 2.0).abs() < 1e-6);
+        // parallel and behind
+        assert!(hit([1.0, 1.0, 2.0], [1.0, 0.0, 0.0], false).is_none());
+        assert!(hit([1.0, 1.0, 2.0], [0.0, 0.0, 1.0], false).is_none());
+    }
+
+    #[test]
+    fn plane() {
+        let (point, normal) = (mx!(VR[0.0, 2.0, 0.0, 1.0]), mx!(VR[0.0, 1.0, 0.0, 0.0]));
+        assert_eq!(
+            ray([5.0, 0.0, 1.0], [0.0, 1.0, 0.0]).intersect_plane(point, normal),
+            Some(2.0)
+        );
+        assert_eq!(
+            ray([0.0, 6.0, 0.0], [0.0, -2.0, 0.0]).intersect_plane(point, normal),
+            Some(2.0)
+        );
+        let t = ray([0.0, 0.0, 0.0], [1.0, 1.0, 0.0]).intersect_plane(point, normal);
+        assert_eq!(t, Some(2.0));
+        assert_eq!(
+            ray([0.0, 0.0, 0.0], [1.0, 0.0, 0.0]).intersect_plane(point, normal),
+            None
+        );
+        assert_eq!(
+            ray([0.0, 0.0, 0.0], [0.0, -1.0, 0.0]).intersect_plane(point, normal),
+            None
+        );
+    }
+
+    #[test]
+    fn aabb() {
+        let (min, max) = (mx!(VR[-1.0, -1.0, -1.0, 1.0]), mx!(VR[1.0, 1.0, 1.0, 1.0]));
+        let hit = |origin, direction| ray(origin, direction).intersect_aabb(min, max);
+        assert_eq!(hit([-5.0, 0.0, 0.0], [1.0, 0.0, 0.0]), Some((4.0, 6.0)));
+        assert_eq!(hit([0.5, 0.0, 0.0], [1.0, 0.0, 0.0]), Some((-1.5, 0.5)));
+        // grazing the (-1, 1, -1) corner
+        assert_eq!(hit([-2.0, 0.0, -2.0], [1.0, 1.0, 1.0]), Some((1.0, 1.0)));
+        assert_eq!(hit([-2.0, 0.1, -2.0], [1.0, 1.0, 1.0]), None);
+        assert_eq!(hit([5.0, 0.0, 0.0], [1.0, 0.0, 0.0]), None);
+        assert_eq!(hit([-5.0, 2.0, 0.0], [1.0, 0.0, 0.0]), None);
+    }
+
+    #[test]
+    fn aabb_parallel() {
+        let (min, max) = (mx!(VR[-1.0, -1.0, -1.0, 1.0]), mx!(VR[1.0, 1.0, 1.0, 1.0]));
+        let hit = |origin, direction| ray(origin, direction).intersect_aabb(min, max);
+        let aabb = Aabb::new(min, max);
+        let face = |origin, direction| ray(origin, direction).intersect_aabb_face(&aabb);
+        let entry = Some(Face {
+            axis: 0,
+            positive: false,
+        });
+        // lying in the top and bottom face planes, along an edge, and through the middle of the box
+        for origin in [[-5.0, 1.0, 0.0], [-5.0, -1.0, 0.5], [-5.0, 1.0, 1.0], [-5.0, 0.0, 0.0]] {
+            assert_eq!(hit(origin, [1.0, 0.0, 0.0]), Some((4.0, 6.0)));
+            assert_eq!(face(origin, [1.0, 0.0, 0.0]), Some((4.0, entry)));
+        }
+        // negative zero components are parallel too
+        assert_eq!(hit([0.0, 1.0, 5.0], [-0.0, 0.0, -1.0]), Some((4.0, 6.0)));
+        // parallel outside the slab, even by a tiny offset from the face
+        for origin in [[-5.0, 1.0001, 0.0], [-5.0, 0.0, -1.5]] {
+            assert_eq!(hit(origin, [1.0, 0.0, 0.0]), None);
+            assert_eq!(face(origin, [1.0, 0.0, 0.0]), None);
+        }
+    }
+
+    #[test]
+    fn disk() {
+        let (center, normal) = (mx!(VR[0.0, 0.0, -5.0, 1.0]), mx!(VR[0.0, 0.0, 1.0, 0.0]));
+        let t = ray([0.0, 0.0, 0.0], [0.0, 0.0, -1.0]).intersect_disk(center, normal, 1.0);
+        assert_eq!(t, Some(5.0));
+        let t = ray([0.0, 0.0, -10.0], [0.0, 0.0, 1.0]).intersect_disk(center, normal, 1.0);
+        assert_eq!(t, Some(5.0));
+        assert_eq!(
+            ray([2.0, 0.0, 0.0], [0.0, 0.0, -1.0]).intersect_disk(center, normal, 1.0),
+            None
+        );
+        assert_eq!(
+            ray([0.0, 0.0, 0.0], [0.0, 0.0, 1.0]).intersect_disk(center, normal, 1.0),
+            None
+        );
+        assert_eq!(
+            ray([0.0, 0.0, 0.0], [1.0, 0.0, 0.0]).intersect_disk(center, normal, 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn cylinder() {
+        // vertical cylinder from y = 0 to y = 2 with radius 1
+        let (base, axis) = (mx!(VR[0.0, 0.0, 0.0, 1.0]), mx!(VR[0.0, 1.0, 0.0, 0.0]));
+        let t = ray([-5.0, 1.0, 0.0], [1.0, 0.0, 0.0]).intersect_cylinder(base, axis, 1.0, 2.0);
+        assert!((t.unwrap() - 4.0).abs() < 1e-5);
+        let t = ray([0.0, 0.0, 0.0], [1.0, 0.0, 0.0]).intersect_cylinder(base, axis, 1.0, 2.0);
+        assert!((t.unwrap() - 1.0).abs() < 1e-5);
+        let t = ray([0.5, 5.0, 0.0], [0.0, -1.0, 0.0]).intersect_cylinder(base, axis, 1.0, 2.0);
+        assert!((t.unwrap() - 3.0).abs() < 1e-5);
+        let t = ray([0.5, -5.0, 0.0], [0.0, 1.0, 0.0]).intersect_cylinder(base, axis, 1.0, 2.0);
+        assert!((t.unwrap() - 5.0).abs() < 1e-5);
+        assert_eq!(
+            ray([-5.0, 3.0, 0.0], [1.0, 0.0, 0.0]).intersect_cylinder(base, axis, 1.0, 2.0),
+            None
+        );
+        assert_eq!(
+            ray([-5.0, 1.0, 2.0], [1.0, 0.0, 0.0]).intersect_cylinder(base, axis, 1.0, 2.0),
+            None
+        );
+        assert_eq!(
+            ray([2.0, 5.0, 0.0], [0.0, -1.0, 0.0]).intersect_cylinder(base, axis, 1.0, 2.0),
+            None
+        );
+    }
+
+    #[test]
+    fn aabb_face() {
+        let aabb = Aabb::new(mx!(VR[-1.0, -1.0, -1.0, 1.0]), mx!(VR[1.0, 1.0, 1.0, 1.0]));
+        let hit = |origin, direction| ray(origin, direction).intersect_aabb_face(&aabb);
+        let face = |axis, positive| Some(Face { axis, positive });
+        assert_eq!(hit([5.0, 0.0, 0.0], [-1.0, 0.0, 0.0]), Some((4.0, face(0, true))));
+        assert_eq!(hit([0.2, -3.0, 0.5], [0.0, 1.0, 0.0]), Some((2.0, face(1, false))));
+        assert_eq!(hit([-4.0, -4.0, 0.0], [1.0, 1.5, 0.0]), Some((3.0, face(0, false))));
+        assert_eq!(hit([0.0, 0.0, 0.0], [0.0, 0.0, 1.0]), Some((1.0, None)));
+        assert_eq!(hit([5.0, 3.0, 0.0], [-1.0, 0.0, 0.0]), None);
+        assert_eq!(hit([5.0, 0.0, 0.0], [1.0, 0.0, 0.0]), None);
+    }
+}
@@ -1,6 +1,1028 @@
-use crate::math::VR;
+#![allow(unused)]
+use crate::math::sampling::{cosine_hemisphere, halton_2d, Rng};
+use crate::math::{mx, Aabb, Mesh, Triangle, Vx, VxF, MX, V2, V3, VR};
+use crate::raytrace::bvh::{Bvh, BvhBuildStrategy};
+use crate::raytrace::material::{dielectric_scatter, Material};
+use crate::raytrace::ray::Ray;
+use std::ops::Range;
+
+/// Vertical field of view of `Scene::trace` in radians.
+const FIELD_OF_VIEW: f32 = std::f32::consts::FRAC_PI_3;
+
+/// Fraction of the primitive color lit regardless of the surface orientation, scaled by the ambient occlusion.
+const AMBIENT: f32 = 0.1;
+
+/// Distance and number of rays of the ambient occlusion estimate of `Scene::trace`.
+const OCCLUSION_RADIUS: f32 = 1.0;
+const OCCLUSION_SAMPLES: u32 = 16;
+
+/// Number of times a ray continues through dielectrics before `Scene::trace` renders it black.
+const MAX_DEPTH: u32 = 8;
+
+/// Offset of secondary ray origins along the surface normal, keeping them from hitting the surface they start from.
+const BIAS: f32 = 1e-3;
 
 pub struct Camera {
     pub position: VR<f32, 4>,
     pub direction: VR<f32, 4>,
+    /// View space distances along the direction mapped to depths 0 and 1 by `Scene::render_gbuffer`.
+    pub near: f32,
+    pub far: f32,
+    /// Time the shutter stays open, ray times are in `[0, shutter]`, moving primitives are blurred along their path.
+    pub shutter: f32,
+}
+
+impl Camera {
+    /// Return the view matrix of the camera with the world `Y` axis up, see `MX::look_at`.
+    pub fn view_matrix(&self) -> MX<f32, 4, 4> {
+        let (p, d) = (&self.position, &self.direction);
+        let eye = V3(p[0], p[1], p[2]);
+        MX::look_at(eye, eye + V3(d[0], d[1], d[2]), V3(0.0, 1.0, 0.0))
+    }
+
+    /// Return the unit right, up, and forward vectors of the camera in world space, see `view_matrix`.
+    fn basis(&self) -> [VR<f32, 4>; 3] {
+        let m = self.view_matrix();
+        let row = |i: usize, sign: f32| mx!(VR[m[4 * i] * sign, m[4 * i + 1] * sign, m[4 * i + 2] * sign, 0.0]);
+        [row(0, 1.0), row(1, 1.0), row(2, -1.0)]
+    }
+
+    /// Return the ray from the camera position through the image plane point at `ndc_x`, `ndc_y` in `[-1, 1]`, with
+    /// `+Y` up, for an image with `aspect` width over height and `fov` vertical field of view in radians.
+    ///
+    /// The ray time is the `time` fraction in `[0, 1]` of the shutter, with shutter 0 every ray is at time 0.
+    pub fn generate_ray(&self, ndc_x: f32, ndc_y: f32, aspect: f32, fov: f32, time: f32) -> Ray {
+        let [right, up, forward] = self.basis();
+        let half_height = (fov / 2.0).tan();
+        let direction = forward + right * (ndc_x * half_height * aspect) + up * (ndc_y * half_height);
+        Ray {
+            time: time * self.shutter,
+            ..Ray::new(self.position, direction)
+        }
+    }
+
+    /// Return the ray through the center of pixel `x`, `y` of a `width`x`height` image, pixel `0, 0` is the top-left
+    /// corner, with a `FIELD_OF_VIEW` vertical field of view, see `generate_ray`.
+    fn pixel_ray(&self, x: u32, y: u32, width: u32, height: u32, time: f32) -> Ray {
+        let ndc_x = (x as f32 + 0.5) / width as f32 * 2.0 - 1.0;
+        let ndc_y = 1.0 - (y as f32 + 0.5) / height as f32 * 2.0;
+        self.generate_ray(ndc_x, ndc_y, width as f32 / height as f32, FIELD_OF_VIEW, time)
+    }
+}
+
+/// Scene primitive with a `material`, positions are points (`w = 1`).
+pub enum Primitive {
+    /// Spheres move by `velocity` (`w = 0`) per unit of time, they are at `center + velocity * ray.time`.
+    Sphere {
+        center: VR<f32, 4>,
+        velocity: VR<f32, 4>,
+        radius: f32,
+        material: Material,
+    },
+    /// Triangles are hit from both sides.
+    Triangle { vertices: Triangle<3>, material: Material },
+    /// Mesh triangles are hit from both sides, the material is sampled by the interpolated texture coordinates.
+    Mesh { mesh: Mesh, material: Material },
+}
+
+/// Ray hit, see `Primitive::intersect`.
+struct Hit<'a> {
+    t: f32,
+    /// Unit shading normal (`w = 0`), perturbed by the material normal map.
+    normal: VR<f32, 4>,
+    /// Interpolated texture coordinates, zero for primitives other than meshes.
+    uv: V2<f32>,
+    primitive: &'a Primitive,
+}
+
+impl Primitive {
+    /// Intersect the primitive, returning the nearest hit.
+    fn intersect(&self, ray: &Ray) -> Option<Hit<'_>> {
+        let v3 = |v: &VR<f32, 3>| V3(v[0], v[1], v[2]);
+        let (t, normal, uv) = match self {
+            Primitive::Sphere {
+                center,
+                velocity,
+                radius,
+                ..
+            } => {
+                let center = *center + *velocity * ray.time;
+                let t = ray.intersect_sphere(center, *radius)?;
+                (t, (ray.at(t) - center) / *radius, V2(0.0, 0.0))
+            }
+            Primitive::Triangle { vertices, .. } => {
+                let (t, ..) = ray.intersect_triangle(vertices, false)?;
+                let normal = (v3(&vertices.1) - v3(&vertices.0))
+                    .cross(&(v3(&vertices.2) - v3(&vertices.0)))
+                    .norm();
+                (t, mx!(VR[normal.0, normal.1, normal.2, 0.0]), V2(0.0, 0.0))
+            }
+            Primitive::Mesh { mesh, material } => {
+                let (i, (t, u, v)) = (0..mesh.indices.len())
+                    .filter_map(|i| ray.intersect_triangle(&mesh.triangle(i), false).map(|hit| (i, hit)))
+                    .min_by(|a, b| a.1 .0.total_cmp(&b.1 .0))?;
+                let (a, b, c) = mesh.triangle(i);
+                let [uv_a, uv_b, uv_c] = mesh.triangle_uvs(i);
+                let (ab, ac) = (v3(&b) - v3(&a), v3(&c) - v3(&a));
+                let (uv_ab, uv_ac) = (uv_b - uv_a, uv_c - uv_a);
+                // positions change along the edges as the texture coordinates, solve for the change per u and v,
+                // degenerate texture coordinates have zero tangents
+                let det = uv_ab.0 * uv_ac.1 - uv_ac.0 * uv_ab.1;
+                let inv = if det.abs() > f32::EPSILON { 1.0 / det } else { 0.0 };
+                let tangent = (ab * uv_ac.1 - ac * uv_ab.1) * inv;
+                let bitangent = (ac * uv_ab.0 - ab * uv_ac.0) * inv;
+                let uv = uv_a * (1.0 - u - v) + uv_b * u + uv_c * v;
+                let normal = material.shading_normal(ab.cross(&ac).norm(), tangent, bitangent, uv);
+                (t, mx!(VR[normal.0, normal.1, normal.2, 0.0]), uv)
+            }
+        };
+        Some(Hit {
+            t,
+            normal,
+            uv,
+            primitive: self,
+        })
+    }
+
+    /// Return the bounds of the primitive at ray times in `[0, shutter]`, see `Bvh`.
+    pub fn bounds(&self, shutter: f32) -> Aabb {
+        let point = |v: &VR<f32, 3>| mx!(VR[v[0], v[1], v[2], 1.0]);
+        match self {
+            Primitive::Sphere {
+                center,
+                velocity,
+                radius,
+                ..
+            } => {
+                let offset = mx!(VR[*radius, *radius, *radius, 0.0]);
+                let end = *center + *velocity * shutter;
+                Aabb::new(*center - offset, *center + offset).merge(&Aabb::new(end - offset, end + offset))
+            }
+            Primitive::Triangle { vertices, .. } => {
+                Aabb::from_points(&[point(&vertices.0), point(&vertices.1), point(&vertices.2)])
+            }
+            Primitive::Mesh { mesh, .. } => Aabb::from_points(&mesh.positions.iter().map(point).collect::<Vec<_>>()),
+        }
+    }
+
+    fn material(&self) -> &Material {
+        match self {
+            Primitive::Sphere { material, .. } | Primitive::Triangle { material, .. } => material,
+            Primitive::Mesh { material, .. } => material,
+        }
+    }
+}
+
+/// Scene light, positions and corners are points (`w = 1`) and directions and edges are vectors (`w = 0`).
+///
+/// `intensity` is the linear color reaching a surface facing the light at unit distance, for quads per unit of area,
+/// and at any distance for directional lights.
+pub enum Light {
+    /// Light emitted from `position` in all directions, falling off with the squared distance.
+    Point { position: VR<f32, 4>, intensity: V3<f32> },
+    /// Parallel light travelling along `direction` from infinitely far, like the sun, without falloff.
+    Directional { direction: VR<f32, 4>, intensity: V3<f32> },
+    /// Point light emitting only within `cone_angle` radians of `direction`.
+    ///
+    /// The light falls off with the squared distance, and linearly with the angle to `direction`, from full at the
+    /// axis to none at the cone edge.
+    Spot {
+        position: VR<f32, 4>,
+        direction: VR<f32, 4>,
+        cone_angle: f32,
+        intensity: V3<f32>,
+    },
+    /// Parallelogram spanned by `edge_u` and `edge_v` from `corner`, emitting from both sides.
+    ///
+    /// Surfaces partially hidden from the quad are in penumbra, rendering soft shadows.
+    Quad {
+        corner: VR<f32, 4>,
+        edge_u: VR<f32, 4>,
+        edge_v: VR<f32, 4>,
+        intensity: V3<f32>,
+    },
+}
+
+impl Light {
+    /// Sample a point of the light seen from `point` with the unit square `sample`, returning the unit direction and
+    /// the distance to it, and the light reaching `point` from it.
+    ///
+    /// Quad points are sampled uniformly by area, their light is weighted by the inverse of the solid angle density,
+    /// `area * cos / distance²`, so the average of many samples is the light of the whole quad. Directional lights are
+    /// at an infinite distance, so any primitive towards them casts a shadow.
+    fn sample(&self, point: VR<f32, 4>, sample: V2<f32>) -> (VR<f32, 4>, f32, V3<f32>) {
+        match self {
+            Light::Point { position, intensity } => {
+                let offset = *position - point;
+                let distance = offset.dot(&offset).sqrt();
+                (offset / distance, distance, *intensity / (distance * distance))
+            }
+            Light::Directional { direction, intensity } => {
+                let direction = *direction / -direction.dot(direction).sqrt();
+                (direction, f32::INFINITY, *intensity)
+            }
+            Light::Spot {
+                position,
+                direction,
+                cone_angle,
+                intensity,
+            } => {
+                let offset = *position - point;
+                let distance = offset.dot(&offset).sqrt();
+                let axis = *direction / direction.dot(direction).sqrt();
+                let angle = (-offset.dot(&axis) / distance).clamp(-1.0, 1.0).acos();
+                let falloff = (1.0 - angle / cone_angle).max(0.0);
+                (
+                    offset / distance,
+                    distance,
+                    *intensity * (falloff / (distance * distance)),
+                )
+            }
+            Light::Quad {
+                corner,
+                edge_u,
+                edge_v,
+                intensity,
+            } => {
+                let offset = *corner + *edge_u * sample.0 + *edge_v * sample.1 - point;
+                let distance = offset.dot(&offset).sqrt();
+                let direction = offset / distance;
+                let v3 = |v: &VR<f32, 4>| V3(v[0], v[1], v[2]);
+                let normal = v3(edge_u).cross(&v3(edge_v));
+                let cos = normal.dot(&v3(&direction)).abs();
+                (direction, distance, *intensity * (cos / (distance * distance)))
+            }
+        }
+    }
+}
+
+/// Per pixel geometry of an image, pixel `(x, y)` is at `width * y + x`, see `Scene::render_gbuffer`.
+pub struct GBuffer {
+    pub width: u32,
+    pub height: u32,
+    /// View space distance along the camera direction, from the camera `near` at 0 to `far` at 1, clamped.
+    pub depth: Vec<f32>,
+    /// Unit world space shading normal, facing the camera.
+    pub normal: Vec<V3<f32>>,
+    /// Material color, unlit.
+    pub albedo: Vec<V3<f32>>,
+}
+
+/// Primitives seen by a camera, rendered with `trace`.
+pub struct Scene {
+    pub camera: Camera,
+    pub primitives: Vec<Primitive>,
+    /// Scenes without lights are lit by a light at the camera.
+    pub lights: Vec<Light>,
+    /// Color of the pixels whose rays miss all primitives.
+    pub background: V3<f32>,
+    /// Hierarchy of the primitive bounds searched for hits, without it every primitive is intersected. It must be
+    /// built again with `build_bvh` after changing the primitives or the camera shutter.
+    pub bvh: Option<Bvh>,
+}
+
+impl Scene {
+    /// Return the color of pixel `x`, `y` of a `width`x`height` image, pixel `0, 0` is the top-left corner.
+    ///
+    /// The primary ray goes through the pixel center with a `FIELD_OF_VIEW` vertical field of view. The nearest
+    /// primitive hit is lit by the `AMBIENT` fraction of its color, darkened by the `ambient_occlusion` of the hit,
+    /// and by the `direct_light` of the scene lights, sampled with `rng`. Without lights, surfaces facing the camera
+    /// have the full primitive color. The ray time is sampled with `rng` too, averaging many samples of moving
+    /// primitives renders motion blur.
+    ///
+    /// Rays hitting dielectrics continue refracted or reflected, chosen with `rng`, up to `MAX_DEPTH` times.
+    pub fn trace(&self, x: u32, y: u32, width: u32, height: u32, rng: &mut Rng) -> V3<f32> {
+        let ray = self.camera.pixel_ray(x, y, width, height, rng.next_f32());
+        self.radiance(&ray, rng, 0)
+    }
+
+    /// Return the colors of a `width`x`height` image, each pixel is the average of `samples` calls to `trace`, pixel
+    /// `(x, y)` is at `width * y + x`.
+    pub fn render(&self, width: u32, height: u32, samples: u32, rng: &mut Rng) -> Vec<V3<f32>> {
+        self.render_rows(width, height, 0..height, samples, rng)
+    }
+
+    /// Return the colors of the `rows` of a `width`x`height` image, see `render`.
+    ///
+    /// Rendering consecutive rows with the same `rng` returns the same colors as rendering the whole image at once.
+    pub fn render_rows(&self, width: u32, height: u32, rows: Range<u32>, samples: u32, rng: &mut Rng) -> Vec<V3<f32>> {
+        (width * rows.start..width * rows.end)
+            .map(|i| {
+                let color = (0..samples).fold(V3(0.0, 0.0, 0.0), |color, _| {
+                    color + self.trace(i % width, i / width, width, height, rng)
+                });
+                color / samples as f32
+            })
+            .collect()
+    }
+
+    /// Return the color seen by `ray` after continuing through `depth` dielectric hits, see `trace`.
+    fn radiance(&self, ray: &Ray, rng: &mut Rng, depth: u32) -> V3<f32> {
+        let Some(Hit {
+            t,
+            normal,
+            uv,
+            primitive,
+        }) = self.hit(ray)
+        else {
+            return self.background;
+        };
+        let point = ray.at(t);
+        if let Material::Dielectric { ior } = primitive.material() {
+            if depth >= MAX_DEPTH {
+                return V3(0.0, 0.0, 0.0);
+            }
+            let v3 = |v: &VR<f32, 4>| V3(v[0], v[1], v[2]);
+            let d = dielectric_scatter(v3(&ray.direction), v3(&normal), *ior, rng.next_f32());
+            let direction = mx!(VR[d.0, d.1, d.2, 0.0]);
+            // refracted rays start below the surface, reflected ones above
+            let side = normal.dot(&direction).signum();
+            let ray = Ray {
+                time: ray.time,
+                ..Ray::new(point + normal * (side * BIAS), direction)
+            };
+            return self.radiance(&ray, rng, depth + 1);
+        }
+        let cos = normal.dot(&ray.direction);
+        // triangles are hit from both sides, the normal faces the ray
+        let normal = if cos > 0.0 { -normal } else { normal };
+        let occlusion = self.ambient_occlusion(point, normal, ray.time, OCCLUSION_RADIUS, OCCLUSION_SAMPLES);
+        let light = match self.lights.is_empty() {
+            true => V3(1.0, 1.0, 1.0) * ((1.0 - AMBIENT) * cos.abs()),
+            false => self.direct_light(point, normal, ray.time, rng),
+        };
+        primitive.material().color(uv) * (light + AMBIENT * occlusion)
+    }
+
+    /// Return the depth, normal, and albedo of the primary rays of a `width`x`height` image seen by `camera`, with the
+    /// same rays as `trace` at time 0.
+    ///
+    /// Pixels whose rays miss all primitives have depth 1, zero normals, and the background albedo.
+    pub fn render_gbuffer(&self, camera: &Camera, width: u32, height: u32) -> GBuffer {
+        let [.., forward] = camera.basis();
+        let mut gbuffer = GBuffer {
+            width,
+            height,
+            depth: Vec::with_capacity((width * height) as usize),
+            normal: Vec::with_capacity((width * height) as usize),
+            albedo: Vec::with_capacity((width * height) as usize),
+        };
+        for (x, y) in (0..height).flat_map(|y| (0..width).map(move |x| (x, y))) {
+            let ray = camera.pixel_ray(x, y, width, height, 0.0);
+            let (depth, normal, albedo) = match self.hit(&ray) {
+                Some(Hit {
+                    t,
+                    normal,
+                    uv,
+                    primitive,
+                }) => {
+                    let distance = t * ray.direction.dot(&forward);
+                    let depth = ((distance - camera.near) / (camera.far - camera.near)).clamp(0.0, 1.0);
+                    let normal = if normal.dot(&ray.direction) > 0.0 {
+                        -normal
+                    } else {
+                        normal
+                    };
+                    (
+                        depth,
+                        V3(normal[0], normal[1], normal[2]),
+                        primitive.material().color(uv),
+                    )
+                }
+                None => (1.0, V3(0.0, 0.0, 0.0), self.background),
+            };
+            gbuffer.depth.push(depth);
+            gbuffer.normal.push(normal);
+            gbuffer.albedo.push(albedo);
+        }
+        gbuffer
+    }
+
+    /// Return the light reaching `point` on a surface with unit `normal` (`w = 0`) from the scene lights, with moving
+    /// primitives at `time`.
+    ///
+    /// Each light is sampled once with `rng`, area lights are estimated from a random point, which is noisy but
+    /// averages to the penumbra over many samples. Lights behind the surface or hidden by primitives do not
+    /// contribute.
+    pub fn direct_light(&self, point: VR<f32, 4>, normal: VR<f32, 4>, time: f32, rng: &mut Rng) -> V3<f32> {
+        let origin = point + normal * BIAS;
+        self.lights.iter().fold(V3(0.0, 0.0, 0.0), |light, source| {
+            let (direction, distance, intensity) = source.sample(origin, rng.next_v2());
+            let cos = normal.dot(&direction);
+            let shadow = Ray {
+                time,
+                ..Ray::new(origin, direction)
+            };
+            match cos > 0.0 && self.hit(&shadow).is_none_or(|hit| hit.t >= distance - BIAS) {
+                true => light + intensity * cos,
+                false => light,
+            }
+        })
+    }
+
+    /// Return the fraction of the hemisphere around `normal` (`w = 0`) at `point` that is unoccluded within `radius`,
+    /// with moving primitives at `time`.
+    ///
+    /// Directions are cosine weighted, occluders near the normal darken more than occluders near the horizon, and
+    /// spread with the Halton sequence, so the estimate is the same for every call.
+    pub fn ambient_occlusion(
+        &self,
+        point: VR<f32, 4>,
+        normal: VR<f32, 4>,
+        time: f32,
+        radius: f32,
+        samples: u32,
+    ) -> f32 {
+        let origin = point + normal * BIAS;
+        let normal = V3(normal[0], normal[1], normal[2]);
+        let unoccluded = (0..samples)
+            .filter(|&i| {
+                let direction = cosine_hemisphere(normal, halton_2d(i));
+                let ray = Ray {
+                    time,
+                    ..Ray::new(origin, mx!(VR[direction.0, direction.1, direction.2, 0.0]))
+                };
+                self.hit(&ray).is_none_or(|hit| hit.t >= radius)
+            })
+            .count();
+        unoccluded as f32 / samples.max(1) as f32
+    }
+
+    /// Build the `bvh` of the primitive bounds over the camera shutter with `strategy`.
+    pub fn build_bvh(&mut self, strategy: BvhBuildStrategy) {
+        let bounds = self
+            .primitives
+            .iter()
+            .map(|primitive| primitive.bounds(self.camera.shutter))
+            .collect::<Vec<_>>();
+        self.bvh = Some(Bvh::new(&bounds, strategy));
+    }
+
+    /// Return the nearest hit of the primitives, searching the `bvh` if built.
+    fn hit(&self, ray: &Ray) -> Option<Hit<'_>> {
+        let Some(bvh) = &self.bvh else {
+            return self
+                .primitives
+                .iter()
+                .filter_map(|primitive| primitive.intersect(ray))
+                .min_by(|a, b| a.t.total_cmp(&b.t));
+        };
+        let intersect = |i: usize| self.primitives[i].intersect(ray).map(|hit| hit.t);
+        // the hit is computed again for the nearest primitive only
+        let (nearest, _) = bvh.intersect(ray, &mut 0, intersect)?;
+        self.primitives[nearest].intersect(ray)
+    }
+}
+
+mod test {
+    use crate::math::sampling::Rng;
+    use crate::math::{mx, Mesh, VxF, MX, V2, V3, VR};
+    use crate::raytrace::bvh::BvhBuildStrategy;
+    use crate::raytrace::material::{Addressing, Material, Texture};
+    use crate::raytrace::ray::Ray;
+    use crate::raytrace::scene::{Camera, Light, Primitive, Scene};
+
+    #[test]
+    fn view_matrix() {
+        let camera = Camera {
+            position: mx!(VR[1.0, 2.0, 5.0, 1.0]),
+            direction: mx!(VR[0.0, 0.0, -3.0, 0.0]),
+            near: 0.1,
+            far: 100.0,
+            shutter: 0.0,
+        };
+        // looking down -Z the view basis is the world basis, only the position is moved to the origin
+        let expected = mx!([1.0, 0.0, 0.0, -1.0][0.0, 1.0, 0.0, -2.0][0.0, 0.0, 1.0, -5.0][0.0, 0.0, 0.0, 1.0]);
+        assert!(camera.view_matrix() == expected);
+        let eye = camera.view_matrix().multiply(&mx!(VC[1.0, 2.0, 5.0, 1.0]));
+        assert!(eye == mx!(VC[0.0, 0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn generate_ray() {
+        let camera = Camera {
+            position: mx!(VR[1.0, 2.0, 3.0, 1.0]),
+            direction: mx!(VR[1.0, 0.0, -1.0, 0.0]),
+            near: 0.1,
+            far: 100.0,
+            shutter: 0.0,
+        };
+        let v3 = |v: VR<f32, 4>| V3(v[0], v[1], v[2]);
+        let fov = std::f32::consts::FRAC_PI_2;
+        let ray = camera.generate_ray(0.0, 0.0, 2.0, fov, 0.0);
+        assert!(ray.origin == camera.position);
+        assert!(v3(ray.direction).approx_eq(&V3(1.0, 0.0, -1.0).norm(), 1e-6) && ray.direction[3] == 0.0);
+        // with a 90 degree field of view the top edge is 45 degrees above the direction, with the world Y axis up
+        let ray = camera.generate_ray(0.0, 1.0, 2.0, fov, 0.0);
+        assert!(v3(ray.direction).approx_eq(&V3(1.0, 2.0f32.sqrt(), -1.0).norm(), 1e-6));
+        // the right edge is scaled by the aspect ratio, the direction is still normalized
+        let direction = v3(camera.generate_ray(1.0, -1.0, 2.0, fov, 0.0).direction);
+        assert!((direction.mag() - 1.0).abs() < 1e-6);
+        assert!(direction.1 < 0.0 && direction.0 > direction.2.abs());
+    }
+
+    #[test]
+    fn ambient_occlusion() {
+        let triangle = |y: f32, size: f32| Primitive::Triangle {
+            vertices: (mx!(VR[-size, y, size]), mx!(VR[size, y, size]), mx!(VR[0.0, y, -size])),
+            material: Material::diffuse(V3(1.0, 1.0, 1.0)),
+        };
+        let mut scene = Scene {
+            camera: Camera {
+                position: mx!(VR[0.0, 1.0, 5.0, 1.0]),
+                direction: mx!(VR[0.0, 0.0, -1.0, 0.0]),
+                near: 0.1,
+                far: 100.0,
+                shutter: 0.0,
+            },
+            primitives: vec![triangle(0.0, 50.0)],
+            lights: Vec::new(),
+            background: V3(0.0, 0.0, 0.0),
+            bvh: None,
+        };
+        let (point, up) = (mx!(VR[0.0, 0.0, 0.0, 1.0]), mx!(VR[0.0, 1.0, 0.0, 0.0]));
+        // the floor does not occlude itself
+        assert_eq!(scene.ambient_occlusion(point, up, 0.0, 1.0, 64), 1.0);
+        // a sphere just above the point occludes most of its hemisphere, points away from it are open
+        scene.primitives.push(Primitive::Sphere {
+            center: mx!(VR[0.0, 0.5, 0.0, 1.0]),
+            velocity: mx!(VR[0.0, 0.0, 0.0, 0.0]),
+            radius: 0.45,
+            material: Material::diffuse(V3(1.0, 1.0, 1.0)),
+        });
+        let near = scene.ambient_occlusion(point, up, 0.0, 1.0, 64);
+        let open = scene.ambient_occlusion(mx!(VR[10.0, 0.0, 0.0, 1.0]), up, 0.0, 1.0, 64);
+        assert!(near < 0.5 && open == 1.0, "near {near} open {open}");
+        // a ceiling at half the radius occludes the directions with cosines above one half, a quarter of the cosine
+        // weighted hemisphere is open
+        scene.primitives[1] = triangle(0.5, 50.0);
+        assert!((scene.ambient_occlusion(point, up, 0.0, 1.0, 64) - 0.25).abs() < 0.05);
+        assert_eq!(scene.ambient_occlusion(point, up, 0.0, 0.4, 64), 1.0);
+    }
+
+    #[test]
+    fn normal_map() {
+        // a quad facing +Z with u along +X and v along -Y
+        let quad = |texel: V3<f32>| Primitive::Mesh {
+            mesh: Mesh {
+                positions: vec![
+                    mx!(VR[-1.0, 1.0, 0.0]),
+                    mx!(VR[-1.0, -1.0, 0.0]),
+                    mx!(VR[1.0, -1.0, 0.0]),
+                    mx!(VR[1.0, 1.0, 0.0]),
+                ],
+                uvs: vec![V2(0.0, 0.0), V2(0.0, 1.0), V2(1.0, 1.0), V2(1.0, 0.0)],
+                indices: vec![[0, 1, 2], [0, 2, 3]],
+            },
+            material: Material::Diffuse {
+                color: V3(1.0, 1.0, 1.0),
+                texture: None,
+                normal_map: Some(Texture::new(1, 1, vec![texel])),
+            },
+        };
+        let normal = |primitive: &Primitive, x: f32, y: f32| {
+            let ray = Ray::new(mx!(VR[x, y, 5.0, 1.0]), mx!(VR[0.0, 0.0, -1.0, 0.0]));
+            let hit = primitive.intersect(&ray).unwrap();
+            assert!((hit.t - 5.0).abs() < 1e-5);
+            V3(hit.normal[0], hit.normal[1], hit.normal[2])
+        };
+        // the flat map keeps the geometric normal on both triangles
+        let flat = quad(V3(0.5, 0.5, 1.0));
+        assert!(normal(&flat, -0.5, -0.2).approx_eq(&V3(0.0, 0.0, 1.0), 1e-6));
+        assert!(normal(&flat, 0.5, 0.2).approx_eq(&V3(0.0, 0.0, 1.0), 1e-6));
+        // tangent space x follows u and y follows v
+        assert!(normal(&quad(V3(1.0, 0.5, 0.5)), 0.5, 0.2).approx_eq(&V3(1.0, 0.0, 0.0), 1e-6));
+        assert!(normal(&quad(V3(0.5, 1.0, 0.5)), -0.5, -0.2).approx_eq(&V3(0.0, -1.0, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn texture() {
+        let (red, green, blue, white) = (
+            V3(1.0, 0.0, 0.0),
+            V3(0.0, 1.0, 0.0),
+            V3(0.0, 0.0, 1.0),
+            V3(1.0, 1.0, 1.0),
+        );
+        // a quad facing +Z from (-1, -1) to (1, 1) with texture coordinates from (0, 0) at the top-left to (2, 2)
+        let quad = |addressing: Addressing| Primitive::Mesh {
+            mesh: Mesh {
+                positions: vec![
+                    mx!(VR[-1.0, 1.0, 0.0]),
+                    mx!(VR[-1.0, -1.0, 0.0]),
+                    mx!(VR[1.0, -1.0, 0.0]),
+                    mx!(VR[1.0, 1.0, 0.0]),
+                ],
+                uvs: vec![V2(0.0, 0.0), V2(0.0, 2.0), V2(2.0, 2.0), V2(2.0, 0.0)],
+                indices: vec![[0, 1, 2], [0, 2, 3]],
+            },
+            material: Material::Diffuse {
+                color: V3(0.5, 0.5, 0.5),
+                texture: Some(Texture::new(2, 2, vec![red, green, blue, white]).addressing(addressing)),
+                normal_map: None,
+            },
+        };
+        let color = |primitive: &Primitive, x: f32, y: f32| {
+            let ray = Ray::new(mx!(VR[x, y, 5.0, 1.0]), mx!(VR[0.0, 0.0, -1.0, 0.0]));
+            let hit = primitive.intersect(&ray).unwrap();
+            hit.primitive.material().color(hit.uv) * 2.0
+        };
+        // the top-left quarter of the quad is the whole texture
+        let (clamp, wrap) = (quad(Addressing::Clamp), quad(Addressing::Wrap));
+        assert!(color(&clamp, -0.75, 0.75) == red && color(&clamp, -0.25, 0.75) == green);
+        assert!(color(&clamp, -0.75, 0.25) == blue && color(&clamp, -0.25, 0.25) == white);
+        // outside the texture clamped coordinates extend the edges, wrapped coordinates repeat the texture
+        assert!(color(&clamp, 0.25, 0.75) == green && color(&wrap, 0.25, 0.75) == red);
+        assert!(color(&clamp, -0.75, -0.25) == blue && color(&wrap, -0.75, -0.25) == red);
+        assert!(color(&clamp, 0.75, -0.75) == white && color(&wrap, 0.75, -0.75) == white);
+        // materials without textures have the same color everywhere
+        assert!(Material::diffuse(red).color(V2(0.3, 0.6)) == red);
+    }
+
+    #[test]
+    fn lights() {
+        let white = V3(1.0, 1.0, 1.0);
+        let mut scene = Scene {
+            camera: Camera {
+                position: mx!(VR[0.0, 1.0, 5.0, 1.0]),
+                direction: mx!(VR[0.0, 0.0, -1.0, 0.0]),
+                near: 0.1,
+                far: 100.0,
+                shutter: 0.0,
+            },
+            primitives: Vec::new(),
+            // a unit square 2 units above the origin
+            lights: vec![Light::Quad {
+                corner: mx!(VR[-0.5, 2.0, -0.5, 1.0]),
+                edge_u: mx!(VR[1.0, 0.0, 0.0, 0.0]),
+                edge_v: mx!(VR[0.0, 0.0, 1.0, 0.0]),
+                intensity: white,
+            }],
+            background: V3(0.0, 0.0, 0.0),
+            bvh: None,
+        };
+        let (point, up) = (mx!(VR[0.0, 0.0, 0.0, 1.0]), mx!(VR[0.0, 1.0, 0.0, 0.0]));
+        let light = |scene: &Scene| -> Vec<V3<f32>> {
+            let mut rng = Rng::new(3);
+            (0..4096)
+                .map(|_| scene.direct_light(point, up, 0.0, &mut rng))
+                .collect()
+        };
+        let unoccluded = light(&scene);
+        // the average converges to the light of the whole square, the integral of distance² / (x² + y² + distance²)²
+        let mean = unoccluded.iter().map(|light| light.0).sum::<f32>() / unoccluded.len() as f32;
+        assert!((mean - 0.2308).abs() < 0.005, "mean {mean}");
+        // a primitive aside does not shadow the point, which is fully lit by every sample
+        scene.primitives.push(Primitive::Sphere {
+            center: mx!(VR[3.0, 1.0, 0.0, 1.0]),
+            velocity: mx!(VR[0.0, 0.0, 0.0, 0.0]),
+            radius: 0.5,
+            material: Material::diffuse(white),
+        });
+        assert!(light(&scene) == unoccluded);
+        // a sphere between the point and the light hides the whole square
+        scene.primitives.push(Primitive::Sphere {
+            center: mx!(VR[0.0, 1.0, 0.0, 1.0]),
+            velocity: mx!(VR[0.0, 0.0, 0.0, 0.0]),
+            radius: 0.5,
+            material: Material::diffuse(white),
+        });
+        assert!(light(&scene).iter().all(|&light| light == V3(0.0, 0.0, 0.0)));
+        // and a point below the edge of the sphere is in penumbra
+        let mut rng = Rng::new(3);
+        let penumbra = (0..4096)
+            .map(|_| scene.direct_light(mx!(VR[0.6, 0.0, 0.0, 1.0]), up, 0.0, &mut rng).0)
+            .sum::<f32>();
+        assert!(0.0 < penumbra && penumbra < unoccluded.iter().map(|light| light.0).sum::<f32>());
+        // point lights fall off with the squared distance
+        scene.lights = vec![Light::Point {
+            position: mx!(VR[0.0, 0.0, 2.0, 1.0]),
+            intensity: V3(4.0, 4.0, 4.0),
+        }];
+        let light = scene.direct_light(point, mx!(VR[0.0, 0.0, 1.0, 0.0]), 0.0, &mut rng);
+        assert!(light.approx_eq(&white, 1e-2));
+    }
+
+    #[test]
+    fn directional_light() {
+        let white = V3(1.0, 1.0, 1.0);
+        let floor = Primitive::Triangle {
+            vertices: (
+                mx!(VR[-50.0, 0.0, 50.0]),
+                mx!(VR[50.0, 0.0, 50.0]),
+                mx!(VR[0.0, 0.0, -50.0]),
+            ),
+            material: Material::diffuse(white),
+        };
+        let mut scene = Scene {
+            camera: Camera {
+                position: mx!(VR[0.0, 1.0, 5.0, 1.0]),
+                direction: mx!(VR[0.0, 0.0, -1.0, 0.0]),
+                near: 0.1,
+                far: 100.0,
+                shutter: 0.0,
+            },
+            primitives: vec![floor],
+            // the sun 60 degrees above the horizon, travelling down and towards -Z
+            lights: vec![Light::Directional {
+                direction: mx!(VR[0.0, -3.0f32.sqrt(), -1.0, 0.0]),
+                intensity: white,
+            }],
+            background: V3(0.0, 0.0, 0.0),
+            bvh: None,
+        };
+        let up = mx!(VR[0.0, 1.0, 0.0, 0.0]);
+        let points = [[0.0, 0.0], [10.0, -20.0], [-30.0, 5.0]].map(|[x, z]| mx!(VR[x, 0.0, z, 1.0]));
+        let light = |scene: &Scene, point| scene.direct_light(point, up, 0.0, &mut Rng::new(3));
+        // the light is the same everywhere on the floor, weighted by the cosine of the angle to the normal
+        for point in points {
+            assert!(light(&scene, point).approx_eq(&(white * (3.0f32.sqrt() / 2.0)), 1e-5));
+        }
+        // a sphere far towards the light still shadows the point below it
+        scene.primitives.push(Primitive::Sphere {
+            center: mx!(VR[0.0, 3.0f32.sqrt() * 20.0, 20.0, 1.0]),
+            velocity: mx!(VR[0.0, 0.0, 0.0, 0.0]),
+            radius: 1.0,
+            material: Material::diffuse(white),
+        });
+        assert!(light(&scene, points[0]) == V3(0.0, 0.0, 0.0));
+        assert!(light(&scene, points[1]) != V3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn spot_light() {
+        let white = V3(1.0, 1.0, 1.0);
+        let scene = Scene {
+            camera: Camera {
+                position: mx!(VR[0.0, 1.0, 5.0, 1.0]),
+                direction: mx!(VR[0.0, 0.0, -1.0, 0.0]),
+                near: 0.1,
+                far: 100.0,
+                shutter: 0.0,
+            },
+            primitives: Vec::new(),
+            // a spot 1 unit above the origin pointing down, with a 45 degree cone
+            lights: vec![Light::Spot {
+                position: mx!(VR[0.0, 1.0, 0.0, 1.0]),
+                direction: mx!(VR[0.0, -2.0, 0.0, 0.0]),
+                cone_angle: std::f32::consts::FRAC_PI_4,
+                intensity: white,
+            }],
+            background: V3(0.0, 0.0, 0.0),
+            bvh: None,
+        };
+        let up = mx!(VR[0.0, 1.0, 0.0, 0.0]);
+        let light = |x: f32| scene.direct_light(mx!(VR[x, 0.0, 0.0, 1.0]), up, 0.0, &mut Rng::new(3));
+        // fully lit below the spot, shadow rays start slightly above the surface
+        assert!(light(0.0).approx_eq(&white, 1e-2));
+        // half way to the cone edge the light is halved, and reduced by the distance and cosine
+        let x = (std::f32::consts::PI / 8.0).tan();
+        let expected = 0.5 * (1.0 / (1.0 + x * x)) * (1.0 / (1.0 + x * x).sqrt());
+        assert!(light(x).approx_eq(&(white * expected), 1e-2));
+        // points on and outside the cone edge are not lit
+        for x in [1.0, 1.5, 3.0, -2.0] {
+            assert!(light(x) == V3(0.0, 0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn gbuffer() {
+        let gray = V3(0.5, 0.5, 0.5);
+        let scene = Scene {
+            // looking 45 degrees down at a floor, the top rows are 15 degrees below the horizon
+            camera: Camera {
+                position: mx!(VR[0.0, 1.0, 0.0, 1.0]),
+                direction: mx!(VR[0.0, -1.0, -1.0, 0.0]),
+                near: 0.5,
+                far: 10.0,
+                shutter: 0.0,
+            },
+            primitives: vec![Primitive::Triangle {
+                vertices: (
+                    mx!(VR[-50.0, 0.0, 50.0]),
+                    mx!(VR[50.0, 0.0, 50.0]),
+                    mx!(VR[0.0, 0.0, -50.0]),
+                ),
+                material: Material::diffuse(gray),
+            }],
+            lights: Vec::new(),
+            background: V3(0.0, 0.0, 0.2),
+            bvh: None,
+        };
+        let gbuffer = scene.render_gbuffer(&scene.camera, 4, 4);
+        assert!(gbuffer
+            .normal
+            .iter()
+            .all(|normal| normal.approx_eq(&V3(0.0, 1.0, 0.0), 1e-6)));
+        assert!(gbuffer.albedo.iter().all(|&albedo| albedo == gray));
+        // without roll the floor depth is constant along rows and increases towards the top rows
+        let row = |y: usize| &gbuffer.depth[4 * y..4 * y + 4];
+        for y in 0..4 {
+            assert!(row(y).iter().all(|depth| (depth - row(y)[0]).abs() < 1e-5));
+            assert!(0.0 < row(y)[0] && row(y)[0] < 1.0);
+        }
+        assert!(row(0)[0] > row(1)[0] && row(1)[0] > row(2)[0] && row(2)[0] > row(3)[0]);
+        // looking up the rays miss the floor
+        let camera = Camera {
+            direction: mx!(VR[0.0, 1.0, -1.0, 0.0]),
+            ..scene.camera
+        };
+        let gbuffer = scene.render_gbuffer(&camera, 4, 4);
+        assert!(gbuffer.depth.iter().all(|&depth| depth == 1.0));
+        assert!(gbuffer.albedo.iter().all(|&albedo| albedo == scene.background));
+    }
+
+    #[test]
+    fn motion_blur() {
+        let sphere = |center: [f32; 3], velocity: [f32; 3]| Primitive::Sphere {
+            center: mx!(VR[center[0], center[1], center[2], 1.0]),
+            velocity: mx!(VR[velocity[0], velocity[1], velocity[2], 0.0]),
+            radius: 1.5,
+            material: Material::diffuse(V3(1.0, 0.0, 0.0)),
+        };
+        let mut scene = Scene {
+            camera: Camera {
+                position: mx!(VR[0.0, 0.0, 0.0, 1.0]),
+                direction: mx!(VR[0.0, 0.0, -1.0, 0.0]),
+                near: 0.1,
+                far: 100.0,
+                shutter: 0.0,
+            },
+            primitives: vec![sphere([0.0, 0.0, -5.0], [0.0, 0.0, 0.0])],
+            lights: Vec::new(),
+            background: V3(0.0, 0.0, 0.2),
+            bvh: None,
+        };
+        let render = |scene: &Scene| -> Vec<V3<f32>> {
+            let mut rng = Rng::new(0);
+            (0..64).map(|i| scene.trace(i % 8, i / 8, 8, 8, &mut rng)).collect()
+        };
+        let image = render(&scene);
+        // with the shutter closed a moving sphere is where it starts
+        scene.primitives = vec![sphere([0.0, 0.0, -5.0], [3.0, 0.0, 0.0])];
+        assert!(render(&scene) == image);
+        // with the shutter open, it is blurred to the right, covering pixels the static sphere does not
+        scene.camera.shutter = 1.0;
+        let blurred = render(&scene);
+        assert!(blurred != image);
+        assert!(image[8 * 3 + 7] == scene.background && blurred[8 * 3 + 7] != scene.background);
+    }
+
+    #[test]
+    fn dielectric() {
+        let triangle = |x: f32, color: V3<f32>| Primitive::Triangle {
+            vertices: (
+                mx!(VR[0.0, -20.0, -5.0]),
+                mx!(VR[0.0, 20.0, -5.0]),
+                mx!(VR[x, 0.0, -5.0]),
+            ),
+            material: Material::diffuse(color),
+        };
+        let (red, green) = (V3(1.0, 0.0, 0.0), V3(0.0, 1.0, 0.0));
+        let mut scene = Scene {
+            camera: Camera {
+                position: mx!(VR[0.0, 0.0, 5.0, 1.0]),
+                direction: mx!(VR[0.0, 0.0, -1.0, 0.0]),
+                near: 0.1,
+                far: 100.0,
+                shutter: 0.0,
+            },
+            // a wall red on the left and green on the right
+            primitives: vec![triangle(-20.0, red), triangle(20.0, green)],
+            lights: Vec::new(),
+            background: V3(0.0, 0.0, 0.2),
+            bvh: None,
+        };
+        let ray = Ray::new(mx!(VR[0.3, 0.0, 5.0, 1.0]), mx!(VR[0.0, 0.0, -1.0, 0.0]));
+        let mut rng = Rng::new(5);
+        assert!(scene.radiance(&ray, &mut rng, 0).1 > 0.5);
+        // a glass sphere in front of the wall focuses rays behind it, so the right side ray lands on the left side
+        scene.primitives.push(Primitive::Sphere {
+            center: mx!(VR[0.0, 0.0, 0.0, 1.0]),
+            velocity: mx!(VR[0.0, 0.0, 0.0, 0.0]),
+            radius: 1.0,
+            material: Material::Dielectric { ior: 1.5 },
+        });
+        let colors = (0..64).map(|_| scene.radiance(&ray, &mut rng, 0)).collect::<Vec<_>>();
+        let refracted = colors.iter().filter(|color| color.0 > 0.5 && color.1 == 0.0).count();
+        // some rays reflect at the surfaces instead, towards the background
+        assert!(
+            refracted > 48 && colors.iter().all(|&color| color.1 == 0.0),
+            "refracted {refracted}"
+        );
+    }
+
+    #[test]
+    fn trace() {
+        let red = V3(1.0, 0.0, 0.0);
+        let mut scene = Scene {
+            camera: Camera {
+                position: mx!(VR[0.0, 0.0, 0.0, 1.0]),
+                direction: mx!(VR[0.0, 0.0, -1.0, 0.0]),
+                near: 0.1,
+                far: 100.0,
+                shutter: 0.0,
+            },
+            primitives: vec![Primitive::Sphere {
+                center: mx!(VR[0.0, 0.0, -5.0, 1.0]),
+                velocity: mx!(VR[0.0, 0.0, 0.0, 0.0]),
+                radius: 1.5,
+                material: Material::diffuse(red),
+            }],
+            lights: Vec::new(),
+            background: V3(0.0, 0.0, 0.2),
+            bvh: None,
+        };
+        let mut rng = Rng::new(0);
+        let mut render =
+            |scene: &Scene| -> Vec<V3<f32>> { (0..16).map(|i| scene.trace(i % 4, i / 4, 4, 4, &mut rng)).collect() };
+        let image = render(&scene);
+        // the sphere covers the 4 center pixels
+        for i in [5, 6, 9, 10] {
+            assert!(image[i].0 > 0.5 && image[i].1 == 0.0, "pixel {i} missed the sphere");
+        }
+        for i in [0, 3, 12, 15] {
+            assert_eq!(image[i], scene.background);
+        }
+        // a larger triangle behind the sphere fills the corners, the sphere is still in front
+        scene.primitives.push(Primitive::Triangle {
+            vertices: (
+                mx!(VR[-20.0, -20.0, -10.0]),
+                mx!(VR[20.0, -20.0, -10.0]),
+                mx!(VR[0.0, 20.0, -10.0]),
+            ),
+            material: Material::diffuse(V3(0.0, 1.0, 0.0)),
+        });
+        let image = render(&scene);
+        assert!(image[5].0 > 0.5 && image[13].1 > 0.9 && image[13].0 == 0.0);
+    }
+
+    #[test]
+    fn bvh() {
+        // a row of moving spheres over a floor, lit by a point light that casts their shadows on it
+        let sphere = |x: f32| Primitive::Sphere {
+            center: mx!(VR[x, 0.5, -5.0, 1.0]),
+            velocity: mx!(VR[0.0, 0.5, 0.0, 0.0]),
+            radius: 0.4,
+            material: Material::diffuse(V3(1.0, 0.5, 0.25)),
+        };
+        let floor = Primitive::Triangle {
+            vertices: (
+                mx!(VR[-50.0, 0.0, 50.0]),
+                mx!(VR[50.0, 0.0, 50.0]),
+                mx!(VR[0.0, 0.0, -50.0]),
+            ),
+            material: Material::diffuse(V3(1.0, 1.0, 1.0)),
+        };
+        let mut scene = Scene {
+            camera: Camera {
+                position: mx!(VR[0.0, 1.0, 0.0, 1.0]),
+                direction: mx!(VR[0.0, -0.2, -1.0, 0.0]),
+                near: 0.1,
+                far: 100.0,
+                shutter: 0.5,
+            },
+            primitives: (-5..=5).map(|x| sphere(x as f32)).chain([floor]).collect(),
+            lights: vec![Light::Point {
+                position: mx!(VR[0.0, 4.0, -4.0, 1.0]),
+                intensity: V3(10.0, 10.0, 10.0),
+            }],
+            background: V3(0.0, 0.0, 0.2),
+            bvh: None,
+        };
+        let expected = scene.render(16, 9, 2, &mut Rng::new(3));
+        for strategy in [BvhBuildStrategy::Median, BvhBuildStrategy::Sah] {
+            scene.build_bvh(strategy);
+            assert_eq!(scene.render(16, 9, 2, &mut Rng::new(3)), expected);
+        }
+    }
+
+    #[test]
+    fn render_rows() {
+        let scene = Scene {
+            camera: Camera {
+                position: mx!(VR[0.0, 0.0, 0.0, 1.0]),
+                direction: mx!(VR[0.0, 0.0, -1.0, 0.0]),
+                near: 0.1,
+                far: 100.0,
+                shutter: 0.0,
+            },
+            primitives: vec![Primitive::Sphere {
+                center: mx!(VR[0.0, 0.0, -5.0, 1.0]),
+                velocity: mx!(VR[0.0, 0.0, 0.0, 0.0]),
+                radius: 1.5,
+                material: Material::diffuse(V3(1.0, 0.0, 0.0)),
+            }],
+            lights: Vec::new(),
+            background: V3(0.0, 0.0, 0.2),
+            bvh: None,
+        };
+        let expected = scene.render(8, 7, 2, &mut Rng::new(5));
+        // uneven chunks of rows, the last one shorter
+        let mut rng = Rng::new(5);
+        let image = [0..3, 3..6, 6..7]
+            .into_iter()
+            .flat_map(|rows| scene.render_rows(8, 7, rows, 2, &mut rng))
+            .collect::<Vec<_>>();
+        assert_eq!(image, expected);
+    }
 }
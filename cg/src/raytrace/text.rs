@@ -0,0 +1,440 @@
+//! Text format of scenes, see `Scene::to_text`.
+#![allow(unused)]
+use crate::math::{mx, Mesh, Triangle, MX, V2, V3, VR};
+use crate::raytrace::bvh::BvhBuildStrategy;
+use crate::raytrace::material::{Addressing, Material, Texture};
+use crate::raytrace::scene::{Camera, Light, Primitive, Scene};
+
+/// Whitespace separated values of a line, read in order.
+struct Tokens<'a>(std::str::SplitWhitespace<'a>);
+
+impl<'a> Tokens<'a> {
+    fn word(&mut self) -> Option<&'a str> {
+        self.0.next()
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        self.word()?.parse().ok()
+    }
+
+    fn count(&mut self) -> Option<usize> {
+        self.word()?.parse().ok()
+    }
+
+    fn v3(&mut self) -> Option<V3<f32>> {
+        Some(V3(self.number()?, self.number()?, self.number()?))
+    }
+
+    fn point(&mut self) -> Option<VR<f32, 4>> {
+        let V3(x, y, z) = self.v3()?;
+        Some(mx!(VR[x, y, z, 1.0]))
+    }
+
+    fn vector(&mut self) -> Option<VR<f32, 4>> {
+        let V3(x, y, z) = self.v3()?;
+        Some(mx!(VR[x, y, z, 0.0]))
+    }
+
+    fn position(&mut self) -> Option<VR<f32, 3>> {
+        let V3(x, y, z) = self.v3()?;
+        Some(mx!(VR[x, y, z]))
+    }
+
+    fn texture(&mut self) -> Option<Texture> {
+        let (width, height) = (self.count()?, self.count()?);
+        let addressing = match self.word()? {
+            "wrap" => Addressing::Wrap,
+            "clamp" => Addressing::Clamp,
+            _ => return None,
+        };
+        let texels = (0..width.checked_mul(height)?)
+            .map(|_| self.v3())
+            .collect::<Option<Vec<_>>>()?;
+        (width > 0 && height > 0).then(|| Texture::new(width, height, texels).addressing(addressing))
+    }
+
+    /// Read the material at the end of the line.
+    fn material(&mut self) -> Option<Material> {
+        let material = match self.word()? {
+            "diffuse" => {
+                let color = self.v3()?;
+                let (mut texture, mut normal_map) = (None, None);
+                while let Some(word) = self.word() {
+                    match word {
+                        "texture" if texture.is_none() => texture = Some(self.texture()?),
+                        "normal_map" if normal_map.is_none() => normal_map = Some(self.texture()?),
+                        _ => return None,
+                    }
+                }
+                Material::Diffuse {
+                    color,
+                    texture,
+                    normal_map,
+                }
+            }
+            "dielectric" => Material::Dielectric { ior: self.number()? },
+            _ => return None,
+        };
+        self.word().is_none().then_some(material)
+    }
+
+    /// Return `value` if the line has no more values.
+    fn end<T>(&mut self, value: T) -> Option<T> {
+        self.word().is_none().then_some(value)
+    }
+}
+
+/// Values of a line, written separated by spaces.
+#[derive(Default)]
+struct Line(Vec<String>);
+
+impl Line {
+    fn word(mut self, word: impl ToString) -> Line {
+        self.0.push(word.to_string());
+        self
+    }
+
+    fn v3(self, v: V3<f32>) -> Line {
+        self.word(v.0).word(v.1).word(v.2)
+    }
+
+    /// Write the first 3 components of `v`, the `w` of points and vectors is implied by the keyword.
+    #[allow(clippy::identity_op)]
+    fn xyz<const N: usize>(self, v: &VR<f32, N>) -> Line
+    where
+        [(); 1 * N]:,
+    {
+        self.word(v[0]).word(v[1]).word(v[2])
+    }
+
+    fn texture(self, keyword: &str, texture: &Texture) -> Line {
+        let addressing = match texture.addressing {
+            Addressing::Wrap => "wrap",
+            Addressing::Clamp => "clamp",
+        };
+        let line = self
+            .word(keyword)
+            .word(texture.width)
+            .word(texture.height)
+            .word(addressing);
+        texture.texels.iter().fold(line, |line, &texel| line.v3(texel))
+    }
+
+    fn material(self, material: &Material) -> Line {
+        match material {
+            Material::Diffuse {
+                color,
+                texture,
+                normal_map,
+            } => {
+                let line = self.word("diffuse").v3(*color);
+                let line = match texture {
+                    Some(texture) => line.texture("texture", texture),
+                    None => line,
+                };
+                match normal_map {
+                    Some(normal_map) => line.texture("normal_map", normal_map),
+                    None => line,
+                }
+            }
+            Material::Dielectric { ior } => self.word("dielectric").word(ior),
+        }
+    }
+}
+
+impl Scene {
+    /// Return the scene as text, one line per camera, background, primitive, and light, each a keyword followed by
+    /// its values separated by spaces, points and vectors as their `x y z` components.
+    ///
+    /// ```text
+    /// camera <position> <direction> <near> <far> <shutter>
+    /// background <r g b>
+    /// sphere <center> <velocity> <radius> <material>
+    /// triangle <a> <b> <c> <material>
+    /// mesh <vertex count> <triangle count> <positions> <u v per vertex> <indices> <material>
+    /// point <position> <intensity>
+    /// quad <corner> <edge u> <edge v> <intensity>
+    /// directional <direction> <intensity>
+    /// spot <position> <direction> <cone angle> <intensity>
+    /// ```
+    ///
+    /// Materials are `dielectric <ior>` or `diffuse <r g b>`, optionally followed by `texture` and `normal_map`, each
+    /// `<width> <height> <wrap or clamp>` followed by the `r g b` texels. Numbers are written with the shortest
+    /// representation that parses back to the same value, so `from_text` restores the exact scene.
+    pub fn to_text(&self) -> String {
+        let camera = &self.camera;
+        let mut lines = vec![
+            Line::default()
+                .word("camera")
+                .xyz(&camera.position)
+                .xyz(&camera.direction)
+                .word(camera.near)
+                .word(camera.far)
+                .word(camera.shutter),
+            Line::default().word("background").v3(self.background),
+        ];
+        lines.extend(self.primitives.iter().map(|primitive| {
+            match primitive {
+                Primitive::Sphere {
+                    center,
+                    velocity,
+                    radius,
+                    material,
+                } => Line::default()
+                    .word("sphere")
+                    .xyz(center)
+                    .xyz(velocity)
+                    .word(radius)
+                    .material(material),
+                Primitive::Triangle { vertices, material } => Line::default()
+                    .word("triangle")
+                    .xyz(&vertices.0)
+                    .xyz(&vertices.1)
+                    .xyz(&vertices.2)
+                    .material(material),
+                Primitive::Mesh { mesh, material } => {
+                    let line = Line::default()
+                        .word("mesh")
+                        .word(mesh.positions.len())
+                        .word(mesh.indices.len());
+                    let line = mesh.positions.iter().fold(line, |line, position| line.xyz(position));
+                    let line = mesh.uvs.iter().fold(line, |line, uv| line.word(uv.0).word(uv.1));
+                    let line = mesh.indices.iter().flatten().fold(line, |line, index| line.word(index));
+                    line.material(material)
+                }
+            }
+        }));
+        lines.extend(self.lights.iter().map(|light| {
+            match light {
+                Light::Point { position, intensity } => Line::default().word("point").xyz(position).v3(*intensity),
+                Light::Quad {
+                    corner,
+                    edge_u,
+                    edge_v,
+                    intensity,
+                } => Line::default()
+                    .word("quad")
+                    .xyz(corner)
+                    .xyz(edge_u)
+                    .xyz(edge_v)
+                    .v3(*intensity),
+                Light::Directional { direction, intensity } => {
+                    Line::default().word("directional").xyz(direction).v3(*intensity)
+                }
+                Light::Spot {
+                    position,
+                    direction,
+                    cone_angle,
+                    intensity,
+                } => Line::default()
+                    .word("spot")
+                    .xyz(position)
+                    .xyz(direction)
+                    .word(cone_angle)
+                    .v3(*intensity),
+            }
+        }));
+        lines.iter().map(|line| line.0.join(" ") + "\n").collect()
+    }
+
+    /// Parse a scene created by `to_text`, returning `None` if it is malformed.
+    ///
+    /// Empty lines are ignored. The camera is required, the background is black if missing. The hierarchy of the
+    /// primitives is built with `BvhBuildStrategy::Sah`.
+    pub fn from_text(text: &str) -> Option<Scene> {
+        let mut camera = None;
+        let mut scene = Scene {
+            camera: Camera {
+                position: mx!(VR[0.0, 0.0, 0.0, 1.0]),
+                direction: mx!(VR[0.0, 0.0, -1.0, 0.0]),
+                near: 0.1,
+                far: 100.0,
+                shutter: 0.0,
+            },
+            primitives: Vec::new(),
+            lights: Vec::new(),
+            background: V3(0.0, 0.0, 0.0),
+            bvh: None,
+        };
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let mut tokens = Tokens(line.split_whitespace());
+            match tokens.word()? {
+                "camera" => {
+                    let value = Camera {
+                        position: tokens.point()?,
+                        direction: tokens.vector()?,
+                        near: tokens.number()?,
+                        far: tokens.number()?,
+                        shutter: tokens.number()?,
+                    };
+                    camera = Some(tokens.end(value)?);
+                }
+                "background" => {
+                    let background = tokens.v3()?;
+                    scene.background = tokens.end(background)?;
+                }
+                "sphere" => scene.primitives.push(Primitive::Sphere {
+                    center: tokens.point()?,
+                    velocity: tokens.vector()?,
+                    radius: tokens.number()?,
+                    material: tokens.material()?,
+                }),
+                "triangle" => scene.primitives.push(Primitive::Triangle {
+                    vertices: (tokens.position()?, tokens.position()?, tokens.position()?),
+                    material: tokens.material()?,
+                }),
+                "mesh" => {
+                    let (vertices, triangles) = (tokens.count()?, tokens.count()?);
+                    let positions = (0..vertices).map(|_| tokens.position()).collect::<Option<Vec<_>>>()?;
+                    let uvs = (0..vertices)
+                        .map(|_| Some(V2(tokens.number()?, tokens.number()?)))
+                        .collect::<Option<Vec<_>>>()?;
+                    let mut index = || tokens.word()?.parse::<u32>().ok().filter(|&i| (i as usize) < vertices);
+                    let indices = (0..triangles)
+                        .map(|_| Some([index()?, index()?, index()?]))
+                        .collect::<Option<Vec<_>>>()?;
+                    let mesh = Mesh {
+                        positions,
+                        uvs,
+                        indices,
+                    };
+                    let material = tokens.material()?;
+                    scene.primitives.push(Primitive::Mesh { mesh, material });
+                }
+                keyword => {
+                    let light = match keyword {
+                        "point" => Light::Point {
+                            position: tokens.point()?,
+                            intensity: tokens.v3()?,
+                        },
+                        "quad" => Light::Quad {
+                            corner: tokens.point()?,
+                            edge_u: tokens.vector()?,
+                            edge_v: tokens.vector()?,
+                            intensity: tokens.v3()?,
+                        },
+                        "directional" => Light::Directional {
+                            direction: tokens.vector()?,
+                            intensity: tokens.v3()?,
+                        },
+                        "spot" => Light::Spot {
+                            position: tokens.point()?,
+                            direction: tokens.vector()?,
+                            cone_angle: tokens.number()?,
+                            intensity: tokens.v3()?,
+                        },
+                        _ => return None,
+                    };
+                    scene.lights.push(tokens.end(light)?);
+                }
+            }
+        }
+        scene.camera = camera?;
+        scene.build_bvh(BvhBuildStrategy::Sah);
+        Some(scene)
+    }
+}
+
+mod test {
+    use crate::math::sampling::Rng;
+    use crate::math::{mx, Mesh, MX, V2, V3, VR};
+    use crate::raytrace::material::{Addressing, Material, Texture};
+    use crate::raytrace::scene::{Camera, Light, Primitive, Scene};
+
+    /// Scene with every kind of primitive, material, and light.
+    fn scene() -> Scene {
+        let checker = Texture::new(2, 1, vec![V3(1.0, 0.0, 0.0), V3(0.0, 0.25, 1.0)]).addressing(Addressing::Clamp);
+        let mesh = Mesh {
+            positions: vec![
+                mx!(VR[-1.0, 0.0, -1.0]),
+                mx!(VR[1.0, 0.0, -1.0]),
+                mx!(VR[1.0, 0.0, -3.0]),
+                mx!(VR[-1.0, 0.0, -3.0]),
+            ],
+            uvs: vec![V2(0.0, 0.0), V2(1.0, 0.0), V2(1.0, 1.0), V2(0.0, 1.0)],
+            indices: vec![[0, 1, 2], [0, 2, 3]],
+        };
+        Scene {
+            camera: Camera {
+                position: mx!(VR[0.0, 1.0, 3.0, 1.0]),
+                direction: mx!(VR[0.0, -0.3, -1.0, 0.0]),
+                near: 0.1,
+                far: 100.0,
+                shutter: 0.5,
+            },
+            primitives: vec![
+                Primitive::Sphere {
+                    center: mx!(VR[0.3, 0.5, -1.5, 1.0]),
+                    velocity: mx!(VR[0.1, 0.0, 0.0, 0.0]),
+                    radius: 0.5,
+                    material: Material::Dielectric { ior: 1.5 },
+                },
+                Primitive::Triangle {
+                    vertices: (
+                        mx!(VR[-1.0, 0.0, -4.0]),
+                        mx!(VR[1.0, 0.0, -4.0]),
+                        mx!(VR[0.0, 1.7, -4.0]),
+                    ),
+                    material: Material::diffuse(V3(0.2, 0.7, 1.0 / 3.0)),
+                },
+                Primitive::Mesh {
+                    mesh,
+                    material: Material::Diffuse {
+                        color: V3(1.0, 1.0, 1.0),
+                        texture: Some(checker),
+                        normal_map: Some(Texture::new(1, 1, vec![V3(0.5, 0.5, 1.0)])),
+                    },
+                },
+            ],
+            lights: vec![
+                Light::Point {
+                    position: mx!(VR[0.0, 3.0, 0.0, 1.0]),
+                    intensity: V3(4.0, 4.0, 4.0),
+                },
+                Light::Quad {
+                    corner: mx!(VR[-0.5, 3.0, -2.5, 1.0]),
+                    edge_u: mx!(VR[1.0, 0.0, 0.0, 0.0]),
+                    edge_v: mx!(VR[0.0, 0.0, 1.0, 0.0]),
+                    intensity: V3(1.0, 0.9, 0.8),
+                },
+                Light::Directional {
+                    direction: mx!(VR[0.2, -1.0, -0.4, 0.0]),
+                    intensity: V3(0.3, 0.3, 0.3),
+                },
+                Light::Spot {
+                    position: mx!(VR[1.0, 2.0, 0.0, 1.0]),
+                    direction: mx!(VR[-0.5, -1.0, -1.0, 0.0]),
+                    cone_angle: 0.4,
+                    intensity: V3(2.0, 2.0, 2.0),
+                },
+            ],
+            background: V3(0.05, 0.1, 0.2),
+            bvh: None,
+        }
+    }
+
+    #[test]
+    fn text() {
+        let scene = scene();
+        let text = scene.to_text();
+        let parsed = Scene::from_text(&text).unwrap();
+        assert_eq!(parsed.to_text(), text);
+        let mut rng = (Rng::new(5), Rng::new(5));
+        assert!(scene.render(16, 12, 2, &mut rng.0) == parsed.render(16, 12, 2, &mut rng.1));
+        let crafted = "camera 0 0 0 0 0 -1 0.1 100 0\n\nsphere 0 0 -3 0 0 0 1 diffuse 1 0 0\npoint 0 2 0 1 1 1\n";
+        let crafted = Scene::from_text(crafted).unwrap();
+        assert!(crafted.primitives.len() == 1 && crafted.lights.len() == 1 && crafted.background == V3(0.0, 0.0, 0.0));
+        for text in [
+            "",
+            "background 0 0 0",
+            "camera 0 0 0 0 0 -1 0.1 100",
+            "camera 0 0 0 0 0 -1 0.1 100 0 1",
+            "camera 0 0 0 0 0 -1 0.1 100 0\nsphere 0 0 -3 0 0 0 1 metal",
+            "camera 0 0 0 0 0 -1 0.1 100 0\nsphere 0 0 -3 0 0 0 1 diffuse 1 0 0 texture 1 1 wrap 1 1",
+            "camera 0 0 0 0 0 -1 0.1 100 0\nmesh 1 1 0 0 0 0 0 0 0 1 dielectric 1.5",
+            "camera 0 0 0 0 0 -1 0.1 100 0\ncube 0 0 0",
+        ] {
+            assert!(Scene::from_text(text).is_none(), "{text}");
+        }
+    }
+}
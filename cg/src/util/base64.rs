@@ -0,0 +1,65 @@
+//! URL safe base64 encoding (`-` and `_` instead of `+` and `/`) without padding.
+#![allow(unused)]
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub fn encode(data: &[u8]) -> String {
+    let mut res = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &byte)| acc | (byte as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            res.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    res
+}
+
+/// Decode `text`, returning `None` if it contains characters outside the alphabet or has an invalid length.
+pub fn decode(text: &str) -> Option<Vec<u8>> {
+    let mut res = Vec::with_capacity(text.len() / 4 * 3 + 2);
+    for chunk in text.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut bits = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            bits |= (ALPHABET.iter().position(|a| a == c)? as u32) << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            res.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(res)
+}
+
+mod test {
+    use crate::util::base64::{decode, encode};
+
+    #[test]
+    fn encoding() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg"),
+            ("fo", "Zm8"),
+            ("foo", "Zm9v"),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (data, text) in cases {
+            assert_eq!(encode(data.as_bytes()), text);
+            assert_eq!(decode(text).unwrap(), data.as_bytes());
+        }
+        let data = (0..=255).collect::<Vec<u8>>();
+        assert_eq!(decode(&encode(&data)).unwrap(), data);
+        assert_eq!(encode(&[0xfb, 0xff]), "-_8");
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(decode("Zm9vY"), None);
+        assert_eq!(decode("Zm+v"), None);
+        assert_eq!(decode("Zm9v="), None);
+    }
+}
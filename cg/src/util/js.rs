@@ -1,11 +1,12 @@
 #![allow(unused)]
 use js_sys::{
     Array, ArrayBuffer, BigInt64Array, BigUint64Array, Float32Array, Float64Array, Int16Array, Int32Array, Int8Array,
-    SharedArrayBuffer, Uint16Array, Uint32Array, Uint8Array, Uint8ClampedArray, WebAssembly,
+    Promise, SharedArrayBuffer, Uint16Array, Uint32Array, Uint8Array, Uint8ClampedArray, WebAssembly,
 };
 use std::iter::Iterator;
 use std::mem::size_of;
 use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
 
 /// Javascript interoperability utilities.
 
@@ -73,6 +74,51 @@ macro_rules! js {
 
 pub(crate) use js;
 
+/// Return `object[key]`, propagating errors thrown by `Reflect`.
+pub fn try_get(object: &JsValue, key: &JsValue) -> Result<JsValue, JsValue> {
+    js_sys::Reflect::get(object, key)
+}
+
+/// Set `object[key] = value` and return `value`, propagating errors thrown by `Reflect`.
+///
+/// Setting a property that is not writable (e.g. in frozen objects) is also an error.
+pub fn try_set(object: &JsValue, key: &JsValue, value: &JsValue) -> Result<JsValue, JsValue> {
+    match js_sys::Reflect::set(object, key, value)? {
+        true => Ok(value.clone()),
+        false => Err(JsValue::from(format!("property {key:?} is not writable"))),
+    }
+}
+
+/// The `js_try!` macro is the checked counterpart of `js!` property access, errors are propagated with `?`.
+/// The enclosing function must return a `Result` whose error is convertible from [`wasm_bindgen::JsValue`].
+///
+/// Accessing properties:
+/// - getter: `let v = js_try!(object["a"])`
+/// - getter and cast: `let v = js_try!(object["a"] as js_sys::Array)`, failed casts are errors
+/// - setter: `js_try!(object["a"] = v)`
+macro_rules! js_try {
+    // object getter and cast
+    ($object:ident[$key:expr] as $type:ty) => {
+        crate::util::js::try_get(&$object, &wasm_bindgen::JsValue::from($key))?.dyn_into::<$type>()?
+    };
+
+    // object setter
+    ($object:ident[$key:expr] = $value:expr) => {
+        crate::util::js::try_set(
+            &$object,
+            &wasm_bindgen::JsValue::from($key),
+            &wasm_bindgen::JsValue::from(&$value),
+        )?
+    };
+
+    // object getter
+    ($object:ident[$key:expr]) => {
+        crate::util::js::try_get(&$object, &wasm_bindgen::JsValue::from($key))?
+    };
+}
+
+pub(crate) use js_try;
+
 /// The `js_fn!` macro provides utilities for creating [`wasm_bindgen::closure::Closure`] and [`js_sys::Function`].
 /// Function parameters and return types must all be convertible to [`wasm_bindgen::JsValue`].
 /// Other constraints in [`wasm_bindgen::closure::Closure`] must also be followed.
@@ -88,6 +134,34 @@ macro_rules! js_fn {
 
 pub(crate) use js_fn;
 
+// promises
+
+/// Await `promise`, returning the resolved value or the rejection reason.
+pub async fn await_value(promise: Promise) -> Result<JsValue, JsValue> {
+    JsFuture::from(promise).await
+}
+
+/// Await `promise` and cast the resolved value to `T`, `null` and `undefined` values are errors.
+///
+/// The cast is unchecked, `T` must match the resolved value type.
+pub async fn await_cast<T: JsCast>(promise: Promise) -> Result<T, JsValue> {
+    let value = await_value(promise).await?;
+    if value.is_null() || value.is_undefined() {
+        return Err(JsValue::from("promise resolved to null or undefined"));
+    }
+    Ok(value.unchecked_into::<T>())
+}
+
+/// Return a promise resolved after `millis` milliseconds, awaiting it lets the browser handle other events meanwhile.
+pub fn timeout(millis: i32) -> Promise {
+    Promise::new(&mut |resolve, _| {
+        let window = web_sys::window().unwrap();
+        window
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis)
+            .unwrap();
+    })
+}
+
 // memory buffers
 
 /// Return the WebAssembly memory as an `ArrayBuffer`.
@@ -0,0 +1,113 @@
+//! Byte oriented LZ77 compression, repeated sequences are replaced by references to their previous occurrence.
+//!
+//! The data is a sequence of tokens, a control byte below `0x80` is followed by `control + 1` literal bytes, other
+//! control bytes copy `(control & 0x7f) + MIN_MATCH` bytes from the little endian `u16` distance back that follows.
+#![allow(unused)]
+use std::collections::HashMap;
+
+/// Shortest repeated sequence referenced, shorter ones take more bytes than their literals.
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 0x7f + MIN_MATCH;
+const MAX_LITERALS: usize = 0x80;
+const MAX_DISTANCE: usize = u16::MAX as usize;
+
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(data.len() / 2);
+    // latest position of each sequence of MIN_MATCH bytes, only that occurrence is considered
+    let mut latest = HashMap::<&[u8], usize>::new();
+    let mut literals = 0..0;
+    let flush = |res: &mut Vec<u8>, literals: std::ops::Range<usize>| {
+        for chunk in data[literals].chunks(MAX_LITERALS) {
+            res.push((chunk.len() - 1) as u8);
+            res.extend_from_slice(chunk);
+        }
+    };
+    let mut i = 0;
+    while i < data.len() {
+        let key = &data[i..(i + MIN_MATCH).min(data.len())];
+        let previous = latest
+            .insert(key, i)
+            .filter(|&p| key.len() == MIN_MATCH && i - p <= MAX_DISTANCE);
+        let length = previous.map_or(0, |p| {
+            (0..MAX_MATCH.min(data.len() - i))
+                .take_while(|&j| data[p + j] == data[i + j])
+                .count()
+        });
+        if length < MIN_MATCH {
+            literals.end = i + 1;
+            i += 1;
+            continue;
+        }
+        flush(&mut res, literals);
+        res.push(0x80 | (length - MIN_MATCH) as u8);
+        res.extend_from_slice(&((i - previous.unwrap()) as u16).to_le_bytes());
+        for j in i + 1..i + length {
+            if j + MIN_MATCH <= data.len() {
+                latest.insert(&data[j..j + MIN_MATCH], j);
+            }
+        }
+        i += length;
+        literals = i..i;
+    }
+    flush(&mut res, literals);
+    res
+}
+
+/// Decompress `data` created by `compress`, returning `None` if it is truncated or references data before the start.
+pub fn decompress(data: &[u8]) -> Option<Vec<u8>> {
+    let mut res = Vec::with_capacity(data.len() * 2);
+    let mut i = 0;
+    while i < data.len() {
+        let control = data[i] as usize;
+        if control < 0x80 {
+            res.extend_from_slice(data.get(i + 1..i + 2 + control)?);
+            i += 2 + control;
+            continue;
+        }
+        let distance = u16::from_le_bytes([*data.get(i + 1)?, *data.get(i + 2)?]) as usize;
+        let start = res.len().checked_sub(distance).filter(|_| distance > 0)?;
+        // the copy may overlap the bytes it writes, repeating the sequence
+        for j in 0..(control & 0x7f) + MIN_MATCH {
+            res.push(res[start + j]);
+        }
+        i += 3;
+    }
+    Some(res)
+}
+
+mod test {
+    use crate::util::lz77::{compress, decompress};
+
+    #[test]
+    fn round_trip() {
+        let repetitive = "sphere 0 1 0 0 0 0 0.5 diffuse 1 1 1\n".repeat(40);
+        let varied = (0..2000).map(|i| (i * 7919 % 251) as u8).collect::<Vec<_>>();
+        let long_literals = (0..=255).collect::<Vec<u8>>();
+        for data in [
+            b"".as_slice(),
+            b"a",
+            b"aaaaaaaa",
+            repetitive.as_bytes(),
+            &varied[..],
+            &long_literals[..],
+        ] {
+            assert_eq!(decompress(&compress(data)).unwrap(), data);
+        }
+        assert!(compress(repetitive.as_bytes()).len() < repetitive.len() / 10);
+        // overlapping copies repeat the last bytes
+        assert_eq!(compress(b"aaaaaaaa"), [0, b'a', 0x80 + 4, 1, 0]);
+    }
+
+    #[test]
+    fn malformed() {
+        // truncated literals and distances, and distances before the start or zero
+        for data in [
+            &[3, b'a'][..],
+            &[0, b'a', 0x80],
+            &[0, b'a', 0x80, 2, 0],
+            &[0, b'a', 0x80, 0, 0],
+        ] {
+            assert!(decompress(data).is_none());
+        }
+    }
+}
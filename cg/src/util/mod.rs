@@ -1,2 +1,4 @@
+pub mod base64;
 pub mod js;
+pub mod lz77;
 pub mod types;
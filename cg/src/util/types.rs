@@ -16,6 +16,11 @@ pub mod monaco_editor {
     use js_sys::{Function, JsString, Object};
     use wasm_bindgen::prelude::*;
 
+    /// `monaco.KeyMod.CtrlCmd`, Ctrl on Windows and Linux and Cmd on macOS.
+    pub const KEY_MOD_CTRL_CMD: u32 = 1 << 11;
+    /// `monaco.KeyCode.Enter`.
+    pub const KEY_CODE_ENTER: u32 = 3;
+
     #[wasm_bindgen(raw_module = "monaco-editor")]
     extern "C" {
         pub fn editor() -> Editor;
@@ -25,18 +30,38 @@ pub mod monaco_editor {
         pub type Editor;
         #[wasm_bindgen(method)]
         pub fn create(this: &Editor, element: &web_sys::Element, options: &Object) -> StandaloneCodeEditor;
+        #[wasm_bindgen(method, js_name = "createDiffEditor")]
+        pub fn create_diff_editor(this: &Editor, element: &web_sys::Element, options: &Object) -> StandaloneDiffEditor;
+        #[wasm_bindgen(method, js_name = "setTheme")]
+        pub fn set_theme(this: &Editor, theme: &str);
+        #[wasm_bindgen(method, js_name = "createModel")]
+        pub fn create_model(this: &Editor, value: &str, language: &str) -> TextModel;
 
         #[wasm_bindgen(extends=Object)]
         #[derive(Debug, Clone)]
         pub type StandaloneCodeEditor;
         #[wasm_bindgen(method, js_name = "getModel")]
         pub fn get_model(this: &StandaloneCodeEditor) -> TextModel;
+        #[wasm_bindgen(method, js_name = "addCommand")]
+        pub fn add_command(this: &StandaloneCodeEditor, keybinding: u32, handler: &Function) -> JsValue;
+
+        #[wasm_bindgen(extends=Object)]
+        #[derive(Debug, Clone)]
+        pub type StandaloneDiffEditor;
+        #[wasm_bindgen(method, js_name = "setModel")]
+        pub fn set_model(this: &StandaloneDiffEditor, model: &Object);
+        #[wasm_bindgen(method, js_name = "getOriginalEditor")]
+        pub fn get_original_editor(this: &StandaloneDiffEditor) -> StandaloneCodeEditor;
+        #[wasm_bindgen(method, js_name = "getModifiedEditor")]
+        pub fn get_modified_editor(this: &StandaloneDiffEditor) -> StandaloneCodeEditor;
 
         #[wasm_bindgen(extends=Object)]
         #[derive(Debug, Clone)]
         pub type TextModel;
         #[wasm_bindgen(method, js_name = "getValue")]
         pub fn get_value(this: &TextModel) -> JsString;
+        #[wasm_bindgen(method, js_name = "setValue")]
+        pub fn set_value(this: &TextModel, value: &str);
         #[wasm_bindgen(method, js_name = "onDidChangeContent")]
         pub fn on_did_change_content(this: &TextModel, listener: &Function) -> TextModel;
     }
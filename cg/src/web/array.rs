@@ -24,6 +24,16 @@ pub fn memory_buffer_shared() -> SharedArrayBuffer {
 
 // typed arrays
 
+/// Return the `[begin, end)` range of `data` in elements of `E` from the start of the WebAssembly memory.
+///
+/// `data` must be aligned to `E`, otherwise the range would start before it.
+fn element_range<T: Sized, E>(data: &T) -> (u32, u32) {
+    let address = data as *const T as usize;
+    debug_assert!(address.is_multiple_of(size_of::<E>()), "unaligned typed array data");
+    let begin = (address / size_of::<E>()) as u32;
+    (begin, begin + (size_of::<T>() / size_of::<E>()) as u32)
+}
+
 macro_rules! array_buffer {
     ($name:ident $name_copy:ident $arr:ident::$t:ty) => {
         /// Return a typed array that contains the `data` memory region.
@@ -31,9 +41,9 @@ macro_rules! array_buffer {
         /// The underlying typed array buffer is a subarray of the WebAssembly memory and is not copied.
         /// The buffer type will depend on the WebAssembly memory type.
         /// This might be unsafe, as the memory region might be altered from javascript operations.
+        /// The array is only valid while `data` is borrowed, and until the WebAssembly memory grows.
         pub fn $name<T: Sized>(data: &T) -> $arr {
-            let begin = data as *const T as u32 / size_of::<$t>() as u32;
-            let end = begin + (size_of::<T>() / size_of::<$t>()) as u32;
+            let (begin, end) = element_range::<T, $t>(data);
             $arr::new(&memory_buffer()).subarray(begin, end)
         }
 
@@ -41,8 +51,7 @@ macro_rules! array_buffer {
         ///
         /// The underlying typed array buffer is copied from the WebAssembly memory.
         pub fn $name_copy<T: Sized>(data: &T) -> $arr {
-            let begin = data as *const T as u32 / size_of::<$t>() as u32;
-            let end = begin + (size_of::<T>() / size_of::<$t>()) as u32;
+            let (begin, end) = element_range::<T, $t>(data);
             $arr::new(&memory_buffer()).slice(begin, end)
         }
     };
@@ -60,6 +69,21 @@ array_buffer!(typed_i64 typed_i64_copy BigInt64Array::i64);
 array_buffer!(typed_f32 typed_f32_copy Float32Array::f32);
 array_buffer!(typed_f64 typed_f64_copy Float64Array::f64);
 
+/// Return a new typed array with the contents of `parts` in order.
+///
+/// Packing several buffers into a single array allows uploading them with a single `write_buffer` call.
+pub fn concat_f32(parts: &[&[f32]]) -> Float32Array {
+    let len = parts.iter().map(|part| part.len() as u32).sum();
+    let array = Float32Array::new_with_length(len);
+    let mut offset = 0;
+    for part in parts {
+        let end = offset + part.len() as u32;
+        array.subarray(offset, end).copy_from(part);
+        offset = end;
+    }
+    array
+}
+
 // arrays
 
 /// Wrap a single `&JsValue` into a js `Array`.
@@ -78,3 +102,20 @@ where
     array.push(&JsValue::from(value));
     array
 }
+
+mod test {
+    use crate::math::mx;
+    use crate::web::array::element_range;
+
+    #[test]
+    fn range() {
+        let data = mx!(VR[1.0f32, 2.0, 3.0, 4.0]);
+        let (begin, end) = element_range::<_, f32>(&data);
+        assert_eq!(end - begin, 4);
+        assert_eq!(begin, (&data as *const _ as usize / 4) as u32);
+        let (begin, end) = element_range::<_, u8>(&data);
+        assert_eq!(end - begin, 16);
+        let (begin, end) = element_range::<_, f64>(&[0.0f64; 3]);
+        assert_eq!(end - begin, 3);
+    }
+}
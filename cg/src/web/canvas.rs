@@ -0,0 +1,29 @@
+//! Canvas utilities.
+use crate::math::{mx, VR};
+use crate::util::js;
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Read the color of the pixel at `x`, `y` in canvas pixel coordinates, components are in `[0, 1]`.
+///
+/// The pixel is copied from the displayed canvas content, so it works for `webgpu` and `2d` contexts alike. The copy
+/// is asynchronous and does not stall rendering. Colors are returned as written to the canvas, which are linear
+/// colors for shaders in this crate.
+pub async fn read_pixel(canvas: &web_sys::HtmlCanvasElement, x: i32, y: i32) -> Result<VR<f32, 4>, JsValue> {
+    let window = leptos::window();
+    let bitmap =
+        window.create_image_bitmap_with_html_canvas_element_and_a_sx_and_a_sy_and_a_sw_and_a_sh(canvas, x, y, 1, 1)?;
+    let bitmap = js::await_cast::<web_sys::ImageBitmap>(bitmap).await?;
+    let target = leptos::document()
+        .create_element("canvas")?
+        .unchecked_into::<web_sys::HtmlCanvasElement>();
+    target.set_width(1);
+    target.set_height(1);
+    let context = target
+        .get_context("2d")?
+        .ok_or("2d context not available")?
+        .unchecked_into::<web_sys::CanvasRenderingContext2d>();
+    context.draw_image_with_image_bitmap(&bitmap, 0.0, 0.0)?;
+    let data = context.get_image_data(0.0, 0.0, 1.0, 1.0)?.data();
+    let component = |i: usize| data[i] as f32 / 255.0;
+    Ok(mx!(VR[component(0), component(1), component(2), component(3)]))
+}
@@ -1,2 +1,4 @@
 pub mod array;
+pub mod canvas;
 pub mod webgpu;
+pub mod wgsl;
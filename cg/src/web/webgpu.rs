@@ -1,14 +1,13 @@
-use std::{cell, mem::size_of};
+use std::{any::Any, cell, collections::HashMap, fmt, future::Future, mem::size_of, rc::Rc};
 
 use crate::mx;
-use js_sys::{Array, JsString, Object, Reflect};
-use leptos::html::tr;
-use wasm_bindgen::{JsCast, JsValue};
-use wasm_bindgen_futures::JsFuture;
+use js_sys::{Array, Object};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
 
 use web_sys::*;
 
-use crate::math::Triangle;
+use crate::math::{Triangle, MX, VR};
+use crate::util::js::{self, js};
 
 use super::array;
 
@@ -18,43 +17,444 @@ pub struct WebGpu {
     pub adapter: web_sys::GpuAdapter,
     pub device: web_sys::GpuDevice,
     pub format: web_sys::GpuTextureFormat,
+    pub alpha_mode: web_sys::GpuCanvasAlphaMode,
+    bind_group_layouts: cell::RefCell<HashMap<String, web_sys::GpuBindGroupLayout>>,
+    pipeline_layouts: cell::RefCell<HashMap<String, web_sys::GpuPipelineLayout>>,
+    bundles: cell::RefCell<HashMap<String, web_sys::GpuRenderBundle>>,
+    constants: cell::RefCell<HashMap<String, f64>>,
+    resources: cell::RefCell<HashMap<String, Rc<dyn Any>>>,
+    stats: cell::Cell<FrameStats>,
+    srgb: cell::Cell<bool>,
+    mipmaps: cell::OnceCell<(GpuRenderPipeline, GpuSampler)>,
+}
+
+/// WebGPU setup and pipeline creation errors, variants with a `JsValue` carry the rejection from the browser.
+#[derive(Debug, Clone)]
+pub enum WebGpuError {
+    /// No canvas element to render to.
+    NoCanvas,
+    /// The canvas does not provide a `webgpu` context, WebGPU is not supported or the canvas uses another context.
+    NoWebGpuContext,
+    /// `requestAdapter` failed or no adapter is available.
+    NoAdapter(JsValue),
+    /// `requestDevice` failed.
+    NoDevice(JsValue),
+    /// `createRenderPipelineAsync` was rejected, usually by shader compilation or validation errors.
+    Pipeline(JsValue),
+}
+
+impl fmt::Display for WebGpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // error objects are described by their message, other values by their debug string
+        let cause = |value: &JsValue| match value.dyn_ref::<js_sys::Error>() {
+            Some(error) => String::from(error.message()),
+            None => value.as_string().unwrap_or_else(|| format!("{value:?}")),
+        };
+        match self {
+            WebGpuError::NoCanvas => write!(f, "no canvas to render to"),
+            WebGpuError::NoWebGpuContext => write!(f, "canvas has no webgpu context, WebGPU may not be supported"),
+            WebGpuError::NoAdapter(value) => write!(f, "no WebGPU adapter available: {}", cause(value)),
+            WebGpuError::NoDevice(value) => write!(f, "WebGPU device request failed: {}", cause(value)),
+            WebGpuError::Pipeline(value) => write!(f, "render pipeline creation failed: {}", cause(value)),
+        }
+    }
+}
+
+impl From<WebGpuError> for JsValue {
+    fn from(error: WebGpuError) -> JsValue {
+        js_sys::Error::new(&error.to_string()).into()
+    }
 }
 
 impl WebGpu {
+    /// Create the WebGPU context for `canvas`.
+    ///
+    /// `alpha_mode` controls how the canvas is composited with the page:
+    /// - `Opaque`: the alpha channel is ignored, the canvas always covers whatever is behind it.
+    /// - `Premultiplied`: colors are expected to be premultiplied by alpha, translucent pixels blend with the page.
+    ///   Shaders must output `rgb * a`, otherwise colors brighter than alpha are composited incorrectly.
     pub async fn new(
         canvas: Option<leptos::HtmlElement<leptos::html::Canvas>>,
-    ) -> Result<WebGpu, JsValue> {
-        let canvas = canvas.ok_or(Some("adsf"))?;
+        alpha_mode: web_sys::GpuCanvasAlphaMode,
+    ) -> Result<WebGpu, WebGpuError> {
+        let canvas = canvas.ok_or(WebGpuError::NoCanvas)?;
         let context = canvas
-            .get_context("webgpu")?
-            .ok_or(Some("asdf"))?
+            .get_context("webgpu")
+            .ok()
+            .flatten()
+            .ok_or(WebGpuError::NoWebGpuContext)?
             .unchecked_into::<web_sys::GpuCanvasContext>();
         let adapter = leptos::window() //
             .navigator()
             .gpu()
             .request_adapter();
-        let adapter = JsFuture::from(adapter)
-            .await?
-            .unchecked_into::<web_sys::GpuAdapter>();
-        let device = JsFuture::from(adapter.request_device())
-            .await?
-            .unchecked_into::<web_sys::GpuDevice>();
+        let adapter = js::await_cast::<web_sys::GpuAdapter>(adapter)
+            .await
+            .map_err(WebGpuError::NoAdapter)?;
+        let device = js::await_cast::<web_sys::GpuDevice>(adapter.request_device())
+            .await
+            .map_err(WebGpuError::NoDevice)?;
         let format = leptos::window()
             .navigator()
             .gpu()
             .get_preferred_canvas_format();
-        context.configure(&web_sys::GpuCanvasConfiguration::new(
-            &device,
-            format.clone(),
-        ));
 
-        Result::Ok(WebGpu {
+        let webgpu = WebGpu {
             canvas,
             context,
             adapter,
             device,
             format,
-        })
+            alpha_mode,
+            bind_group_layouts: Default::default(),
+            pipeline_layouts: Default::default(),
+            bundles: Default::default(),
+            constants: Default::default(),
+            resources: Default::default(),
+            stats: Default::default(),
+            srgb: Default::default(),
+            mipmaps: Default::default(),
+        };
+        webgpu.configure();
+        Result::Ok(webgpu)
+    }
+
+    /// Configure the canvas context with the current device, format, alpha mode, and sRGB view format.
+    pub fn configure(&self) {
+        let mut configuration = web_sys::GpuCanvasConfiguration::new(&self.device, self.format);
+        configuration.alpha_mode(self.alpha_mode);
+        if self.srgb.get() {
+            configuration.view_formats(&array::wrap(&JsValue::from(self.view_format())));
+        }
+        self.context.configure(&configuration);
+    }
+
+    /// Draw to the canvas through an sRGB view if `srgb` is set and the canvas format has an sRGB variant, returning
+    /// whether the sRGB view is used.
+    ///
+    /// With the sRGB view shaders output linear colors, which are encoded when written, and blending happens in linear
+    /// space. Without it outputs are stored as is and displayed as sRGB, so shaders must encode colors themselves, or
+    /// linear outputs look too dark. Clear colors follow the same rule. Pipelines target `view_format`, so pipelines
+    /// created before the change must be recreated, cached bundles and resources are invalidated.
+    pub fn set_srgb(&self, srgb: bool) -> bool {
+        let srgb = srgb && srgb_format(self.format).is_some();
+        if self.srgb.replace(srgb) != srgb {
+            self.bundles.borrow_mut().clear();
+            self.resources.borrow_mut().clear();
+            self.configure();
+        }
+        srgb
+    }
+
+    /// Format of the views returned by `current_view`, pipelines drawing to the canvas must target it.
+    pub fn view_format(&self) -> GpuTextureFormat {
+        match self.srgb.get() {
+            true => srgb_format(self.format).unwrap_or(self.format),
+            false => self.format,
+        }
+    }
+
+    /// Return a view of the current canvas texture in `view_format`.
+    pub fn current_view(&self) -> GpuTextureView {
+        let mut descriptor = GpuTextureViewDescriptor::new();
+        descriptor.format(self.view_format());
+        self.context
+            .get_current_texture()
+            .create_view_with_descriptor(&descriptor)
+    }
+
+    /// Resize the canvas backing texture to `width` x `height` pixels and reconfigure the context.
+    ///
+    /// Sizes are clamped to the device texture limit. Zero sizes, from hidden or collapsed canvases, are skipped and
+    /// keep the previous size, the context can not provide empty textures.
+    pub fn resize(&self, width: u32, height: u32) {
+        let max = self.device.limits().max_texture_dimension_2d();
+        let (width, height) = (width.min(max), height.min(max));
+        if width == 0 || height == 0 || (self.canvas.width(), self.canvas.height()) == (width, height) {
+            return;
+        }
+        self.canvas.set_width(width);
+        self.canvas.set_height(height);
+        self.configure();
+    }
+
+    /// Required alignment in bytes of dynamic uniform buffer offsets.
+    pub fn uniform_alignment(&self) -> u32 {
+        self.device.limits().min_uniform_buffer_offset_alignment()
+    }
+
+    /// Record `draws` into a render bundle targeting `view_format`, bundles replay their draws in any render pass
+    /// with `execute_bundles`, without encoding them again.
+    pub fn record_bundle(&self, draws: impl FnOnce(&GpuRenderBundleEncoder)) -> GpuRenderBundle {
+        let formats = array::wrap(&JsValue::from(self.view_format()));
+        let encoder = self
+            .device
+            .create_render_bundle_encoder(&GpuRenderBundleEncoderDescriptor::new(&formats));
+        draws(&encoder);
+        encoder.finish()
+    }
+
+    /// Return the cached bundle for `key`, recording it with `draws` if necessary.
+    ///
+    /// Bundles keep the pipelines and buffers they were recorded with, call `invalidate_bundle` when they change.
+    pub fn bundle(&self, key: &str, draws: impl FnOnce(&GpuRenderBundleEncoder)) -> GpuRenderBundle {
+        if let Some(bundle) = self.bundles.borrow().get(key) {
+            return bundle.clone();
+        }
+        let bundle = self.record_bundle(draws);
+        self.bundles.borrow_mut().insert(key.into(), bundle.clone());
+        bundle
+    }
+
+    /// Remove the cached bundle for `key`, the next `bundle` call records it again.
+    #[allow(dead_code)]
+    pub fn invalidate_bundle(&self, key: &str) {
+        self.bundles.borrow_mut().remove(key);
+    }
+
+    /// Set the value of the WGSL `override` constant `name` for pipelines created afterwards.
+    ///
+    /// Resources cached with `cached_with_constants` for `name` are created again with the new value.
+    pub fn set_constant(&self, name: &str, value: f64) {
+        self.constants.borrow_mut().insert(name.into(), value);
+    }
+
+    /// Return the resources cached for `key`, creating them with `create` if necessary.
+    ///
+    /// Resources are kept across frames, so draws only write their buffers and record commands. They are dropped
+    /// when `set_srgb` changes the view format, and created again on next use. Each `key` must always be used with the
+    /// same type, otherwise the resources are created again on every call.
+    pub fn cached<T: 'static>(&self, key: &str, create: impl FnOnce() -> T) -> Rc<T> {
+        let cached = self.resources.borrow().get(key).cloned();
+        if let Some(resources) = cached.and_then(|resources| resources.downcast::<T>().ok()) {
+            return resources;
+        }
+        // created without borrowing the cache, which create may use
+        let resources = Rc::new(create());
+        self.resources.borrow_mut().insert(key.into(), resources.clone());
+        resources
+    }
+
+    /// Return the resources cached for `key` like `cached`, also creating them again when the values of the `names`
+    /// override constants changed since they were created, see `constants`.
+    pub fn cached_with_constants<T: 'static>(&self, key: &str, names: &[&str], create: impl FnOnce() -> T) -> Rc<T> {
+        let values = {
+            let constants = self.constants.borrow();
+            names
+                .iter()
+                .map(|&name| constants.get(name).copied())
+                .collect::<Vec<_>>()
+        };
+        let cached = self.resources.borrow().get(key).cloned();
+        let cached = cached.and_then(|resources| resources.downcast::<(Vec<Option<f64>>, Rc<T>)>().ok());
+        if let Some((_, resources)) = cached.as_deref().filter(|(cached, _)| *cached == values) {
+            return resources.clone();
+        }
+        let resources = Rc::new(create());
+        let entry = Rc::new((values, resources.clone()));
+        self.resources.borrow_mut().insert(key.into(), entry);
+        resources
+    }
+
+    /// Return the `constants` record of a pipeline stage, with the values set for the `names` overrides.
+    ///
+    /// Pipeline creation fails if the record has constants not declared by the shader, so each stage lists the
+    /// overrides it declares. Overrides without a value keep the shader default.
+    pub fn constants(&self, names: &[&str]) -> Object {
+        let constants = self.constants.borrow();
+        let record = js!({});
+        for (name, value) in names.iter().filter_map(|&name| Some((name, *constants.get(name)?))) {
+            js!(record[name] = JsValue::from(value));
+        }
+        record
+    }
+
+    /// Summarize the optional features supported by the adapter and the limits of the device.
+    pub fn capabilities(&self) -> Capabilities {
+        let features = self.adapter.features();
+        let limits = self.device.limits();
+        Capabilities {
+            supports_timestamp_query: features.has("timestamp-query"),
+            supports_float32_filterable: features.has("float32-filterable"),
+            max_texture_dimension_2d: limits.max_texture_dimension_2d(),
+            max_buffer_size: limits.max_buffer_size(),
+        }
+    }
+
+    /// Create a render pipeline without blocking while the shaders are compiled.
+    ///
+    /// Falls back to `create_render_pipeline` where `createRenderPipelineAsync` is not available. The future does not
+    /// borrow `self`, it resolves to the pipeline or the rejected `GPUPipelineError`.
+    pub fn create_render_pipeline_async(
+        &self,
+        descriptor: &GpuRenderPipelineDescriptor,
+    ) -> impl Future<Output = Result<GpuRenderPipeline, WebGpuError>> {
+        let device = &self.device;
+        let pipeline = if js!(device["createRenderPipelineAsync"]).is_function() {
+            PendingPipeline::Compiling(device.create_render_pipeline_async(descriptor))
+        } else {
+            PendingPipeline::Ready(device.create_render_pipeline(descriptor))
+        };
+        async move {
+            match pipeline {
+                PendingPipeline::Ready(pipeline) => Ok(pipeline),
+                PendingPipeline::Compiling(promise) => js::await_cast::<GpuRenderPipeline>(promise)
+                    .await
+                    .map_err(WebGpuError::Pipeline),
+            }
+        }
+    }
+
+    /// Clear `texture` to `color` with an empty render pass, `texture` needs a renderable format and the
+    /// `RENDER_ATTACHMENT` usage.
+    pub fn clear_texture(&self, texture: &GpuTexture, color: VR<f32, 4>) {
+        let clear = js!([color[0], color[1], color[2], color[3]]);
+        let view = texture.create_view();
+        let attachment = js!({"view": view, "loadOp": "clear", "storeOp": "store", "clearValue": clear});
+        let encoder = self.device.create_command_encoder();
+        encoder
+            .begin_render_pass(&GpuRenderPassDescriptor::new(&array::wrap(&attachment)))
+            .end();
+        self.device.queue().submit(&array::wrap(&encoder.finish()));
+    }
+
+    /// Draw `texture` stretched over `viewport` of the current canvas texture, or the whole canvas if `None`.
+    ///
+    /// `viewport` is `[x, y, width, height]` in canvas pixels from the top-left corner and must be inside the canvas.
+    /// Texels are read with `textureLoad` and written in the canvas format, so storage formats the canvas does not
+    /// support, like `rgba8unorm` or `rgba32float`, can be displayed. `texture` needs the `TEXTURE_BINDING` usage and
+    /// a float format, integer and depth formats are not supported.
+    pub fn blit(&self, texture: &GpuTexture, viewport: Option<[f32; 4]>) {
+        let blit = self.cached("blit", || {
+            let module = self
+                .device
+                .create_shader_module(&GpuShaderModuleDescriptor::new(BLIT_SHADER));
+            // unfilterable-float accepts all float formats, including 32-bit ones
+            let texture_layout = js!({"sampleType": "unfilterable-float"});
+            let entry = js!({"binding": 0, "visibility": gpu_shader_stage::FRAGMENT, "texture": texture_layout});
+            let group = self
+                .device
+                .create_bind_group_layout(&GpuBindGroupLayoutDescriptor::new(&array::wrap(&entry)));
+            let layout = self
+                .device
+                .create_pipeline_layout(&GpuPipelineLayoutDescriptor::new(&array::wrap(&group)));
+            let target = js!({"format": JsValue::from(self.view_format())});
+            let fragment = GpuFragmentState::new("fragment_main", &module, &array::wrap(&target));
+            let vertex = GpuVertexState::new("vertex_main", &module);
+            let mut descriptor = GpuRenderPipelineDescriptor::new(&layout, &vertex);
+            descriptor.fragment(&fragment);
+            (self.device.create_render_pipeline(&descriptor), group)
+        });
+        let (pipeline, group) = &*blit;
+        // textures change between calls, only the bind group is created every time
+        let bind_group = self.device.create_bind_group(&GpuBindGroupDescriptor::new(
+            &array::wrap(&GpuBindGroupEntry::new(0, &texture.create_view())),
+            group,
+        ));
+
+        let view = self.current_view();
+        let attachment = js!({"view": view, "loadOp": "load", "storeOp": "store"});
+        let encoder = self.device.create_command_encoder();
+        let pass = encoder.begin_render_pass(&GpuRenderPassDescriptor::new(&array::wrap(&attachment)));
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group);
+        if let Some([x, y, width, height]) = viewport {
+            pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        }
+        pass.draw(3);
+        self.count_draw(GpuPrimitiveTopology::TriangleList, 3);
+        pass.end();
+        self.device.queue().submit(&array::wrap(&encoder.finish()));
+    }
+
+    /// Create an `rgba8unorm` texture of `width` x `height` texels with the `rgba` bytes, rows from top to bottom.
+    ///
+    /// With `mipmaps` the texture has all `mip_level_count` levels, generated with `generate_mipmaps`, minified
+    /// textures sampled with linear `min_filter` and `mipmap_filter` look smooth instead of noisy.
+    pub fn create_texture_from_rgba(&self, width: u32, height: u32, rgba: &[u8], mipmaps: bool) -> GpuTexture {
+        let size = js!([width, height]);
+        let (levels, usage) = match mipmaps {
+            true => (mip_level_count(width, height), gpu_texture_usage::RENDER_ATTACHMENT),
+            false => (1, 0),
+        };
+        let mut descriptor = GpuTextureDescriptor::new(
+            GpuTextureFormat::Rgba8unorm,
+            &size,
+            usage | gpu_texture_usage::TEXTURE_BINDING | gpu_texture_usage::COPY_DST,
+        );
+        descriptor.mip_level_count(levels);
+        let texture = self.device.create_texture(&descriptor);
+        let mut layout = GpuImageDataLayout::new();
+        layout.bytes_per_row(4 * width);
+        self.device.queue().write_texture_with_u8_array_and_u32_sequence(
+            &GpuImageCopyTexture::new(&texture),
+            rgba,
+            &layout,
+            &size,
+        );
+        if mipmaps {
+            self.generate_mipmaps(&texture);
+        }
+        texture
+    }
+
+    /// Fill the mip levels of the `rgba8unorm` `texture` after the first by downsampling the previous level.
+    ///
+    /// WebGPU does not generate mipmaps, each level is rendered with a linear sampler reading the previous one, which
+    /// averages 2x2 texels. Odd sizes round down, so the last row or column of odd sized levels is partially skipped.
+    /// `texture` needs the `TEXTURE_BINDING` and `RENDER_ATTACHMENT` usages.
+    pub fn generate_mipmaps(&self, texture: &GpuTexture) {
+        let (pipeline, sampler) = self.mipmaps.get_or_init(|| {
+            let module = self
+                .device
+                .create_shader_module(&GpuShaderModuleDescriptor::new(SAMPLE_SHADER));
+            let target = js!({"format": JsValue::from(GpuTextureFormat::Rgba8unorm)});
+            let fragment = GpuFragmentState::new("fragment_main", &module, &array::wrap(&target));
+            let mut descriptor =
+                GpuRenderPipelineDescriptor::new(&JsValue::from("auto"), &GpuVertexState::new("vertex_main", &module));
+            descriptor.fragment(&fragment);
+            let sampler = SamplerDesc::new().filter(GpuFilterMode::Linear).build(self);
+            (self.device.create_render_pipeline(&descriptor), sampler)
+        });
+        let level_view = |level: u32| {
+            let mut descriptor = GpuTextureViewDescriptor::new();
+            descriptor.base_mip_level(level).mip_level_count(1);
+            texture.create_view_with_descriptor(&descriptor)
+        };
+        let encoder = self.device.create_command_encoder();
+        for level in 1..texture.mip_level_count() {
+            let entries = Array::new();
+            entries.push(&GpuBindGroupEntry::new(0, sampler));
+            entries.push(&GpuBindGroupEntry::new(1, &level_view(level - 1)));
+            let bind_group = self.device.create_bind_group(&GpuBindGroupDescriptor::new(
+                &entries,
+                &pipeline.get_bind_group_layout(0),
+            ));
+            let attachment = js!({"view": level_view(level), "loadOp": "clear", "storeOp": "store"});
+            let pass = encoder.begin_render_pass(&GpuRenderPassDescriptor::new(&array::wrap(&attachment)));
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group);
+            pass.draw(3);
+            pass.end();
+        }
+        self.device.queue().submit(&array::wrap(&encoder.finish()));
+    }
+
+    /// Reset the frame statistics, call before recording the draws of a frame.
+    pub fn begin_frame(&self) {
+        self.stats.take();
+    }
+
+    /// Count a draw call of `vertices` as `topology` primitives in the frame statistics.
+    pub fn count_draw(&self, topology: GpuPrimitiveTopology, vertices: u32) {
+        let mut stats = self.stats.get();
+        stats.record(topology, vertices);
+        self.stats.set(stats);
+    }
+
+    /// Return the statistics of the draws counted since `begin_frame`.
+    pub fn frame_stats(&self) -> FrameStats {
+        self.stats.get()
     }
 
     pub fn print(&self) {
@@ -66,171 +466,1571 @@ impl WebGpu {
     }
 }
 
-pub fn draw(webgpu: &WebGpu, clear: crate::math::MX<f32, 1, 4>) {
+/// Render pipeline created synchronously or being compiled, see `WebGpu::create_render_pipeline_async`.
+enum PendingPipeline {
+    Ready(GpuRenderPipeline),
+    Compiling(js_sys::Promise),
+}
+
+/// Geometry submitted in a frame, see `WebGpu::count_draw`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    pub vertices: u32,
+    pub triangles: u32,
+    pub draw_calls: u32,
+}
+
+impl FrameStats {
+    /// Accumulate a draw call, lines and points are counted as vertices but not triangles.
+    pub fn record(&mut self, topology: GpuPrimitiveTopology, vertices: u32) {
+        self.vertices += vertices;
+        self.triangles += match topology {
+            GpuPrimitiveTopology::TriangleList => vertices / 3,
+            GpuPrimitiveTopology::TriangleStrip => vertices.saturating_sub(2),
+            _ => 0,
+        };
+        self.draw_calls += 1;
+    }
+}
+
+/// Animation frame callback, it schedules itself again after each frame until it is removed by `RenderLoop`.
+type FrameCallback = Rc<cell::RefCell<Option<Closure<dyn FnMut(f64)>>>>;
+
+/// Render loop started by `start_loop`, the loop is cancelled when the handle is dropped.
+pub struct RenderLoop {
+    id: Rc<cell::Cell<i32>>,
+    callback: FrameCallback,
+}
+
+impl Drop for RenderLoop {
+    fn drop(&mut self) {
+        _ = leptos::window().cancel_animation_frame(self.id.get());
+        // breaks the reference cycle of the callback, which is kept alive by itself
+        self.callback.borrow_mut().take();
+    }
+}
+
+/// Call `frame` on animation frames with the `requestAnimationFrame` timestamp in milliseconds.
+///
+/// `max_fps` caps the rate `frame` is called at, skipped animation frames still schedule the next one, see
+/// `FramePacer`. The loop does not borrow the `WebGpu`, `frame` reads it from wherever it is stored. Dropping the
+/// handle inside `frame` stops the loop after the current frame.
+pub fn start_loop(max_fps: Option<f64>, mut frame: impl FnMut(f64) + 'static) -> RenderLoop {
+    let id = Rc::new(cell::Cell::new(0));
+    let callback: FrameCallback = Default::default();
+    let (next_id, next_callback) = (id.clone(), callback.clone());
+    let mut pacer = FramePacer::new(max_fps);
+    *callback.borrow_mut() = Some(Closure::new(move |timestamp| {
+        if pacer.ready(timestamp) {
+            frame(timestamp);
+        }
+        if let Some(next) = next_callback.borrow().as_ref() {
+            next_id.set(request_frame(next));
+        }
+    }));
+    id.set(request_frame(callback.borrow().as_ref().unwrap()));
+    RenderLoop { id, callback }
+}
+
+fn request_frame(callback: &Closure<dyn FnMut(f64)>) -> i32 {
+    leptos::window()
+        .request_animation_frame(callback.as_ref().unchecked_ref())
+        .unwrap()
+}
+
+/// Frames up to `PACING_TOLERANCE` milliseconds early are rendered, animation frame timestamps jitter around the
+/// display refresh interval, which would skip every other frame when the cap matches the refresh rate.
+const PACING_TOLERANCE: f64 = 1.0;
+
+/// Frame rate limiter, selecting which animation frames are rendered to stay under a maximum rate.
+///
+/// The time a rendered frame is late is carried over to the next one, so the average rate matches the cap even when
+/// the cap is not a divisor of the display refresh rate. After long pauses, like a hidden tab, the carry is reset.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FramePacer {
+    interval: f64,
+    previous: Option<f64>,
+}
+
+impl FramePacer {
+    /// Create a pacer for `max_fps` frames per second, `None` renders every frame.
+    pub fn new(max_fps: Option<f64>) -> FramePacer {
+        let interval = max_fps.filter(|&fps| fps > 0.0).map_or(0.0, |fps| 1000.0 / fps);
+        FramePacer {
+            interval,
+            previous: None,
+        }
+    }
+
+    /// Return true if the frame at `time` in milliseconds should be rendered.
+    pub fn ready(&mut self, time: f64) -> bool {
+        let elapsed = self.previous.map_or(f64::INFINITY, |previous| time - previous);
+        if elapsed + PACING_TOLERANCE < self.interval {
+            return false;
+        }
+        let late = elapsed - self.interval;
+        let carry = if late > 0.0 && late < self.interval { late } else { 0.0 };
+        self.previous = Some(time - carry);
+        true
+    }
+}
+
+/// Plain data types, written to buffers as their memory bytes.
+///
+/// # Safety
+///
+/// Implementors must have a defined layout without padding bytes, which are uninitialized, and without pointers or
+/// references, so any bit pattern is a valid value.
+pub unsafe trait Pod {}
+
+macro_rules! pod {
+    ($($t:ty),*) => { $(unsafe impl Pod for $t {})* };
+}
+pod!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+unsafe impl<T: Pod> Pod for [T] {}
+// `MX` is `repr(C)` with the array of its elements as its only field
+unsafe impl<T: Pod, const R: usize, const C: usize> Pod for MX<T, R, C> where [(); R * C]: {}
+
+/// GPU buffer created with its contents.
+///
+/// Buffers are created with `COPY_DST` to write the contents, sizes are rounded up to a multiple of 4 bytes as
+/// required by `writeBuffer`.
+pub struct Buffer {
+    pub buffer: GpuBuffer,
+    #[allow(dead_code)]
+    len: u32,
+}
+
+impl Buffer {
+    /// Create a buffer with `usage` and write the bytes of `data` to it.
+    pub fn new<T: Pod + ?Sized>(device: &GpuDevice, usage: u32, data: &T) -> Buffer {
+        let bytes = Buffer::bytes(data);
+        let size = align_to(bytes.len() as u32, 4);
+        let buffer = device.create_buffer(&GpuBufferDescriptor::new(
+            size as f64,
+            usage | gpu_buffer_usage::COPY_DST,
+        ));
+        let mut padded = bytes.to_vec();
+        padded.resize(size as usize, 0);
+        device.queue().write_buffer_with_u32_and_u8_array(&buffer, 0, &padded);
+        Buffer {
+            buffer,
+            len: bytes.len() as u32,
+        }
+    }
+
+    pub fn vertex<T: Pod + ?Sized>(device: &GpuDevice, data: &T) -> Buffer {
+        Buffer::new(device, gpu_buffer_usage::VERTEX, data)
+    }
+
+    pub fn uniform<T: Pod + ?Sized>(device: &GpuDevice, data: &T) -> Buffer {
+        Buffer::new(device, gpu_buffer_usage::UNIFORM, data)
+    }
+
+    pub fn index<T: Pod + ?Sized>(device: &GpuDevice, data: &T) -> Buffer {
+        Buffer::new(device, gpu_buffer_usage::INDEX, data)
+    }
+
+    /// Write `data` to the start of the buffer, replacing it with a larger buffer with the same usage if it does not
+    /// fit, bind groups of the replaced buffer must be created again.
+    pub fn write<T: Pod + ?Sized>(&mut self, device: &GpuDevice, data: &T) {
+        let bytes = Buffer::bytes(data);
+        let size = align_to(bytes.len() as u32, 4);
+        if size as f64 > self.buffer.size() {
+            let label = self.buffer.label();
+            *self = Buffer::new(device, self.buffer.usage(), data);
+            self.buffer.set_label(&label);
+            return;
+        }
+        let mut padded = bytes.to_vec();
+        padded.resize(size as usize, 0);
+        device
+            .queue()
+            .write_buffer_with_u32_and_u8_array(&self.buffer, 0, &padded);
+        self.len = bytes.len() as u32;
+    }
+
+    /// Create a uniform buffer with a `mat4x4f`, matrices are row-major and WGSL matrices are column-major, so the
+    /// transpose is written and `m * p` in the shader matches `m.multiply(&p)`.
+    pub fn matrix(device: &GpuDevice, m: &MX<f32, 4, 4>) -> Buffer {
+        Buffer::uniform(device, &m.transpose())
+    }
+
+    /// Size of the contents in bytes, without the padding.
+    #[allow(dead_code)]
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the memory of `data` as bytes.
+    fn bytes<T: Pod + ?Sized>(data: &T) -> &[u8] {
+        // `Pod` types have no padding, all their bytes are initialized
+        unsafe { std::slice::from_raw_parts(data as *const T as *const u8, std::mem::size_of_val(data)) }
+    }
+}
+
+/// WebGPU capabilities, see `WebGpu::capabilities`.
+///
+/// Features are supported by the adapter but must still be requested when creating the device to be used.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    pub supports_timestamp_query: bool,
+    pub supports_float32_filterable: bool,
+    pub max_texture_dimension_2d: u32,
+    pub max_buffer_size: f64,
+}
+
+impl Capabilities {
+    /// Return true if a 2D texture of `width`x`height` is within the device limits.
+    pub fn fits_texture(&self, width: u32, height: u32) -> bool {
+        width.max(height) <= self.max_texture_dimension_2d
+    }
+}
+
+/// Builder for explicit `GpuBindGroupLayout`s.
+///
+/// Layouts are cached in `WebGpu` by their entries, building an equivalent layout twice returns the same object.
+/// Bind groups created from a cached layout can be shared by all pipelines that use it.
+#[derive(Clone, Default)]
+pub struct BindGroupLayoutBuilder {
+    entries: Vec<(u32, u32, GpuBufferBindingType, bool)>,
+}
+
+impl BindGroupLayoutBuilder {
+    pub fn new() -> BindGroupLayoutBuilder {
+        BindGroupLayoutBuilder::default()
+    }
+
+    /// Add a buffer entry at `binding`, `visibility` is a `gpu_shader_stage` mask.
+    pub fn buffer(mut self, binding: u32, visibility: u32, kind: GpuBufferBindingType) -> BindGroupLayoutBuilder {
+        self.entries.push((binding, visibility, kind, false));
+        self
+    }
+
+    /// Add a uniform buffer entry at `binding`, `visibility` is a `gpu_shader_stage` mask.
+    pub fn uniform(self, binding: u32, visibility: u32) -> BindGroupLayoutBuilder {
+        self.buffer(binding, visibility, GpuBufferBindingType::Uniform)
+    }
+
+    /// Add a uniform buffer entry at `binding` with a dynamic offset, `visibility` is a `gpu_shader_stage` mask.
+    ///
+    /// The offset is provided for each draw in `set_bind_group_with_u32_sequence`, in the order of the dynamic entries.
+    /// Offsets must be multiples of `WebGpu::uniform_alignment`.
+    pub fn dynamic_uniform(mut self, binding: u32, visibility: u32) -> BindGroupLayoutBuilder {
+        self.entries
+            .push((binding, visibility, GpuBufferBindingType::Uniform, true));
+        self
+    }
+
+    fn key(&self) -> String {
+        format!("{:?}", self.entries)
+    }
+
+    /// Return the cached layout for the builder entries, creating it if necessary.
+    pub fn build(&self, webgpu: &WebGpu) -> GpuBindGroupLayout {
+        let mut layouts = webgpu.bind_group_layouts.borrow_mut();
+        let layout = layouts.entry(self.key()).or_insert_with(|| {
+            let entries = Array::new();
+            for (binding, visibility, kind, dynamic) in &self.entries {
+                let mut buffer = GpuBufferBindingLayout::new();
+                buffer.type_(*kind).has_dynamic_offset(*dynamic);
+                let mut entry = GpuBindGroupLayoutEntry::new(*binding, *visibility);
+                entry.buffer(&buffer);
+                entries.push(&entry);
+            }
+            webgpu
+                .device
+                .create_bind_group_layout(&GpuBindGroupLayoutDescriptor::new(&entries))
+        });
+        layout.clone()
+    }
+}
+
+/// Builder for explicit `GpuPipelineLayout`s.
+///
+/// Layouts are cached in `WebGpu` by their bind group layouts, see `BindGroupLayoutBuilder`.
+#[derive(Clone, Default)]
+pub struct PipelineLayoutBuilder {
+    groups: Vec<BindGroupLayoutBuilder>,
+}
+
+impl PipelineLayoutBuilder {
+    pub fn new() -> PipelineLayoutBuilder {
+        PipelineLayoutBuilder::default()
+    }
+
+    /// Append a bind group layout, groups are indexed in insertion order.
+    pub fn group(mut self, group: &BindGroupLayoutBuilder) -> PipelineLayoutBuilder {
+        self.groups.push(group.clone());
+        self
+    }
+
+    /// Return the cached layout for the builder groups, creating it if necessary.
+    pub fn build(&self, webgpu: &WebGpu) -> GpuPipelineLayout {
+        let key = self.groups.iter().map(|g| g.key()).collect::<Vec<_>>().join("|");
+        if let Some(layout) = webgpu.pipeline_layouts.borrow().get(&key) {
+            return layout.clone();
+        }
+        let groups = Array::new();
+        self.groups.iter().for_each(|g| {
+            groups.push(&g.build(webgpu));
+        });
+        let layout = webgpu
+            .device
+            .create_pipeline_layout(&GpuPipelineLayoutDescriptor::new(&groups));
+        webgpu.pipeline_layouts.borrow_mut().insert(key, layout.clone());
+        layout
+    }
+}
+
+/// Builder for `GpuSampler`s, the defaults match WebGPU: nearest filtering, clamped addressing, and no anisotropy.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SamplerDesc {
+    mag_filter: GpuFilterMode,
+    min_filter: GpuFilterMode,
+    mipmap_filter: GpuMipmapFilterMode,
+    address_modes: [GpuAddressMode; 3],
+    max_anisotropy: u16,
+}
+
+impl Default for SamplerDesc {
+    fn default() -> SamplerDesc {
+        SamplerDesc {
+            mag_filter: GpuFilterMode::Nearest,
+            min_filter: GpuFilterMode::Nearest,
+            mipmap_filter: GpuMipmapFilterMode::Nearest,
+            address_modes: [GpuAddressMode::ClampToEdge; 3],
+            max_anisotropy: 1,
+        }
+    }
+}
+
+impl SamplerDesc {
+    pub fn new() -> SamplerDesc {
+        SamplerDesc::default()
+    }
+
+    /// Set the magnification, minification, and mipmap filters to `Nearest` (pixelated) or `Linear` (smooth).
+    pub fn filter(self, filter: GpuFilterMode) -> SamplerDesc {
+        let mipmap_filter = match filter {
+            GpuFilterMode::Linear => GpuMipmapFilterMode::Linear,
+            _ => GpuMipmapFilterMode::Nearest,
+        };
+        self.mag_filter(filter).min_filter(filter).mipmap_filter(mipmap_filter)
+    }
+
+    pub fn mag_filter(mut self, filter: GpuFilterMode) -> SamplerDesc {
+        self.mag_filter = filter;
+        self
+    }
+
+    pub fn min_filter(mut self, filter: GpuFilterMode) -> SamplerDesc {
+        self.min_filter = filter;
+        self
+    }
+
+    pub fn mipmap_filter(mut self, filter: GpuMipmapFilterMode) -> SamplerDesc {
+        self.mipmap_filter = filter;
+        self
+    }
+
+    /// Set the address mode of all texture coordinates, `ClampToEdge`, `Repeat`, or `MirrorRepeat`.
+    #[allow(dead_code)]
+    pub fn address_mode(mut self, mode: GpuAddressMode) -> SamplerDesc {
+        self.address_modes = [mode; 3];
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn address_mode_u(mut self, mode: GpuAddressMode) -> SamplerDesc {
+        self.address_modes[0] = mode;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn address_mode_v(mut self, mode: GpuAddressMode) -> SamplerDesc {
+        self.address_modes[1] = mode;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn address_mode_w(mut self, mode: GpuAddressMode) -> SamplerDesc {
+        self.address_modes[2] = mode;
+        self
+    }
+
+    /// Set the maximum anisotropy, clamped to `1..=16`.
+    ///
+    /// WebGPU rejects an anisotropy above 1 unless all filters are linear, otherwise `build` uses 1 instead.
+    #[allow(dead_code)]
+    pub fn max_anisotropy(mut self, max_anisotropy: u16) -> SamplerDesc {
+        self.max_anisotropy = max_anisotropy.clamp(1, 16);
+        self
+    }
+
+    /// Return the anisotropy `build` uses, see `max_anisotropy`.
+    fn anisotropy(&self) -> u16 {
+        let linear = self.mag_filter == GpuFilterMode::Linear
+            && self.min_filter == GpuFilterMode::Linear
+            && self.mipmap_filter == GpuMipmapFilterMode::Linear;
+        if linear {
+            self.max_anisotropy
+        } else {
+            1
+        }
+    }
+
+    pub fn build(&self, webgpu: &WebGpu) -> GpuSampler {
+        let mut descriptor = GpuSamplerDescriptor::new();
+        descriptor
+            .mag_filter(self.mag_filter)
+            .min_filter(self.min_filter)
+            .mipmap_filter(self.mipmap_filter)
+            .address_mode_u(self.address_modes[0])
+            .address_mode_v(self.address_modes[1])
+            .address_mode_w(self.address_modes[2])
+            .max_anisotropy(self.anisotropy());
+        webgpu.device.create_sampler_with_descriptor(&descriptor)
+    }
+}
+
+/// Return the sRGB variant of the 8-bit canvas `format`, other formats have none.
+pub fn srgb_format(format: GpuTextureFormat) -> Option<GpuTextureFormat> {
+    match format {
+        GpuTextureFormat::Bgra8unorm => Some(GpuTextureFormat::Bgra8unormSrgb),
+        GpuTextureFormat::Rgba8unorm => Some(GpuTextureFormat::Rgba8unormSrgb),
+        _ => None,
+    }
+}
+
+/// Return the number of mip levels of a `width` x `height` texture, halving the largest side down to 1 texel.
+///
+/// Sizes that are not powers of two round down at each level, e.g. `5x3` has the `5x3`, `2x1`, and `1x1` levels.
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+/// Round `size` up to the next multiple of `alignment`.
+pub fn align_to(size: u32, alignment: u32) -> u32 {
+    size.div_ceil(alignment) * alignment
+}
+
+/// Create a bind group of `layout` with a buffer binding for each entry, bindings are numbered in order.
+pub fn create_bind_group(webgpu: &WebGpu, layout: &GpuBindGroupLayout, bindings: &[GpuBufferBinding]) -> GpuBindGroup {
+    let entries = Array::new();
+    for (i, binding) in bindings.iter().enumerate() {
+        entries.push(&GpuBindGroupEntry::new(i as u32, binding));
+    }
+    webgpu
+        .device
+        .create_bind_group(&GpuBindGroupDescriptor::new(&entries, layout))
+}
+
+/// Index buffer element types, `u16` or `u32`.
+pub trait IndexElement: Pod + Copy {
+    const FORMAT: GpuIndexFormat;
+}
+
+impl IndexElement for u16 {
+    const FORMAT: GpuIndexFormat = GpuIndexFormat::Uint16;
+}
+
+impl IndexElement for u32 {
+    const FORMAT: GpuIndexFormat = GpuIndexFormat::Uint32;
+}
+
+/// Index buffer created with its indices, `format` matches the index type.
+pub struct IndexBuffer {
+    pub buffer: Buffer,
+    pub format: GpuIndexFormat,
+    pub count: u32,
+}
+
+impl IndexBuffer {
+    pub fn new<I: IndexElement>(device: &GpuDevice, indices: &[I]) -> IndexBuffer {
+        IndexBuffer {
+            buffer: Buffer::index(device, indices),
+            format: I::FORMAT,
+            count: indices.len() as u32,
+        }
+    }
+
+    /// Replace the indices, see `Buffer::write`.
+    pub fn write<I: IndexElement>(&mut self, device: &GpuDevice, indices: &[I]) {
+        self.buffer.write(device, indices);
+        self.format = I::FORMAT;
+        self.count = indices.len() as u32;
+    }
+}
+
+/// Indices of `Geometry::Indexed`, the variant sets the index format.
+pub enum Indices<'a> {
+    U16(&'a [u16]),
+    #[allow(dead_code)]
+    U32(&'a [u32]),
+}
+
+impl<'a> From<&'a [u16]> for Indices<'a> {
+    fn from(indices: &'a [u16]) -> Indices<'a> {
+        Indices::U16(indices)
+    }
+}
+
+impl<'a> From<&'a [u32]> for Indices<'a> {
+    fn from(indices: &'a [u32]) -> Indices<'a> {
+        Indices::U32(indices)
+    }
+}
+
+/// Triangle list geometry, either expanded `Triangles` or `Indexed` vertices shared by the triangles of `indices`.
+///
+/// Vertices are `N`-dimensional positions, see `vertex_format`.
+#[allow(clippy::identity_op)]
+pub enum Geometry<'a, const N: usize>
+where
+    [(); 1 * N]:,
+{
+    #[allow(dead_code)]
+    Triangles(&'a [Triangle<N>]),
+    Indexed(&'a [VR<f32, N>], Indices<'a>),
+}
+
+/// Number of vertices drawn for a `TriangleList` of `triangles`.
+#[allow(clippy::identity_op)]
+fn vertex_count<const N: usize>(triangles: &[Triangle<N>]) -> u32
+where
+    [(); 1 * N]:,
+{
+    (triangles.len() * 3) as u32
+}
+
+/// Vertex dimension `N`, vertex positions have 1 to 4 components, see `VertexFormat`.
+pub struct Dim<const N: usize>;
+
+/// Vertex format of `f32` positions, implemented by the supported `Dim`s.
+pub trait VertexFormat {
+    const FORMAT: &'static str;
+}
+
+impl VertexFormat for Dim<1> {
+    const FORMAT: &'static str = "float32";
+}
+
+impl VertexFormat for Dim<2> {
+    const FORMAT: &'static str = "float32x2";
+}
+
+impl VertexFormat for Dim<3> {
+    const FORMAT: &'static str = "float32x3";
+}
+
+impl VertexFormat for Dim<4> {
+    const FORMAT: &'static str = "float32x4";
+}
+
+/// Vertex format and stride in bytes of `N`-dimensional `f32` positions, `float32x3` and 12 bytes for `N = 3`.
+///
+/// Shaders receive positions as `vec4f`, missing components are filled with `0` and `w` with `1`.
+fn vertex_format<const N: usize>() -> (&'static str, u32)
+where
+    Dim<N>: VertexFormat,
+{
+    (Dim::<N>::FORMAT, (N * size_of::<f32>()) as u32)
+}
+
+/// Offsets of the copies of the geometry drawn by `draw`.
+const DRAW_OFFSETS: [[f32; 2]; 3] = [[-0.5, -0.5], [0.5, -0.5], [0.0, 0.5]];
+
+/// Resources of `draw`, the geometry and transform are written every frame.
+struct DrawState {
+    pipeline: GpuRenderPipeline,
+    bind_group: GpuBindGroup,
+    transform: Buffer,
+    /// Stride of the offsets in their uniform buffer.
+    stride: u32,
+    vertices: cell::RefCell<Buffer>,
+    indices: cell::RefCell<IndexBuffer>,
+}
+
+impl DrawState {
+    fn new<const N: usize>(webgpu: &WebGpu) -> DrawState
+    where
+        Dim<N>: VertexFormat,
+    {
+        let (format, stride) = vertex_format::<N>();
+        let attribute = js!({"format": format, "offset": 0, "shaderLocation": 0});
+        let layout = GpuVertexBufferLayout::new(stride as f64, &array::wrap(&attribute));
+        let module = webgpu.device.create_shader_module(&GpuShaderModuleDescriptor::new(
+            "
+            @group(0) @binding(0) var<uniform> offset: vec4f;
+            @group(0) @binding(1) var<uniform> transform: mat4x4f;
+
+            @vertex
+            fn vertex_main(@location(0) pos: vec4f) -> @builtin(position) vec4f {
+                // depth is moved to the middle of the clip space depth range, 2d positions are drawn at 0.5
+                return transform * vec4f(pos[0] * 0.3 + offset[0], pos[1] * 0.3 + offset[1], pos[2] * 0.3 + 0.5, 1.0);
+            }
+
+            @fragment
+            fn fragment_main() -> @location(0) vec4f {
+                return vec4f(1.0, 0.0, 0.0, 1.0);
+            }
+            ",
+        ));
+        let mut vertex = GpuVertexState::new("vertex_main", &module);
+        vertex.buffers(&array::wrap(&layout));
+        let target = js!({"format": JsValue::from(webgpu.view_format())});
+        let fragment = GpuFragmentState::new("fragment_main", &module, &array::wrap(&target));
+
+        // one uniform buffer with an offset per draw, each in its own aligned slice
+        let stride = align_to(16, webgpu.uniform_alignment());
+        let mut uniforms = vec![0.0f32; DRAW_OFFSETS.len() * stride as usize / 4];
+        for (i, offset) in DRAW_OFFSETS.iter().enumerate() {
+            let start = i * stride as usize / 4;
+            uniforms[start..start + 2].copy_from_slice(offset);
+        }
+        let offsets = Buffer::uniform(&webgpu.device, uniforms.as_slice());
+        offsets.buffer.set_label("offsets");
+        let transform = Buffer::matrix(&webgpu.device, &MX::identity());
+        transform.buffer.set_label("transform");
+
+        let group = BindGroupLayoutBuilder::new()
+            .dynamic_uniform(0, gpu_shader_stage::VERTEX)
+            .uniform(1, gpu_shader_stage::VERTEX);
+        let mut binding = GpuBufferBinding::new(&offsets.buffer);
+        binding.size(16.0);
+        let bind_group = create_bind_group(
+            webgpu,
+            &group.build(webgpu),
+            &[binding, GpuBufferBinding::new(&transform.buffer)],
+        );
+        let pipeline_layout = PipelineLayoutBuilder::new().group(&group).build(webgpu);
+        let mut descriptor = GpuRenderPipelineDescriptor::new(&pipeline_layout, &vertex);
+        descriptor.fragment(&fragment);
+        let vertices = Buffer::vertex(&webgpu.device, &[] as &[f32]);
+        vertices.buffer.set_label("triangles");
+        DrawState {
+            pipeline: webgpu.device.create_render_pipeline(&descriptor),
+            bind_group,
+            transform,
+            stride,
+            vertices: cell::RefCell::new(vertices),
+            indices: cell::RefCell::new(IndexBuffer::new::<u16>(&webgpu.device, &[])),
+        }
+    }
+}
+
+/// Clear the current canvas texture and draw `geometry` once per offset, all transformed by `transform`.
+///
+/// The pipeline and buffers are created once per vertex dimension, drawing only writes the geometry and transform.
+#[allow(clippy::identity_op)]
+pub fn draw<const N: usize>(webgpu: &WebGpu, clear: MX<f32, 1, 4>, geometry: Geometry<N>, transform: &MX<f32, 4, 4>)
+where
+    [(); 1 * N]:,
+    Dim<N>: VertexFormat,
+{
+    let state = webgpu.cached(&format!("draw {N}"), || DrawState::new::<N>(webgpu));
+    let mut vertex_buffer = state.vertices.borrow_mut();
+    let mut index_buffer = state.indices.borrow_mut();
+    let (indexed, vertices) = match geometry {
+        Geometry::Triangles(triangles) => {
+            // tuples have no defined layout, their vertices are copied in order
+            let vertices = triangles.iter().flat_map(|t| [t.0, t.1, t.2]).collect::<Vec<_>>();
+            vertex_buffer.write(&webgpu.device, vertices.as_slice());
+            (false, vertex_count(triangles))
+        }
+        Geometry::Indexed(vertices, indices) => {
+            vertex_buffer.write(&webgpu.device, vertices);
+            match indices {
+                Indices::U16(indices) => index_buffer.write(&webgpu.device, indices),
+                Indices::U32(indices) => index_buffer.write(&webgpu.device, indices),
+            }
+            (true, index_buffer.count)
+        }
+    };
+    let transform = transform.transpose();
+    webgpu
+        .device
+        .queue()
+        .write_buffer_with_u32_and_u8_array(&state.transform.buffer, 0, Buffer::bytes(&transform));
+
+    let clear = js!([clear[0], clear[1], clear[2], clear[3]]);
+    let view = webgpu.current_view();
+    let attachment = js!({"view": view, "loadOp": "clear", "storeOp": "store", "clearValue": clear});
     let encoder = webgpu.device.create_command_encoder();
-    web_sys::console::log_1(&encoder);
-    let descriptor = Object::new();
-    let color_attachment = Object::new();
-    let color_attachments = Array::new();
-    color_attachments.push(&color_attachment);
-    Reflect::set(
-        &descriptor,
-        &JsValue::from("colorAttachments"),
-        &color_attachments,
-    )
-    .unwrap();
-    let texture = webgpu.context.get_current_texture();
-    let view = texture.create_view();
-    Reflect::set(
-        //
-        &color_attachment,
-        &JsValue::from("view"),
-        &view,
-    )
-    .unwrap();
-    Reflect::set(
-        &color_attachment,
-        &JsValue::from("loadOp"),
-        &JsValue::from("clear"),
-    )
-    .unwrap();
-    Reflect::set(
-        &color_attachment,
-        &JsValue::from("storeOp"),
-        &JsValue::from("store"),
-    )
-    .unwrap();
-
-    //
-    //
-
-    let clear3 = array::typed_f32(&clear);
-
-    // Reflect::set(&color_attachment, &JsValue::from("clearValue"), &clear3).unwrap();
-    Reflect::set(&color_attachment, &JsValue::from("clearValue"), &clear3).unwrap();
-
-    web_sys::console::log_1(&color_attachments);
-    web_sys::console::log_1(&texture);
-    web_sys::console::log_1(&view);
-    web_sys::console::log_1(&descriptor);
-
-    //
-
-    // web_sys::console::log_1(&memory_buffer());
-    // web_sys::console::log_1(&data_array(clear, false));
-    // web_sys::console::log_1(&data_array(clear, false));
-    // web_sys::console::log_1(&data_array(clear, true));
-    //
-    //
-
-    let pass = encoder.begin_render_pass(&GpuRenderPassDescriptor::new(&color_attachments));
-
-    let triangles: [Triangle<2>; 2] = [
-        (
-            mx!(VR[-0.8f32, -0.8f32]),
-            mx!(VR[0.8f32, -0.8f32]),
-            mx!(VR[0.8f32, 0.8f32]),
-        ),
-        (
-            mx!(VR[-0.8f32, -0.8f32]),
-            mx!(VR[0.8f32, 0.8f32]),
-            mx!(VR[-0.8f32, 0.8f32]),
-        ),
-    ];
+    let pass = encoder.begin_render_pass(&GpuRenderPassDescriptor::new(&array::wrap(&attachment)));
+    pass.set_pipeline(&state.pipeline);
+    pass.set_vertex_buffer(0, &vertex_buffer.buffer);
+    if indexed {
+        pass.set_index_buffer(&index_buffer.buffer.buffer, index_buffer.format);
+    }
+    for i in 0..DRAW_OFFSETS.len() as u32 {
+        let offset = array::wrap(&JsValue::from(i * state.stride));
+        pass.set_bind_group_with_u32_sequence(0, &state.bind_group, &offset);
+        match indexed {
+            true => pass.draw_indexed(vertices),
+            false => pass.draw(vertices),
+        }
+        webgpu.count_draw(GpuPrimitiveTopology::TriangleList, vertices);
+    }
+    pass.end();
+    webgpu.device.queue().submit(&array::wrap(&encoder.finish()));
+}
 
-    let x = &array::typed_f32(&triangles);
-    web_sys::console::log_1(&x);
-
-    // web_sys::console::log_1(&JsValue::from(x.at(0)));
-    // web_sys::console::log_1(&JsValue::from(x.at(1)));
-    // web_sys::console::log_1(&JsValue::from(x.at(2)));
-    // web_sys::console::log_1(&JsValue::from(x.at(3)));
-    // web_sys::console::log_1(&JsValue::from(x.at(4)));
-    // web_sys::console::log_1(&JsValue::from(x.at(5)));
-    // web_sys::console::log_1(&JsValue::from(x.at(6)));
-    // web_sys::console::log_1(&JsValue::from(x.at(7)));
-    // web_sys::console::log_1(&JsValue::from(x.at(8)));
-    // web_sys::console::log_1(&JsValue::from(x.at(9)));
-    // web_sys::console::log_1(&JsValue::from(x.at(10)));
-    // web_sys::console::log_1(&JsValue::from(x.at(11)));
-
-    let dd = GpuBufferDescriptor::new(x.byte_length() as f64, 8 | 32);
-    let bff = webgpu.device.create_buffer(&dd);
-    bff.set_label("triangles");
-    web_sys::console::log_1(&dd);
-    web_sys::console::log_1(&bff);
+/// Resources of `draw_primitives` for one topology, the vertices and color are written on every call.
+struct PrimitivesState {
+    pipeline: GpuRenderPipeline,
+    bind_group: GpuBindGroup,
+    color: Buffer,
+    vertices: cell::RefCell<Buffer>,
+}
+
+impl PrimitivesState {
+    fn new(webgpu: &WebGpu, topology: GpuPrimitiveTopology) -> PrimitivesState {
+        let color = Buffer::uniform(&webgpu.device, &[0.0f32; 4]);
+        let module = webgpu.device.create_shader_module(&GpuShaderModuleDescriptor::new(
+            "
+            @group(0) @binding(0) var<uniform> color: vec4f;
+
+            @vertex
+            fn vertex_main(@location(0) pos: vec3f) -> @builtin(position) vec4f {
+                return vec4f(pos, 1.0);
+            }
+
+            @fragment
+            fn fragment_main() -> @location(0) vec4f {
+                return color;
+            }
+            ",
+        ));
+        let group = BindGroupLayoutBuilder::new().uniform(0, gpu_shader_stage::FRAGMENT);
+        let bind_group = webgpu.device.create_bind_group(&GpuBindGroupDescriptor::new(
+            &array::wrap(&GpuBindGroupEntry::new(0, &GpuBufferBinding::new(&color.buffer))),
+            &group.build(webgpu),
+        ));
+        let attribute = js!({"format": "float32x3", "offset": 0, "shaderLocation": 0});
+        let mut vertex = GpuVertexState::new("vertex_main", &module);
+        let layout = GpuVertexBufferLayout::new(12.0, &array::wrap(&attribute));
+        vertex.buffers(&array::wrap(&layout));
+        let target = js!({"format": JsValue::from(webgpu.view_format())});
+        let fragment = GpuFragmentState::new("fragment_main", &module, &array::wrap(&target));
+        let mut primitive = GpuPrimitiveState::new();
+        primitive.topology(topology);
+        let pipeline_layout = PipelineLayoutBuilder::new().group(&group).build(webgpu);
+        let mut descriptor = GpuRenderPipelineDescriptor::new(&pipeline_layout, &vertex);
+        descriptor.fragment(&fragment).primitive(&primitive);
+        PrimitivesState {
+            pipeline: webgpu.device.create_render_pipeline(&descriptor),
+            bind_group,
+            color,
+            vertices: cell::RefCell::new(Buffer::vertex(&webgpu.device, &[] as &[f32])),
+        }
+    }
+}
 
+/// Draw `vertices` as `topology` primitives with a single `color` on top of the current canvas texture.
+///
+/// Vertices are in clip space. Points are always rasterized as 1px squares and lines as 1px wide lines. Buffers are
+/// shared by all calls with the same topology, queue writes are ordered with submits, so each call draws its own.
+pub fn draw_primitives(webgpu: &WebGpu, topology: GpuPrimitiveTopology, vertices: &[VR<f32, 3>], color: VR<f32, 4>) {
+    let state = webgpu.cached(&format!("primitives {topology:?}"), || {
+        PrimitivesState::new(webgpu, topology)
+    });
+    let mut vertex_buffer = state.vertices.borrow_mut();
+    vertex_buffer.write(&webgpu.device, vertices);
     webgpu
         .device
         .queue()
-        .write_buffer_with_u32_and_buffer_source(&bff, 0, &x);
+        .write_buffer_with_u32_and_u8_array(&state.color.buffer, 0, Buffer::bytes(&color));
 
-    let attr = Object::new();
-    Reflect::set(&attr, &JsValue::from("format"), &JsValue::from("float32x2")).unwrap();
-    Reflect::set(&attr, &JsValue::from("offset"), &JsValue::from(0)).unwrap();
-    Reflect::set(&attr, &JsValue::from("shaderLocation"), &JsValue::from(0)).unwrap();
+    let view = webgpu.current_view();
+    let attachment = js!({"view": view, "loadOp": "load", "storeOp": "store"});
+    let encoder = webgpu.device.create_command_encoder();
+    let pass = encoder.begin_render_pass(&GpuRenderPassDescriptor::new(&array::wrap(&attachment)));
+    pass.set_pipeline(&state.pipeline);
+    pass.set_bind_group(0, &state.bind_group);
+    pass.set_vertex_buffer(0, &vertex_buffer.buffer);
+    pass.draw(vertices.len() as u32);
+    webgpu.count_draw(topology, vertices.len() as u32);
+    pass.end();
+    webgpu.device.queue().submit(&array::wrap(&encoder.finish()));
+}
 
-    let layout = GpuVertexBufferLayout::new(8.0, &array::wrap(&attr));
-    web_sys::console::log_1(&layout);
+/// Draw a line for each pair of `vertices`, see `draw_primitives`.
+pub fn draw_lines(webgpu: &WebGpu, vertices: &[VR<f32, 3>], color: VR<f32, 4>) {
+    draw_primitives(webgpu, GpuPrimitiveTopology::LineList, vertices, color);
+}
 
-    let sha = GpuShaderModuleDescriptor::new(
-        "
-    @vertex
-    fn vertex_main(@location(0) pos: vec4f) -> @builtin(position) vec4f {
-        // return pos;
-        return vec4f(pos[0], pos[1], 0.0, 1.0);
+/// Draw a point for each of `vertices`, see `draw_primitives`.
+#[allow(dead_code)]
+pub fn draw_points(webgpu: &WebGpu, vertices: &[VR<f32, 3>], color: VR<f32, 4>) {
+    draw_primitives(webgpu, GpuPrimitiveTopology::PointList, vertices, color);
+}
+
+/// Draw the coordinate axes, `x` in red, `y` in green, and `z` in blue, `z` is drawn diagonally towards the viewer.
+pub fn draw_axes(webgpu: &WebGpu) {
+    let origin = mx!(VR[0.0, 0.0, 0.5]);
+    draw_lines(webgpu, &[origin, mx!(VR[0.9, 0.0, 0.5])], mx!(VR[1.0, 0.0, 0.0, 1.0]));
+    draw_lines(webgpu, &[origin, mx!(VR[0.0, 0.9, 0.5])], mx!(VR[0.0, 1.0, 0.0, 1.0]));
+    draw_lines(webgpu, &[origin, mx!(VR[-0.6, -0.6, 0.5])], mx!(VR[0.0, 0.0, 1.0, 1.0]));
+}
+
+/// Draw a triangle from a render bundle, the bundle and its resources are created once and replayed every frame.
+pub fn draw_bundle_demo(webgpu: &WebGpu) {
+    let bundle = webgpu.bundle("demo", |encoder| {
+        let vertices: [f32; 6] = [0.6, 0.6, 0.9, 0.6, 0.75, 0.9];
+        let vertex_buffer = Buffer::vertex(&webgpu.device, &vertices);
+        let module = webgpu.device.create_shader_module(&GpuShaderModuleDescriptor::new(
+            "
+            @vertex
+            fn vertex_main(@location(0) pos: vec2f) -> @builtin(position) vec4f {
+                return vec4f(pos, 0.0, 1.0);
+            }
+
+            @fragment
+            fn fragment_main() -> @location(0) vec4f {
+                return vec4f(1.0, 0.8, 0.0, 1.0);
+            }
+            ",
+        ));
+        let attribute = js!({"format": "float32x2", "offset": 0, "shaderLocation": 0});
+        let layout = GpuVertexBufferLayout::new(8.0, &array::wrap(&attribute));
+        let mut vertex = GpuVertexState::new("vertex_main", &module);
+        vertex.buffers(&array::wrap(&layout));
+        let target = js!({"format": JsValue::from(webgpu.view_format())});
+        let fragment = GpuFragmentState::new("fragment_main", &module, &array::wrap(&target));
+        let pipeline_layout = PipelineLayoutBuilder::new().build(webgpu);
+        let mut descriptor = GpuRenderPipelineDescriptor::new(&pipeline_layout, &vertex);
+        descriptor.fragment(&fragment);
+        encoder.set_pipeline(&webgpu.device.create_render_pipeline(&descriptor));
+        encoder.set_vertex_buffer(0, &vertex_buffer.buffer);
+        encoder.draw(3);
+    });
+    let view = webgpu.current_view();
+    let attachment = js!({"view": view, "loadOp": "load", "storeOp": "store"});
+    let encoder = webgpu.device.create_command_encoder();
+    let pass = encoder.begin_render_pass(&GpuRenderPassDescriptor::new(&array::wrap(&attachment)));
+    pass.execute_bundles(&array::wrap(&bundle));
+    webgpu.count_draw(GpuPrimitiveTopology::TriangleList, 3);
+    pass.end();
+    webgpu.device.queue().submit(&array::wrap(&encoder.finish()));
+}
+
+/// Draw a triangle whose brightness is the `brightness` override constant, see `WebGpu::set_constant`.
+pub fn draw_constants_demo(webgpu: &WebGpu) {
+    let demo = webgpu.cached_with_constants("constants demo", &["brightness"], || {
+        let vertices: [f32; 6] = [-0.9, -0.9, -0.6, -0.9, -0.75, -0.6];
+        let vertex_buffer = Buffer::vertex(&webgpu.device, &vertices);
+        let module = webgpu.device.create_shader_module(&GpuShaderModuleDescriptor::new(
+            "
+            override brightness: f32 = 1.0;
+
+            @vertex
+            fn vertex_main(@location(0) pos: vec2f) -> @builtin(position) vec4f {
+                return vec4f(pos, 0.0, 1.0);
+            }
+
+            @fragment
+            fn fragment_main() -> @location(0) vec4f {
+                return vec4f(vec3f(0.2, 0.6, 1.0) * brightness, 1.0);
+            }
+            ",
+        ));
+        let attribute = js!({"format": "float32x2", "offset": 0, "shaderLocation": 0});
+        let layout = GpuVertexBufferLayout::new(8.0, &array::wrap(&attribute));
+        let mut vertex = GpuVertexState::new("vertex_main", &module);
+        vertex.buffers(&array::wrap(&layout));
+        let target = js!({"format": JsValue::from(webgpu.view_format())});
+        let fragment = GpuFragmentState::new("fragment_main", &module, &array::wrap(&target));
+        js!(fragment["constants"] = webgpu.constants(&["brightness"]));
+        let pipeline_layout = PipelineLayoutBuilder::new().build(webgpu);
+        let mut descriptor = GpuRenderPipelineDescriptor::new(&pipeline_layout, &vertex);
+        descriptor.fragment(&fragment);
+        (webgpu.device.create_render_pipeline(&descriptor), vertex_buffer)
+    });
+    let (pipeline, vertex_buffer) = &*demo;
+
+    let view = webgpu.current_view();
+    let attachment = js!({"view": view, "loadOp": "load", "storeOp": "store"});
+    let encoder = webgpu.device.create_command_encoder();
+    let pass = encoder.begin_render_pass(&GpuRenderPassDescriptor::new(&array::wrap(&attachment)));
+    pass.set_pipeline(pipeline);
+    pass.set_vertex_buffer(0, &vertex_buffer.buffer);
+    pass.draw(3);
+    webgpu.count_draw(GpuPrimitiveTopology::TriangleList, 3);
+    pass.end();
+    webgpu.device.queue().submit(&array::wrap(&encoder.finish()));
+}
+
+/// Compile `source` into a pipeline drawing a fullscreen triangle, see `draw_fullscreen`.
+///
+/// The source must have the `vertex_main` and `fragment_main` entry points, the vertex stage receives
+/// `@builtin(vertex_index)` in `0..3` and no vertex buffers. Compilation does not block, see
+/// `WebGpu::create_render_pipeline_async`.
+pub fn compile_fullscreen(
+    webgpu: &WebGpu,
+    source: &str,
+) -> impl Future<Output = Result<GpuRenderPipeline, WebGpuError>> {
+    let module = webgpu
+        .device
+        .create_shader_module(&GpuShaderModuleDescriptor::new(source));
+    let vertex = GpuVertexState::new("vertex_main", &module);
+    let target = js!({"format": JsValue::from(webgpu.view_format())});
+    let fragment = GpuFragmentState::new("fragment_main", &module, &array::wrap(&target));
+    let mut descriptor = GpuRenderPipelineDescriptor::new(&JsValue::from("auto"), &vertex);
+    descriptor.fragment(&fragment);
+    webgpu.create_render_pipeline_async(&descriptor)
+}
+
+/// Draw a fullscreen triangle with a `pipeline` from `compile_fullscreen` on top of the current canvas texture.
+///
+/// Non-empty `params` are bound as the uniform buffer at `@group(0) @binding(0)`, see `wgsl::params`. The buffer
+/// and its bind group are kept while the pipeline is the same and the params fit, otherwise the params are written.
+pub fn draw_fullscreen(webgpu: &WebGpu, pipeline: &GpuRenderPipeline, params: &[f32]) {
+    let params_state = webgpu.cached("fullscreen", || {
+        cell::RefCell::new(None::<(GpuRenderPipeline, Buffer, GpuBindGroup)>)
+    });
+    let mut params_state = params_state.borrow_mut();
+    let view = webgpu.current_view();
+    let attachment = js!({"view": view, "loadOp": "load", "storeOp": "store"});
+    let encoder = webgpu.device.create_command_encoder();
+    let pass = encoder.begin_render_pass(&GpuRenderPassDescriptor::new(&array::wrap(&attachment)));
+    pass.set_pipeline(pipeline);
+    if !params.is_empty() {
+        let size = std::mem::size_of_val(params) as f64;
+        match &*params_state {
+            Some((bound, buffer, _)) if bound == pipeline && buffer.buffer.size() >= size => {
+                let bytes = Buffer::bytes(params);
+                webgpu
+                    .device
+                    .queue()
+                    .write_buffer_with_u32_and_u8_array(&buffer.buffer, 0, bytes);
+            }
+            _ => {
+                let uniform_buffer = Buffer::uniform(&webgpu.device, params);
+                uniform_buffer.buffer.set_label("params");
+                let binding = GpuBufferBinding::new(&uniform_buffer.buffer);
+                let bind_group = webgpu.device.create_bind_group(&GpuBindGroupDescriptor::new(
+                    &array::wrap(&GpuBindGroupEntry::new(0, &binding)),
+                    &pipeline.get_bind_group_layout(0),
+                ));
+                *params_state = Some((pipeline.clone(), uniform_buffer, bind_group));
+            }
         }
+        let (_, _, bind_group) = params_state.as_ref().unwrap();
+        pass.set_bind_group(0, bind_group);
+    }
+    pass.draw(3);
+    webgpu.count_draw(GpuPrimitiveTopology::TriangleList, 3);
+    pass.end();
+    webgpu.device.queue().submit(&array::wrap(&encoder.finish()));
+}
 
-        @fragment
-        fn fragment_main() -> @location(0) vec4f
-        {
-        return vec4f(1.0, 0.0, 0.0, 1.0);
+/// Fullscreen triangle reading the nearest texel of the bound texture, see `WebGpu::blit`.
+const BLIT_SHADER: &str = "
+struct Varyings {
+    @builtin(position) position: vec4f,
+    @location(0) uv: vec2f,
+}
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+
+@vertex
+fn vertex_main(@builtin(vertex_index) i: u32) -> Varyings {
+    let uv = vec2f(f32((i << 1u) & 2u), f32(i & 2u));
+    return Varyings(vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0), uv);
+}
+
+@fragment
+fn fragment_main(in: Varyings) -> @location(0) vec4f {
+    let size = textureDimensions(source);
+    let texel = min(vec2u(in.uv * vec2f(size)), size - 1u);
+    return textureLoad(source, texel, 0);
+}
+";
+
+/// Fullscreen triangle sampling the bound texture with the bound sampler, see `WebGpu::generate_mipmaps`.
+const SAMPLE_SHADER: &str = "
+struct Varyings {
+    @builtin(position) position: vec4f,
+    @location(0) uv: vec2f,
+}
+
+@group(0) @binding(0) var image_sampler: sampler;
+@group(0) @binding(1) var image: texture_2d<f32>;
+
+@vertex
+fn vertex_main(@builtin(vertex_index) i: u32) -> Varyings {
+    let uv = vec2f(f32((i << 1u) & 2u), f32(i & 2u));
+    return Varyings(vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0), uv);
+}
+
+@fragment
+fn fragment_main(in: Varyings) -> @location(0) vec4f {
+    return textureSample(image, image_sampler, in.uv);
+}
+";
+
+/// Write a gradient disk into a storage texture with a compute shader and blit it to the top-left canvas corner.
+///
+/// The texture is cleared first, texels outside the disk are not written and keep the clear color. The texture is
+/// written once, later frames only blit it.
+pub fn draw_compute_demo(webgpu: &WebGpu) {
+    const SIZE: u32 = 128;
+    let canvas = (webgpu.canvas.width(), webgpu.canvas.height());
+    if canvas.0 < SIZE + 16 || canvas.1 < SIZE + 16 {
+        return;
+    }
+    let texture = webgpu.cached("compute demo", || compute_demo_texture(webgpu, SIZE));
+    webgpu.blit(&texture, Some([16.0, 16.0, SIZE as f32, SIZE as f32]));
+}
+
+/// Create the texture of `draw_compute_demo`.
+fn compute_demo_texture(webgpu: &WebGpu, size: u32) -> GpuTexture {
+    let texture_size = js!([size, size]);
+    let texture = webgpu.device.create_texture(&GpuTextureDescriptor::new(
+        GpuTextureFormat::Rgba8unorm,
+        &texture_size,
+        gpu_texture_usage::STORAGE_BINDING | gpu_texture_usage::TEXTURE_BINDING | gpu_texture_usage::RENDER_ATTACHMENT,
+    ));
+    webgpu.clear_texture(&texture, mx!(VR[0.1, 0.1, 0.1, 1.0]));
+
+    let module = webgpu.device.create_shader_module(&GpuShaderModuleDescriptor::new(
+        "
+        @group(0) @binding(0) var image: texture_storage_2d<rgba8unorm, write>;
+
+        @compute @workgroup_size(8, 8)
+        fn compute_main(@builtin(global_invocation_id) id: vec3u) {
+            let size = textureDimensions(image);
+            let uv = (vec2f(id.xy) + 0.5) / vec2f(size);
+            if any(id.xy >= size) || distance(uv, vec2f(0.5)) > 0.5 {
+                return;
+            }
+            textureStore(image, id.xy, vec4f(uv, 1.0 - uv.x, 1.0));
         }
+        ",
+    ));
+    let stage = GpuProgrammableStage::new("compute_main", &module);
+    let pipeline = webgpu
+        .device
+        .create_compute_pipeline(&GpuComputePipelineDescriptor::new(&JsValue::from("auto"), &stage));
+    let bind_group = webgpu.device.create_bind_group(&GpuBindGroupDescriptor::new(
+        &array::wrap(&GpuBindGroupEntry::new(0, &texture.create_view())),
+        &pipeline.get_bind_group_layout(0),
+    ));
+    let encoder = webgpu.device.create_command_encoder();
+    let pass = encoder.begin_compute_pass();
+    pass.set_pipeline(&pipeline);
+    pass.set_bind_group(0, &bind_group);
+    pass.dispatch_workgroups_with_workgroup_count_y(size.div_ceil(8), size.div_ceil(8));
+    pass.end();
+    webgpu.device.queue().submit(&array::wrap(&encoder.finish()));
+    texture
+}
+
+/// Create a pipeline drawing `SAMPLE_SHADER` to the canvas.
+fn create_sample_pipeline(webgpu: &WebGpu) -> GpuRenderPipeline {
+    let module = webgpu
+        .device
+        .create_shader_module(&GpuShaderModuleDescriptor::new(SAMPLE_SHADER));
+    let target = js!({"format": JsValue::from(webgpu.view_format())});
+    let fragment = GpuFragmentState::new("fragment_main", &module, &array::wrap(&target));
+    let mut descriptor =
+        GpuRenderPipelineDescriptor::new(&JsValue::from("auto"), &GpuVertexState::new("vertex_main", &module));
+    descriptor.fragment(&fragment);
+    webgpu.device.create_render_pipeline(&descriptor)
+}
+
+/// Create the bind group of `sampler` and `texture` for a pipeline from `create_sample_pipeline`.
+fn create_sample_bind_group(
+    webgpu: &WebGpu,
+    pipeline: &GpuRenderPipeline,
+    sampler: &GpuSampler,
+    texture: &GpuTexture,
+) -> GpuBindGroup {
+    let entries = Array::new();
+    entries.push(&GpuBindGroupEntry::new(0, sampler));
+    entries.push(&GpuBindGroupEntry::new(1, &texture.create_view()));
+    webgpu.device.create_bind_group(&GpuBindGroupDescriptor::new(
+        &entries,
+        &pipeline.get_bind_group_layout(0),
+    ))
+}
+
+/// Draw a 4x4 checker texture magnified to the top-right canvas corner, sampled with `filter`.
+///
+/// `Nearest` shows the texels as sharp squares and `Linear` blends neighbouring texels into a smooth gradient.
+pub fn draw_sampler_demo(webgpu: &WebGpu, filter: GpuFilterMode) {
+    const SIZE: u32 = 128;
+    let canvas = (webgpu.canvas.width(), webgpu.canvas.height());
+    if canvas.0 < 2 * SIZE + 32 || canvas.1 < SIZE + 16 {
+        return;
+    }
+    let demo = webgpu.cached("sampler demo", || {
+        // red and green texels alternate, blue increases to the right
+        let texels: [u8; 64] = std::array::from_fn(|i| {
+            let (x, y) = (i / 4 % 4, i / 16);
+            let red = (x + y) % 2 == 0;
+            [255 * red as u8, 255 * !red as u8, x as u8 * 85, 255][i % 4]
+        });
+        let size = js!([4, 4]);
+        let texture = webgpu.device.create_texture(&GpuTextureDescriptor::new(
+            GpuTextureFormat::Rgba8unorm,
+            &size,
+            gpu_texture_usage::TEXTURE_BINDING | gpu_texture_usage::COPY_DST,
+        ));
+        let mut layout = GpuImageDataLayout::new();
+        layout.bytes_per_row(4 * 4);
+        webgpu.device.queue().write_texture_with_u8_array_and_u32_sequence(
+            &GpuImageCopyTexture::new(&texture),
+            &texels,
+            &layout,
+            &size,
+        );
+        (create_sample_pipeline(webgpu), texture)
+    });
+    let (pipeline, texture) = &*demo;
+    let bind_group = webgpu.cached(&format!("sampler demo {filter:?}"), || {
+        let sampler = SamplerDesc::new().filter(filter).build(webgpu);
+        create_sample_bind_group(webgpu, pipeline, &sampler, texture)
+    });
+
+    let view = webgpu.current_view();
+    let attachment = js!({"view": view, "loadOp": "load", "storeOp": "store"});
+    let encoder = webgpu.device.create_command_encoder();
+    let pass = encoder.begin_render_pass(&GpuRenderPassDescriptor::new(&array::wrap(&attachment)));
+    pass.set_pipeline(pipeline);
+    pass.set_bind_group(0, &bind_group);
+    let x = (canvas.0 - SIZE - 16) as f32;
+    pass.set_viewport(x, 16.0, SIZE as f32, SIZE as f32, 0.0, 1.0);
+    pass.draw(3);
+    webgpu.count_draw(GpuPrimitiveTopology::TriangleList, 3);
+    pass.end();
+    webgpu.device.queue().submit(&array::wrap(&encoder.finish()));
+}
+
+/// Draw a texture of 1 texel wide stripes minified to the bottom-right canvas corner, without mipmaps on the left and
+/// with them on the right.
+///
+/// Without mipmaps neighbouring pixels sample unrelated texels and the stripes alias into a noisy pattern, with them
+/// pixels sample a level where the stripes are averaged into a smooth gray.
+pub fn draw_mipmap_demo(webgpu: &WebGpu) {
+    const TEXTURE_SIZE: u32 = 256;
+    // not a power of two fraction of the texture, so the minified texels do not line up with the pixels
+    const SIZE: u32 = 60;
+    let canvas = (webgpu.canvas.width(), webgpu.canvas.height());
+    if canvas.0 < 2 * SIZE + 32 || canvas.1 < SIZE + 16 {
+        return;
+    }
+    let demo = webgpu.cached("mipmap demo", || {
+        let texels = (0..TEXTURE_SIZE * TEXTURE_SIZE)
+            .flat_map(|i| [255 * (i % 2) as u8; 3].into_iter().chain([255]))
+            .collect::<Vec<_>>();
+        let pipeline = create_sample_pipeline(webgpu);
+        let sampler = SamplerDesc::new().filter(GpuFilterMode::Linear).build(webgpu);
+        let bind_groups = [false, true].map(|mipmaps| {
+            let texture = webgpu.create_texture_from_rgba(TEXTURE_SIZE, TEXTURE_SIZE, &texels, mipmaps);
+            create_sample_bind_group(webgpu, &pipeline, &sampler, &texture)
+        });
+        (pipeline, bind_groups)
+    });
+    let (pipeline, bind_groups) = &*demo;
+
+    let view = webgpu.current_view();
+    let attachment = js!({"view": view, "loadOp": "load", "storeOp": "store"});
+    let encoder = webgpu.device.create_command_encoder();
+    let pass = encoder.begin_render_pass(&GpuRenderPassDescriptor::new(&array::wrap(&attachment)));
+    pass.set_pipeline(pipeline);
+    for (i, bind_group) in bind_groups.iter().enumerate() {
+        pass.set_bind_group(0, bind_group);
+        let x = canvas.0 - (2 - i as u32) * (SIZE + 16);
+        let y = canvas.1 - SIZE - 16;
+        pass.set_viewport(x as f32, y as f32, SIZE as f32, SIZE as f32, 0.0, 1.0);
+        pass.draw(3);
+        webgpu.count_draw(GpuPrimitiveTopology::TriangleList, 3);
+    }
+    pass.end();
+    webgpu.device.queue().submit(&array::wrap(&encoder.finish()));
+}
+
+/// Draw a gray gradient strip below the sampler demo, comparing sRGB and linear canvas views.
+///
+/// The top half outputs the gradient decoded to linear values and the bottom half outputs it as is. With
+/// `WebGpu::set_srgb` enabled, the top half is encoded back and matches the bottom half without it, while the bottom
+/// half looks washed out. With it disabled, the top half looks too dark.
+pub fn draw_srgb_demo(webgpu: &WebGpu) {
+    const WIDTH: u32 = 256;
+    const HEIGHT: u32 = 64;
+    let canvas = (webgpu.canvas.width(), webgpu.canvas.height());
+    if canvas.0 < WIDTH + 32 || canvas.1 < HEIGHT + 176 {
+        return;
+    }
+    let pipeline = webgpu.cached("srgb demo", || {
+        let module = webgpu.device.create_shader_module(&GpuShaderModuleDescriptor::new(
+            "
+            struct Varyings {
+                @builtin(position) position: vec4f,
+                @location(0) uv: vec2f,
+            }
+
+            @vertex
+            fn vertex_main(@builtin(vertex_index) i: u32) -> Varyings {
+                let uv = vec2f(f32((i << 1u) & 2u), f32(i & 2u));
+                return Varyings(vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0), uv);
+            }
+
+            @fragment
+            fn fragment_main(in: Varyings) -> @location(0) vec4f {
+                let value = floor(in.uv.x * 16.0) / 15.0;
+                let linear = select(pow((value + 0.055) / 1.055, 2.4), value / 12.92, value <= 0.04045);
+                return vec4f(vec3f(select(value, linear, in.uv.y < 0.5)), 1.0);
+            }
             ",
-    );
-    let cell_shader_module = webgpu.device.create_shader_module(&sha);
-    web_sys::console::log_1(&cell_shader_module);
-
-    let mut gvs = GpuVertexState::new("vertex_main", &cell_shader_module);
-    gvs.buffers(&array::wrap(&layout));
-    web_sys::console::log_1(&gvs);
-    let mut gfs = GpuFragmentState::new("fragment_main", &cell_shader_module, &JsValue::UNDEFINED);
-
-    let targ = Object::new();
-    Reflect::set(
-        &targ,
-        &JsValue::from("format"),
-        &JsValue::from(webgpu.format),
-    )
-    .unwrap();
-    // gfs.targets(&array::wrap(&targ));
-    gfs.targets(&array::wrap(&targ));
-    web_sys::console::log_1(&gfs);
-
-    let mut pip = GpuRenderPipelineDescriptor::new(&JsValue::from("auto"), &gvs);
-    // pip.for
-    pip.fragment(&gfs);
-    web_sys::console::log_1(&pip);
-    let pipeline = webgpu.device.create_render_pipeline(&pip);
-    web_sys::console::log_1(&pipeline);
+        ));
+        let target = js!({"format": JsValue::from(webgpu.view_format())});
+        let fragment = GpuFragmentState::new("fragment_main", &module, &array::wrap(&target));
+        let mut descriptor =
+            GpuRenderPipelineDescriptor::new(&JsValue::from("auto"), &GpuVertexState::new("vertex_main", &module));
+        descriptor.fragment(&fragment);
+        webgpu.device.create_render_pipeline(&descriptor)
+    });
 
+    let view = webgpu.current_view();
+    let attachment = js!({"view": view, "loadOp": "load", "storeOp": "store"});
+    let encoder = webgpu.device.create_command_encoder();
+    let pass = encoder.begin_render_pass(&GpuRenderPassDescriptor::new(&array::wrap(&attachment)));
     pass.set_pipeline(&pipeline);
-    pass.set_vertex_buffer(0, &bff);
-    // pass.draw(triangles.length / 2); // 6 vertices
-    pass.draw(12 / 2); // 6 vertices
+    let x = (canvas.0 - WIDTH - 16) as f32;
+    pass.set_viewport(x, 160.0, WIDTH as f32, HEIGHT as f32, 0.0, 1.0);
+    pass.draw(3);
+    webgpu.count_draw(GpuPrimitiveTopology::TriangleList, 3);
+    pass.end();
+    webgpu.device.queue().submit(&array::wrap(&encoder.finish()));
+}
 
-    //
-    //
-    //
+/// Occlusion queries, each query records whether any sample of the draws between `begin_occlusion_query` and
+/// `end_occlusion_query` passed the depth and stencil tests.
+///
+/// Set `query_set` as the render pass `occlusionQuerySet`, record `resolve` after the pass, and read the results with
+/// `was_visible` once the commands are submitted.
+pub struct OcclusionQueries {
+    pub query_set: GpuQuerySet,
+    resolve: GpuBuffer,
+    readback: GpuBuffer,
+    count: u32,
+}
 
+impl OcclusionQueries {
+    pub fn new(webgpu: &WebGpu, count: u32) -> OcclusionQueries {
+        let query_set = webgpu
+            .device
+            .create_query_set(&GpuQuerySetDescriptor::new(count, GpuQueryType::Occlusion));
+        // each query result is a 64-bit sample count
+        let size = count as f64 * 8.0;
+        let usage = gpu_buffer_usage::QUERY_RESOLVE | gpu_buffer_usage::COPY_SRC;
+        let resolve = webgpu.device.create_buffer(&GpuBufferDescriptor::new(size, usage));
+        let usage = gpu_buffer_usage::MAP_READ | gpu_buffer_usage::COPY_DST;
+        let readback = webgpu.device.create_buffer(&GpuBufferDescriptor::new(size, usage));
+        OcclusionQueries {
+            query_set,
+            resolve,
+            readback,
+            count,
+        }
+    }
+
+    /// Record copying the query results to be read by `was_visible`.
+    pub fn resolve(&self, encoder: &GpuCommandEncoder) {
+        encoder.resolve_query_set_with_u32(&self.query_set, 0, self.count, &self.resolve, 0);
+        encoder.copy_buffer_to_buffer_with_u32_and_u32_and_u32(&self.resolve, 0, &self.readback, 0, self.count * 8);
+    }
+
+    /// Return the visibility of all queries, or `None` if the results cannot be read.
+    pub async fn results(&self) -> Option<Vec<bool>> {
+        let mapped = js::await_value(self.readback.map_async(gpu_map_mode::READ)).await;
+        mapped.ok()?;
+        let samples = js_sys::BigUint64Array::new(&self.readback.get_mapped_range());
+        let visible = (0..self.count).map(|i| samples.get_index(i) != 0).collect();
+        self.readback.unmap();
+        Some(visible)
+    }
+
+    /// Return true if any sample of query `index` passed.
+    ///
+    /// If the results cannot be read (e.g. unsupported or concurrently mapped) the query is considered visible, so
+    /// callers culling on it keep drawing.
+    #[allow(dead_code)]
+    pub async fn was_visible(&self, index: u32) -> bool {
+        self.results()
+            .await
+            .and_then(|visible| visible.get(index as usize).copied())
+            .unwrap_or(true)
+    }
+}
+
+/// Draw a large triangle in front of a small one, returning the queries of whether the small triangle passed the
+/// depth test.
+///
+/// Query 0 wraps the hidden small triangle and query 1 wraps the same triangle drawn in front of the large one. The
+/// commands are submitted, read the results with `OcclusionQueries::results`.
+pub fn draw_occlusion_demo(webgpu: &WebGpu) -> OcclusionQueries {
+    #[rustfmt::skip]
+    let vertices: [f32; 27] = [
+        -0.9, -0.9, 0.2, 0.9, -0.9, 0.2, 0.0, 0.9, 0.2, // large, in front
+        -0.1, -0.1, 0.8, 0.1, -0.1, 0.8, 0.0, 0.1, 0.8, // small, behind
+        -0.1, -0.1, 0.1, 0.1, -0.1, 0.1, 0.0, 0.1, 0.1, // small, in front
+    ];
+    let vertex_buffer = Buffer::vertex(&webgpu.device, &vertices);
+
+    let module = webgpu.device.create_shader_module(&GpuShaderModuleDescriptor::new(
+        "
+        @vertex
+        fn vertex_main(@location(0) pos: vec3f) -> @builtin(position) vec4f {
+            return vec4f(pos, 1.0);
+        }
+
+        @fragment
+        fn fragment_main() -> @location(0) vec4f {
+            return vec4f(1.0, 1.0, 1.0, 1.0);
+        }
+        ",
+    ));
+    let attribute = js!({"format": "float32x3", "offset": 0, "shaderLocation": 0});
+    let layout = GpuVertexBufferLayout::new(12.0, &array::wrap(&attribute));
+    let mut vertex = GpuVertexState::new("vertex_main", &module);
+    vertex.buffers(&array::wrap(&layout));
+    let target = js!({"format": JsValue::from(webgpu.view_format())});
+    let fragment = GpuFragmentState::new("fragment_main", &module, &array::wrap(&target));
+    let mut depth = GpuDepthStencilState::new(GpuTextureFormat::Depth24plus);
+    depth.depth_write_enabled(true).depth_compare(GpuCompareFunction::Less);
+    let pipeline_layout = PipelineLayoutBuilder::new().build(webgpu);
+    let mut descriptor = GpuRenderPipelineDescriptor::new(&pipeline_layout, &vertex);
+    descriptor.fragment(&fragment).depth_stencil(&depth);
+    let pipeline = webgpu.device.create_render_pipeline(&descriptor);
+
+    let texture = webgpu.context.get_current_texture();
+    let size = js!([texture.width(), texture.height()]);
+    let depth_texture = webgpu.device.create_texture(&GpuTextureDescriptor::new(
+        GpuTextureFormat::Depth24plus,
+        &size,
+        gpu_texture_usage::RENDER_ATTACHMENT,
+    ));
+    let view = webgpu.current_view();
+    let attachment = js!({"view": view, "loadOp": "load", "storeOp": "store"});
+    let mut depth_attachment = GpuRenderPassDepthStencilAttachment::new(&depth_texture.create_view());
+    depth_attachment
+        .depth_clear_value(1.0)
+        .depth_load_op(GpuLoadOp::Clear)
+        .depth_store_op(GpuStoreOp::Discard);
+    let queries = OcclusionQueries::new(webgpu, 2);
+    let mut pass_descriptor = GpuRenderPassDescriptor::new(&array::wrap(&attachment));
+    pass_descriptor
+        .depth_stencil_attachment(&depth_attachment)
+        .occlusion_query_set(&queries.query_set);
+
+    let encoder = webgpu.device.create_command_encoder();
+    let pass = encoder.begin_render_pass(&pass_descriptor);
+    pass.set_pipeline(&pipeline);
+    pass.set_vertex_buffer(0, &vertex_buffer.buffer);
+    pass.draw_with_instance_count_and_first_vertex(3, 1, 0);
+    for query in 0..2 {
+        pass.begin_occlusion_query(query);
+        pass.draw_with_instance_count_and_first_vertex(3, 1, 3 + query * 3);
+        pass.end_occlusion_query();
+    }
     pass.end();
-    let command_buffer = encoder.finish();
+    queries.resolve(&encoder);
+    webgpu.device.queue().submit(&array::wrap(&encoder.finish()));
+    queries
+}
 
-    // let x = Array::new();
-    // x.push(&command_buffer);
+#[allow(unused_imports)]
+mod test {
+    use crate::math::{mx, Triangle, MX, VR};
+    use crate::web::webgpu::{
+        self, align_to, mip_level_count, srgb_format, vertex_format, Buffer, Capabilities, FramePacer, FrameStats,
+        Geometry, IndexElement, Indices, SamplerDesc, WebGpuError,
+    };
+    use web_sys::{
+        GpuAddressMode, GpuFilterMode, GpuIndexFormat, GpuMipmapFilterMode, GpuPrimitiveTopology, GpuTextureFormat,
+    };
 
-    web_sys::console::log_1(&pass);
-    web_sys::console::log_1(&command_buffer);
+    #[test]
+    fn align() {
+        assert_eq!(align_to(0, 256), 0);
+        assert_eq!(align_to(16, 256), 256);
+        assert_eq!(align_to(256, 256), 256);
+        assert_eq!(align_to(257, 256), 512);
+        assert_eq!(align_to(64, 16), 64);
+    }
 
-    webgpu.device.queue().submit(&array::wrap(&command_buffer));
+    #[test]
+    fn mip_levels() {
+        assert_eq!(mip_level_count(1, 1), 1);
+        assert_eq!(mip_level_count(256, 256), 9);
+        assert_eq!(mip_level_count(256, 16), 9);
+        assert_eq!(mip_level_count(5, 3), 3);
+        assert_eq!(mip_level_count(255, 1), 8);
+        assert_eq!(mip_level_count(0, 0), 1);
+    }
+
+    #[test]
+    fn srgb() {
+        let formats = [
+            (GpuTextureFormat::Bgra8unorm, GpuTextureFormat::Bgra8unormSrgb),
+            (GpuTextureFormat::Rgba8unorm, GpuTextureFormat::Rgba8unormSrgb),
+        ];
+        for (format, srgb) in formats {
+            assert_eq!(srgb_format(format), Some(srgb));
+        }
+        assert_eq!(srgb_format(GpuTextureFormat::Rgba16float), None);
+    }
+
+    #[test]
+    fn buffer_bytes() {
+        let triangles: [Triangle<2>; 2] = [
+            (mx!(VR[-0.8, -0.8]), mx!(VR[0.8, -0.8]), mx!(VR[0.8, 0.8])),
+            (mx!(VR[-0.8, -0.8]), mx!(VR[0.8, 0.8]), mx!(VR[-0.8, 0.8])),
+        ];
+        let vertices = triangles.map(|t| [t.0, t.1, t.2]);
+        let bytes = Buffer::bytes(&vertices);
+        assert_eq!(bytes.len(), 2 * 3 * 2 * 4);
+        assert_eq!(bytes[..4], (-0.8f32).to_ne_bytes());
+        assert_eq!(bytes[bytes.len() - 4..], 0.8f32.to_ne_bytes());
+        let indices: &[u16] = &[0, 1, 2];
+        assert_eq!(Buffer::bytes(indices).len(), 6);
+        assert_eq!(align_to(Buffer::bytes(indices).len() as u32, 4), 8);
+    }
+
+    #[test]
+    fn vertex_count() {
+        let triangles: [Triangle<2>; 2] = [
+            (mx!(VR[-0.8, -0.8]), mx!(VR[0.8, -0.8]), mx!(VR[0.8, 0.8])),
+            (mx!(VR[-0.8, -0.8]), mx!(VR[0.8, 0.8]), mx!(VR[-0.8, 0.8])),
+        ];
+        assert_eq!(webgpu::vertex_count(&triangles), triangles.len() as u32 * 3);
+        assert_eq!(webgpu::vertex_count(&triangles[..1]), 3);
+        assert_eq!(webgpu::vertex_count::<2>(&[]), 0);
+    }
+
+    #[test]
+    fn geometry() {
+        // neither variant needs type annotations, the index format is chosen by the indices
+        let triangles = [(mx!(VR[0.0, 0.0]), mx!(VR[1.0, 0.0]), mx!(VR[0.0, 1.0]))];
+        assert!(matches!(Geometry::Triangles(&triangles), Geometry::Triangles(t) if t.len() == 1));
+        let vertices = [mx!(VR[0.0, 0.0]), mx!(VR[1.0, 0.0]), mx!(VR[0.0, 1.0])];
+        let indices: [u32; 3] = [0, 1, 2];
+        let geometry = Geometry::Indexed(&vertices, indices[..].into());
+        assert!(matches!(geometry, Geometry::Indexed(_, Indices::U32(i)) if i == indices));
+    }
+
+    #[test]
+    fn vertex_layout() {
+        assert_eq!(vertex_format::<1>(), ("float32", 4));
+        assert_eq!(vertex_format::<2>(), ("float32x2", 8));
+        assert_eq!(vertex_format::<3>(), ("float32x3", 12));
+        assert_eq!(vertex_format::<4>(), ("float32x4", 16));
+        assert_eq!(vertex_format::<3>().1 as usize, std::mem::size_of::<VR<f32, 3>>());
+        let triangles: [Triangle<3>; 1] = [(mx!(VR[0.0, 0.0, 0.0]), mx!(VR[1.0, 0.0, 0.0]), mx!(VR[0.0, 1.0, 0.5]))];
+        assert_eq!(Buffer::bytes(&triangles.map(|t| [t.0, t.1, t.2])).len(), 3 * 12);
+        assert_eq!(webgpu::vertex_count(&triangles), 3);
+    }
+
+    #[test]
+    fn index_bytes() {
+        // a quad as 4 vertices shared by 2 triangles
+        let short: &[u16] = &[0, 1, 2, 0, 2, 3];
+        let long: &[u32] = &[0, 1, 2, 0, 2, 3];
+        assert_eq!(Buffer::bytes(short).len(), 12);
+        assert_eq!(Buffer::bytes(long).len(), 24);
+        assert_eq!(u16::FORMAT, GpuIndexFormat::Uint16);
+        assert_eq!(u32::FORMAT, GpuIndexFormat::Uint32);
+    }
+
+    #[test]
+    fn matrix_bytes() {
+        let m = (0..16).map(|i| i as f32).collect::<MX<f32, 4, 4>>();
+        let transpose = m.transpose();
+        let bytes = Buffer::bytes(&transpose);
+        assert_eq!(bytes.len(), 64);
+        // the second column-major element is the first element of the second row
+        assert_eq!(bytes[4..8], m[4].to_ne_bytes());
+    }
+
+    #[test]
+    fn error_display() {
+        assert_eq!(WebGpuError::NoCanvas.to_string(), "no canvas to render to");
+        assert!(WebGpuError::NoWebGpuContext.to_string().contains("webgpu context"));
+    }
+
+    #[test]
+    fn capabilities() {
+        let capabilities = Capabilities {
+            supports_timestamp_query: false,
+            supports_float32_filterable: false,
+            max_texture_dimension_2d: 8192,
+            max_buffer_size: 268435456.0,
+        };
+        assert!(capabilities.fits_texture(8192, 4096));
+        assert!(!capabilities.fits_texture(1024, 16384));
+    }
+
+    #[test]
+    fn frame_stats() {
+        let mut stats = FrameStats::default();
+        stats.record(GpuPrimitiveTopology::TriangleList, 6);
+        stats.record(GpuPrimitiveTopology::TriangleStrip, 4);
+        stats.record(GpuPrimitiveTopology::LineList, 2);
+        stats.record(GpuPrimitiveTopology::PointList, 5);
+        let expected = FrameStats {
+            vertices: 17,
+            triangles: 4,
+            draw_calls: 4,
+        };
+        assert_eq!(stats, expected);
+        stats.record(GpuPrimitiveTopology::TriangleStrip, 1);
+        assert_eq!(stats.triangles, 4);
+    }
+
+    #[test]
+    fn sampler_desc() {
+        let nearest = SamplerDesc::new();
+        assert_eq!(nearest.mag_filter, GpuFilterMode::Nearest);
+        assert_eq!(nearest.address_modes, [GpuAddressMode::ClampToEdge; 3]);
+        let linear = SamplerDesc::new().filter(GpuFilterMode::Linear).max_anisotropy(64);
+        assert_eq!(linear.mipmap_filter, GpuMipmapFilterMode::Linear);
+        assert_eq!(linear.anisotropy(), 16);
+        // anisotropy needs all filters linear
+        assert_eq!(linear.mag_filter(GpuFilterMode::Nearest).anisotropy(), 1);
+        assert_eq!(SamplerDesc::new().max_anisotropy(0).anisotropy(), 1);
+        let (repeat, mirror) = (GpuAddressMode::Repeat, GpuAddressMode::MirrorRepeat);
+        let tiled = SamplerDesc::new().address_mode(repeat).address_mode_v(mirror);
+        assert_eq!(tiled.address_modes, [repeat, mirror, repeat]);
+    }
+
+    #[test]
+    fn frame_pacer() {
+        let rendered = |max_fps, refresh: f64, frames: u32| {
+            let mut pacer = FramePacer::new(max_fps);
+            (0..frames)
+                .filter(|&i| pacer.ready(i as f64 * 1000.0 / refresh))
+                .count()
+        };
+        assert_eq!(rendered(None, 144.0, 144), 144);
+        assert_eq!(rendered(Some(60.0), 60.0, 60), 60);
+        assert_eq!(rendered(Some(30.0), 60.0, 60), 30);
+        assert_eq!(rendered(Some(30.0), 144.0, 144), 30);
+        // 45 is not a divisor of 60, frames alternate between 1 and 2 refresh intervals
+        assert!((44..=46).contains(&rendered(Some(45.0), 60.0, 60)));
+        // a long pause does not cause a burst of frames
+        let mut pacer = FramePacer::new(Some(30.0));
+        assert!(pacer.ready(0.0) && pacer.ready(5000.0));
+        assert!(!pacer.ready(5016.0) && pacer.ready(5033.0));
+    }
 }
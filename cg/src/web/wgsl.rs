@@ -0,0 +1,268 @@
+//! WGSL source utilities.
+#![allow(unused)]
+use std::fmt;
+
+/// Parameter directive prefix, see `params`.
+const PARAM: &str = "//@ param";
+
+/// Include directive prefix, the name of the included source follows in quotes: `//!include "name"`.
+const INCLUDE: &str = "//!include";
+
+/// Preprocessor errors, `file` is the source containing the failing directive (`None` for the main source) and
+/// `line` is the 1-based line of the directive.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreprocessError {
+    /// The directive is not followed by a quoted name.
+    Malformed { file: Option<String>, line: usize },
+    /// `resolve` has no source for `name`.
+    NotFound {
+        name: String,
+        file: Option<String>,
+        line: usize,
+    },
+    /// `name` is already being included, directly or indirectly including itself.
+    Cycle {
+        name: String,
+        file: Option<String>,
+        line: usize,
+    },
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = |file: &Option<String>, line: &usize| format!("{}:{}", file.as_deref().unwrap_or("main"), line);
+        match self {
+            PreprocessError::Malformed { file, line } => write!(f, "{}: malformed include directive", at(file, line)),
+            PreprocessError::NotFound { name, file, line } => {
+                write!(f, "{}: include \"{name}\" not found", at(file, line))
+            }
+            PreprocessError::Cycle { name, file, line } => {
+                write!(f, "{}: include \"{name}\" is circular", at(file, line))
+            }
+        }
+    }
+}
+
+/// Expand `//!include "name"` directives in `src`, included sources are provided by `resolve`.
+///
+/// Directives must be on their own line, the whole line is replaced by the included source, which is also expanded.
+/// Including the same source multiple times is allowed, as long as it does not include itself.
+pub fn preprocess(src: &str, resolve: impl Fn(&str) -> Option<String>) -> Result<String, PreprocessError> {
+    let mut stack = Vec::new();
+    expand(src, None, &resolve, &mut stack)
+}
+
+fn expand(
+    src: &str,
+    file: Option<&str>,
+    resolve: &impl Fn(&str) -> Option<String>,
+    stack: &mut Vec<String>,
+) -> Result<String, PreprocessError> {
+    let mut res = String::with_capacity(src.len());
+    for (i, text) in src.lines().enumerate() {
+        let Some(directive) = text.trim().strip_prefix(INCLUDE) else {
+            res.push_str(text);
+            res.push('\n');
+            continue;
+        };
+        let (file, line) = (file.map(String::from), i + 1);
+        let name = directive
+            .trim()
+            .strip_prefix('"')
+            .and_then(|name| name.strip_suffix('"'))
+            .filter(|name| !name.is_empty() && !name.contains('"'))
+            .ok_or(PreprocessError::Malformed {
+                file: file.clone(),
+                line,
+            })?;
+        if stack.iter().any(|included| included == name) {
+            return Err(PreprocessError::Cycle {
+                name: name.into(),
+                file,
+                line,
+            });
+        }
+        let included = resolve(name).ok_or(PreprocessError::NotFound {
+            name: name.into(),
+            file,
+            line,
+        })?;
+        stack.push(name.into());
+        res.push_str(&expand(&included, Some(name), resolve, stack)?);
+        stack.pop();
+    }
+    Ok(res)
+}
+
+/// Shader parameter type, `F32` maps to `f32` and `Color` to a linear `vec3<f32>` color.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamKind {
+    F32 { min: f32, max: f32, default: f32 },
+    Color,
+}
+
+/// Shader parameter, `offset` is the byte offset of the field in the parameters uniform struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub kind: ParamKind,
+    pub offset: usize,
+}
+
+/// Parse the `//@ param name min max [default]` and `//@ param name color` directives in `src`.
+///
+/// Parameters are the fields of a uniform struct in declaration order, the source declares the matching struct at
+/// `@group(0) @binding(0)`. The default of `F32` parameters is `min` if missing. Returns `None` if any directive is
+/// malformed or a name is repeated.
+pub fn params(src: &str) -> Option<Vec<Param>> {
+    let mut params = Vec::<Param>::new();
+    let mut offset = 0usize;
+    for directive in src.lines().filter_map(|text| text.trim().strip_prefix(PARAM)) {
+        let args = directive.split_whitespace().collect::<Vec<_>>();
+        let (&name, args) = args.split_first()?;
+        if params.iter().any(|param| param.name == name) {
+            return None;
+        }
+        let (kind, size, align) = match args {
+            ["color"] => (ParamKind::Color, 12, 16),
+            [min, max] | [min, max, _] => {
+                let (min, max) = (min.parse::<f32>().ok()?, max.parse::<f32>().ok()?);
+                let default = args.get(2).map_or(Some(min), |default| default.parse().ok())?;
+                if !(min < max && (min..=max).contains(&default)) {
+                    return None;
+                }
+                (ParamKind::F32 { min, max, default }, 4, 4)
+            }
+            _ => return None,
+        };
+        offset = offset.next_multiple_of(align);
+        params.push(Param {
+            name: name.into(),
+            kind,
+            offset,
+        });
+        offset += size;
+    }
+    Some(params)
+}
+
+/// Size in bytes of the uniform struct of `params`, rounded to 16 bytes as required by uniform buffers.
+pub fn params_size(params: &[Param]) -> usize {
+    let end = params.last().map_or(0, |param| match param.kind {
+        ParamKind::F32 { .. } => param.offset + 4,
+        ParamKind::Color => param.offset + 12,
+    });
+    end.next_multiple_of(16)
+}
+
+/// Return the uniform struct of `params` with the default values, colors are white.
+pub fn params_defaults(params: &[Param]) -> Vec<f32> {
+    let mut values = vec![0.0; params_size(params) / 4];
+    for param in params {
+        match param.kind {
+            ParamKind::F32 { default, .. } => values[param.offset / 4] = default,
+            ParamKind::Color => values[param.offset / 4..param.offset / 4 + 3].fill(1.0),
+        }
+    }
+    values
+}
+
+mod test {
+    use crate::web::wgsl::{params, params_defaults, params_size, preprocess, ParamKind, PreprocessError};
+
+    fn resolve(name: &str) -> Option<String> {
+        match name {
+            "light" => Some("//!include \"consts\"\nfn light() -> f32 { return PI; }".into()),
+            "consts" => Some("const PI = 3.14;".into()),
+            "a" => Some("//!include \"b\"".into()),
+            "b" => Some("\n//!include \"a\"".into()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn include() {
+        let src = preprocess("//!include \"light\"\n  //!include \"consts\"\nfn main() {}", resolve).unwrap();
+        assert_eq!(
+            src,
+            "const PI = 3.14;\nfn light() -> f32 { return PI; }\nconst PI = 3.14;\nfn main() {}\n"
+        );
+        assert_eq!(preprocess("fn main() {}", resolve).unwrap(), "fn main() {}\n");
+    }
+
+    #[test]
+    fn errors() {
+        let err = preprocess("\n//!include \"a\"", resolve).unwrap_err();
+        assert_eq!(
+            err,
+            PreprocessError::Cycle {
+                name: "a".into(),
+                file: Some("b".into()),
+                line: 2
+            }
+        );
+        assert_eq!(err.to_string(), "b:2: include \"a\" is circular");
+        let err = preprocess("//!include \"missing\"", resolve).unwrap_err();
+        assert_eq!(
+            err,
+            PreprocessError::NotFound {
+                name: "missing".into(),
+                file: None,
+                line: 1
+            }
+        );
+        assert_eq!(err.to_string(), "main:1: include \"missing\" not found");
+        for src in [
+            "//!include",
+            "//!include light",
+            "//!include \"\"",
+            "//!include \"light",
+        ] {
+            assert_eq!(
+                preprocess(src, resolve).unwrap_err(),
+                PreprocessError::Malformed { file: None, line: 1 }
+            );
+        }
+    }
+
+    #[test]
+    fn param_layout() {
+        let src = "//@ param speed 0 2 1\n  //@ param tint color\nstruct Params { speed: f32, tint: vec3f }\n//@ param gain -1 1";
+        let parsed = params(src).unwrap();
+        let kinds = parsed
+            .iter()
+            .map(|param| (param.name.as_str(), param.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, [("speed", 0), ("tint", 16), ("gain", 28)]);
+        assert_eq!(
+            parsed[0].kind,
+            ParamKind::F32 {
+                min: 0.0,
+                max: 2.0,
+                default: 1.0
+            }
+        );
+        assert_eq!(parsed[1].kind, ParamKind::Color);
+        assert_eq!(params_size(&parsed), 32);
+        assert_eq!(params_defaults(&parsed), [1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, -1.0]);
+        assert_eq!(params("fn main() {}"), Some(vec![]));
+        assert_eq!(params_size(&[]), 0);
+    }
+
+    #[test]
+    fn param_errors() {
+        for src in [
+            "//@ param",
+            "//@ param speed",
+            "//@ param speed 0",
+            "//@ param speed a 1",
+            "//@ param speed 1 0",
+            "//@ param speed 0 1 2",
+            "//@ param speed 0 1 0.5 0",
+            "//@ param tint colour",
+            "//@ param speed 0 1\n//@ param speed color",
+        ] {
+            assert_eq!(params(src), None, "{src}");
+        }
+    }
+}